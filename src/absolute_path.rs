@@ -7,10 +7,6 @@ use std::{
 pub trait AbsolutePathExt {
     /// Converts a relative path to an absolute path.
     fn as_absolute(&self) -> std::result::Result<PathBuf, std::io::Error>;
-
-    /// Converts a relative path to an absolute path and checks if it is sandboxed within a given directory.
-    fn as_sandboxed_absolute(&self, sandbox: &Path)
-        -> std::result::Result<PathBuf, std::io::Error>;
 }
 
 impl AbsolutePathExt for PathBuf {
@@ -22,40 +18,12 @@ impl AbsolutePathExt for PathBuf {
         let cwd = env::current_dir()?;
         Ok(canonicalize_path(&cwd.join(self)))
     }
-
-    fn as_sandboxed_absolute(
-        &self,
-        sandbox: &Path,
-    ) -> std::result::Result<PathBuf, std::io::Error> {
-        let sandbox = canonicalize_path(sandbox);
-
-        let absolute_path = if self.is_relative() {
-            canonicalize_path(&sandbox.join(self))
-        } else {
-            canonicalize_path(self)
-        };
-
-        if !absolute_path.starts_with(sandbox) {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Path cannot escape the sandbox",
-            ));
-        }
-        Ok(absolute_path)
-    }
 }
 
 impl AbsolutePathExt for std::path::Path {
     fn as_absolute(&self) -> std::result::Result<PathBuf, std::io::Error> {
         PathBuf::from(self).as_absolute()
     }
-
-    fn as_sandboxed_absolute(
-        &self,
-        sandbox: &Path,
-    ) -> std::result::Result<PathBuf, std::io::Error> {
-        PathBuf::from(self).as_sandboxed_absolute(sandbox)
-    }
 }
 
 /// Canonicalizes a path without requiring the files and folders to exist.
@@ -197,133 +165,4 @@ mod tests {
         assert!(absolute_path.starts_with(&cwd));
         assert!(absolute_path.ends_with("src/main.rs"));
     }
-
-    #[test]
-    fn test_path_within_sandbox() {
-        #[cfg(target_family = "unix")]
-        let sandbox = PathBuf::from("/sandbox");
-        #[cfg(target_family = "windows")]
-        let sandbox = PathBuf::from("D:\\sandbox");
-        let relative_path = PathBuf::from("file.txt");
-        let absolute_path = relative_path.as_sandboxed_absolute(&sandbox).unwrap();
-        assert!(absolute_path.is_absolute());
-        assert!(absolute_path.starts_with(&sandbox));
-    }
-
-    #[test]
-    #[should_panic(expected = "Path cannot escape the sandbox")]
-    fn test_path_outside_sandbox() {
-        #[cfg(target_family = "unix")]
-        let sandbox = PathBuf::from("/sandbox");
-        #[cfg(target_family = "windows")]
-        let sandbox = PathBuf::from("D:\\sandbox");
-        let outside_path = PathBuf::from("/outside/file.txt");
-        outside_path.as_sandboxed_absolute(&sandbox).unwrap();
-    }
-
-    #[test]
-    #[should_panic(expected = "Path cannot escape the sandbox")]
-    fn test_path_with_dot_dot_to_escape_sandbox() {
-        #[cfg(target_family = "unix")]
-        let sandbox = PathBuf::from("/sandbox");
-        #[cfg(target_family = "windows")]
-        let sandbox = PathBuf::from("D:\\sandbox");
-        let relative_path = PathBuf::from("../file.txt");
-        relative_path.as_sandboxed_absolute(&sandbox).unwrap();
-    }
-
-    #[test]
-    fn test_empty_path_in_sandbox() {
-        #[cfg(target_family = "unix")]
-        let sandbox = PathBuf::from("/sandbox");
-        #[cfg(target_family = "windows")]
-        let sandbox = PathBuf::from("D:\\sandbox");
-        let empty_path = PathBuf::new();
-        let absolute_path = empty_path.as_sandboxed_absolute(&sandbox).unwrap();
-        assert_eq!(absolute_path, sandbox);
-    }
-
-    #[test]
-    fn test_root_path_in_sandbox() {
-        let sandbox = PathBuf::from("/sandbox");
-        #[cfg(unix)]
-        let root_path = PathBuf::from("/");
-        #[cfg(windows)]
-        let root_path = PathBuf::from("D:\\");
-
-        let result = root_path.as_sandboxed_absolute(&sandbox);
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_multiple_consecutive_slashes_in_sandbox() {
-        #[cfg(target_family = "unix")]
-        let sandbox = PathBuf::from("/sandbox");
-        #[cfg(target_family = "unix")]
-        let multiple_slashes_path = PathBuf::from("src//main.rs");
-
-        #[cfg(target_family = "windows")]
-        let sandbox = PathBuf::from("D:\\sandbox");
-        #[cfg(target_family = "windows")]
-        let multiple_slashes_path = PathBuf::from("src\\\\main.rs");
-
-        let absolute_path = multiple_slashes_path
-            .as_sandboxed_absolute(&sandbox)
-            .unwrap();
-        assert!(absolute_path.is_absolute());
-        assert!(absolute_path.starts_with(&sandbox));
-        #[cfg(target_family = "unix")]
-        assert!(absolute_path.ends_with("src/main.rs"));
-        #[cfg(target_family = "windows")]
-        assert!(absolute_path.ends_with("src\\main.rs"));
-    }
-
-    #[test]
-    #[should_panic(expected = "Path cannot escape the sandbox")]
-    fn test_path_with_multiple_dot_dot_to_escape_sandbox() {
-        let sandbox = PathBuf::from("/sandbox");
-        let relative_path = PathBuf::from("///../../../....//");
-        relative_path.as_sandboxed_absolute(&sandbox).unwrap();
-    }
-
-    #[test]
-    #[should_panic(expected = "Path cannot escape the sandbox")]
-    fn test_path_with_nested_dot_dot_to_escape_sandbox() {
-        let sandbox = PathBuf::from("/sandbox");
-        let relative_path = PathBuf::from("nested/../../../../file.txt");
-        relative_path.as_sandboxed_absolute(&sandbox).unwrap();
-    }
-
-    #[test]
-    #[should_panic(expected = "Path cannot escape the sandbox")]
-    fn test_path_with_mixed_dots_and_slashes() {
-        let sandbox = PathBuf::from("/sandbox");
-        let relative_path = PathBuf::from("./././.././../file.txt");
-        relative_path.as_sandboxed_absolute(&sandbox).unwrap();
-    }
-
-    #[test]
-    fn test_path_with_trailing_slashes() {
-        #[cfg(target_family = "unix")]
-        let sandbox = PathBuf::from("/sandbox");
-        #[cfg(target_family = "unix")]
-        let relative_path = PathBuf::from("file.txt///");
-
-        #[cfg(target_family = "windows")]
-        let sandbox = PathBuf::from("D:\\sandbox");
-        #[cfg(target_family = "windows")]
-        let relative_path = PathBuf::from("file.txt\\\\\\");
-
-        let absolute_path = relative_path.as_sandboxed_absolute(&sandbox).unwrap();
-        assert!(absolute_path.is_absolute());
-        assert!(absolute_path.starts_with(&sandbox));
-    }
-
-    #[test]
-    #[should_panic(expected = "Path cannot escape the sandbox")]
-    fn test_path_with_leading_slashes() {
-        let sandbox = PathBuf::from("/sandbox");
-        let some_malicious_path = PathBuf::from("///file.txt");
-        some_malicious_path.as_sandboxed_absolute(&sandbox).unwrap();
-    }
 }
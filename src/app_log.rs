@@ -1,5 +1,4 @@
 // This file is part of the `rusty-logger` project.
-use env_logger;
 use env_logger::fmt::Formatter;
 use log::Record;
 use std::io::Write;
@@ -1,8 +1,10 @@
 use std::path::PathBuf;
 
-use clap::{Args, Parser};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::env;
 
+use crate::template_config::Difficulty;
+
 /// Styles from <https://github.com/rust-lang/cargo/blob/master/src/cargo/util/style.rs>
 mod style {
     use anstyle::*;
@@ -33,6 +35,7 @@ mod heading {
     pub const GIT_PARAMETERS: &str = "Git Parameters";
     pub const TEMPLATE_SELECTION: &str = "Template Selection";
     pub const OUTPUT_PARAMETERS: &str = "Output Parameters";
+    pub const CLOCK_PARAMETERS: &str = "Clock Parameters";
 }
 
 #[derive(Parser)]
@@ -47,11 +50,27 @@ mod heading {
 )]
 pub enum Cli {
     #[command(name = "stm32bs", visible_alias = "stbs")]
-    ParseArgs(AppArgs),
+    ParseArgs(Stm32bsArgs),
 }
 
-#[derive(Clone, Debug, Args)]
-#[command(arg_required_else_help(false), version, about)]
+#[derive(Clone, Debug, Default, Args)]
+#[command(
+    arg_required_else_help(false),
+    args_conflicts_with_subcommands(true),
+    version,
+    about
+)]
+pub struct Stm32bsArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Without a subcommand a new project is generated, as with `new`
+    #[command(flatten)]
+    pub new: AppArgs,
+}
+
+/// Arguments of `new`, which generates a project from a template
+#[derive(Clone, Debug, Default, Args)]
 pub struct AppArgs {
     #[command(flatten)]
     pub template_path: TemplatePath,
@@ -69,12 +88,13 @@ pub struct AppArgs {
     pub verbose: bool,
 
     /// Pass template values through a file. Values should be in the format `key=value`, one per
-    /// line
+    /// line, or in a `[values]` table of a TOML file. Values of `--define` take precedence
     #[arg(long="values-file", value_parser, alias="template-values-file", value_name="FILE", help_heading = heading::OUTPUT_PARAMETERS)]
     pub template_values_file: Option<String>,
 
-    /// If silent mode is set all variables will be extracted from the template_values_file. If a
-    /// value is missing the project generation will fail
+    /// If silent mode is set nothing is prompted, all variables are taken from --define, the
+    /// values file or the defaults of the template. If values are missing the project generation
+    /// fails and lists all of them
     #[arg(long, short, requires("name"), action)]
     pub silent: bool,
 
@@ -105,6 +125,21 @@ pub struct AppArgs {
     #[arg(long = "demo", conflicts_with = "project_type", help_heading = heading::OUTPUT_PARAMETERS)]
     pub demo_name: Option<String>,
 
+    #[command(flatten)]
+    pub clock: ClockArgs,
+
+    /// Generate a cargo workspace with one crate per core, for the multicore parts
+    #[arg(long, action, help_heading = heading::OUTPUT_PARAMETERS)]
+    pub multicore: bool,
+
+    /// Use a part which is NRND, obsolete or in preview without a confirmation
+    #[arg(long, action, help_heading = heading::OUTPUT_PARAMETERS)]
+    pub allow_nrnd: bool,
+
+    /// Use the soft-float target, even if the core has an FPU
+    #[arg(long, action, help_heading = heading::OUTPUT_PARAMETERS)]
+    pub soft_float: bool,
+
     // /// Allows running system commands without being prompted. Warning: Setting this flag will
     // /// enable the template to run arbitrary system commands without user confirmation. Use at your
     // /// own risk and be sure to review the template code beforehand.
@@ -117,30 +152,220 @@ pub struct AppArgs {
     /// Skip downloading git submodules (if there are any)
     #[arg(long, action, help_heading = heading::GIT_PARAMETERS)]
     pub skip_submodules: bool,
+
+    /// Use the cached template without any network access, fails if it is not cached
+    #[arg(long, action, conflicts_with = "refresh", help_heading = heading::GIT_PARAMETERS)]
+    pub offline: bool,
+
+    /// Fetch the template and replace the cached one, fails instead of using the cached
+    /// template if the fetch fails
+    #[arg(long, action, help_heading = heading::GIT_PARAMETERS)]
+    pub refresh: bool,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Generate a new project, this is also done without a subcommand
+    New(Box<AppArgs>),
+    /// Update the generated files of an existing project from its `.stm32bs.toml`
+    Update(ProjectArgs),
+    /// Add the BSP to an existing project, or generate it again
+    Bsp(ProjectArgs),
+    /// Check the `.stm32bs.toml` of an existing project, nothing is written
+    Check(ProjectArgs),
+    /// Search and list the supported parts
+    Chips(ChipsArgs),
+    /// Show the information of a part, as used to generate a project
+    Info(InfoArgs),
+    /// List the demos of the template
+    Demos(DemosArgs),
+    /// Build or refresh the database of a template from ST's open pin data
+    Import(ImportArgs),
+    /// Solve the clock tree of a part: PLL dividers, flash wait states and voltage scale
+    Clock(ClockTreeArgs),
+}
+
+/// Clock requirements of the project, the clock tree is solved from them
+#[derive(Clone, Debug, Default, Args)]
+pub struct ClockArgs {
+    /// Frequency of the HSE crystal, e.g. `8MHz`, the PLL runs from HSI if omitted
+    #[arg(long, value_name = "FREQ", value_parser = parse_frequency, help_heading = heading::CLOCK_PARAMETERS)]
+    pub hse: Option<u32>,
+
+    /// Wanted SYSCLK, e.g. `168MHz`, the maximum frequency of the part if omitted
+    #[arg(long, value_name = "FREQ", value_parser = parse_frequency, help_heading = heading::CLOCK_PARAMETERS)]
+    pub sysclk: Option<u32>,
+
+    /// Wanted frequency of the PLL Q output, e.g. `48MHz` for USB, SDIO and RNG
+    #[arg(long = "pll-q", value_name = "FREQ", value_parser = parse_frequency, help_heading = heading::CLOCK_PARAMETERS)]
+    pub pll_q: Option<u32>,
 }
 
-impl Default for AppArgs {
-    fn default() -> Self {
-        Self {
-            template_path: TemplatePath::default(),
-            name: None,
-            chip_pn: None,
-            verbose: false,
-            template_values_file: None,
-            silent: false,
-            ssh_identity: None,
-            gitconfig: None,
-            define: Vec::default(),
-            destination: None,
-            project_type: None,
-            demo_name: None,
-            //            allow_commands: false,
-            overwrite: false,
-            skip_submodules: false,
+/// Read a frequency in Hz, `8000000`, `8MHz`, `8M` and `32.768kHz` are accepted
+fn parse_frequency(value: &str) -> Result<u32, String> {
+    let lower = value.trim().to_lowercase();
+    let lower = lower.strip_suffix("hz").unwrap_or(&lower);
+    let (number, factor) = match lower.chars().last() {
+        Some('m') => (&lower[..lower.len() - 1], 1_000_000.0),
+        Some('k') => (&lower[..lower.len() - 1], 1_000.0),
+        _ => (lower, 1.0),
+    };
+    match number.trim().parse::<f64>() {
+        Ok(number) if number > 0.0 && number * factor <= u32::MAX as f64 => {
+            Ok((number * factor).round() as u32)
         }
+        _ => Err(format!("`{value}` is not a frequency, e.g. 8MHz")),
     }
 }
 
+/// Template which provides the database and the demos of the subcommands
+#[derive(Clone, Debug, Default, Args)]
+pub struct TemplateSource {
+    /// Git repository of the template, the default template is used if omitted
+    #[arg(short, long, conflicts_with = "path", help_heading = heading::TEMPLATE_SELECTION)]
+    pub git: Option<String>,
+
+    /// Local path of the template
+    #[arg(short, long, help_heading = heading::TEMPLATE_SELECTION)]
+    pub path: Option<String>,
+
+    /// Use the cached template without any network access, fails if it is not cached
+    #[arg(long, action, conflicts_with = "refresh", help_heading = heading::GIT_PARAMETERS)]
+    pub offline: bool,
+
+    /// Fetch the template and replace the cached one, fails instead of using the cached
+    /// template if the fetch fails
+    #[arg(long, action, help_heading = heading::GIT_PARAMETERS)]
+    pub refresh: bool,
+}
+
+/// Arguments of the subcommands working on an existing project
+#[derive(Clone, Debug, Default, Args)]
+pub struct ProjectArgs {
+    /// Directory of the project, by default `.stm32bs.toml` is searched from the current
+    /// directory upwards
+    #[arg(long = "project-dir", value_name = "DIR")]
+    pub project_dir: Option<PathBuf>,
+
+    /// Enables more verbose output.
+    #[arg(long, short, action)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub template: TemplateSource,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub struct DemosArgs {
+    /// Text searched in the names and descriptions of the demos, e.g. `uart`
+    #[arg()]
+    pub pattern: Option<String>,
+
+    /// Only list demos with the tag, can be repeated to require several tags
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Only list demos of this difficulty
+    #[arg(long, value_enum)]
+    pub difficulty: Option<Difficulty>,
+
+    /// Only list demos the part can run, e.g. `STM32G071CBT6`
+    #[arg(long, value_name = "PN")]
+    pub chip: Option<String>,
+
+    #[command(flatten)]
+    pub template: TemplateSource,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub struct ImportArgs {
+    /// Local copy of https://github.com/STMicroelectronics/STM32_open_pin_data,
+    /// or its `mcu` directory
+    #[arg(value_name = "DIR")]
+    pub source: PathBuf,
+
+    /// Database to write, it is created if it does not exist
+    #[arg(long, value_name = "FILE", default_value = "stm32bs.db")]
+    pub database: PathBuf,
+
+    /// Only import the family, e.g. `STM32G0`, can be given several times
+    #[arg(long)]
+    pub family: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub struct ClockTreeArgs {
+    /// Part number, e.g. `STM32F407VGT6`
+    #[arg()]
+    pub chip_pn: String,
+
+    #[command(flatten)]
+    pub clock: ClockArgs,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub template: TemplateSource,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Toml,
+    Json,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub struct ChipsArgs {
+    /// Part number or a part of it, e.g. `G071`
+    #[arg()]
+    pub pattern: Option<String>,
+
+    /// Only list parts of the family, e.g. `STM32G0` or `G0`
+    #[arg(long)]
+    pub family: Option<String>,
+
+    /// Only list parts with the core, e.g. `M0+`, `M4` or `Cortex-M33`
+    #[arg(long)]
+    pub core: Option<String>,
+
+    /// Only list parts with at least this flash size in KB
+    #[arg(long = "min-flash", value_name = "KB")]
+    pub min_flash: Option<u32>,
+
+    /// Only list parts with at least this RAM size in KB
+    #[arg(long = "min-ram", value_name = "KB")]
+    pub min_ram: Option<u32>,
+
+    /// Only list parts in the package, e.g. `LQFP` or `LQFP48`
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Print the list as JSON
+    #[arg(long, action)]
+    pub json: bool,
+
+    #[command(flatten)]
+    pub template: TemplateSource,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub struct InfoArgs {
+    /// Part number, e.g. `STM32G071CBT6`
+    #[arg()]
+    pub chip_pn: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub template: TemplateSource,
+}
+
 #[derive(Default, Debug, Clone, Args)]
 pub struct TemplatePath {
     /// Auto attempt to use as `--git` or --path. If it is specified explicitly,
@@ -188,16 +413,7 @@ impl TemplatePath {
 
     /// Check exist an auto path or git path or local path
     pub fn have_any_path(&self) -> bool {
-        if self.auto_path != None {
-            return true;
-        }
-        if self.git != None {
-            return true;
-        }
-        if self.path != None {
-            return true;
-        }
-        false
+        self.auto_path.is_some() || self.git.is_some() || self.path.is_some()
     }
 
     pub const fn git(&self) -> Option<&(impl AsRef<str> + '_)> {
@@ -227,7 +443,7 @@ impl TemplatePath {
 
 /// To get the arguments list from terminal
 /// Return : work arguments
-pub fn resolve_args() -> AppArgs {
+pub fn resolve_args() -> Stm32bsArgs {
     let args = env::args();
     let Cli::ParseArgs(args) = Cli::parse_from(args);
     args
@@ -242,4 +458,14 @@ mod cli_tests {
         use clap::CommandFactory;
         Cli::command().debug_assert()
     }
+
+    #[test]
+    fn it_parses_frequencies() {
+        assert_eq!(parse_frequency("8000000"), Ok(8_000_000));
+        assert_eq!(parse_frequency("8MHz"), Ok(8_000_000));
+        assert_eq!(parse_frequency("8m"), Ok(8_000_000));
+        assert_eq!(parse_frequency("32.768kHz"), Ok(32_768));
+        assert!(parse_frequency("fast").is_err());
+        assert!(parse_frequency("-8MHz").is_err());
+    }
 }
@@ -0,0 +1,90 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// A crate requested by the `[driver]` or `[middleware]` table of `.stm32bs.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub spec: toml::Value,
+}
+
+impl Dependency {
+    /// Name of the crate when it is used in rust code
+    pub fn crate_ident(&self) -> String {
+        self.name.replace('-', "_")
+    }
+}
+
+/// Read a `[driver]` or `[middleware]` table, entries look like cargo dependencies:
+/// `ssd1306 = "0.9"` or `defmt = { version = "1.0", features = ["alloc"] }`
+pub fn parse_dependencies(
+    table_name: &str,
+    table: &Option<HashMap<String, toml::Value>>,
+) -> Result<Vec<Dependency>> {
+    let mut dependencies = Vec::new();
+    if let Some(table) = table {
+        for (name, spec) in table.iter() {
+            match spec {
+                toml::Value::String(_) | toml::Value::Table(_) => {}
+                _ => bail!(
+                    "`{}` in [{}] should be a version string or a dependency table",
+                    name,
+                    table_name
+                ),
+            }
+            dependencies.push(Dependency {
+                name: name.to_string(),
+                spec: spec.clone(),
+            });
+        }
+    }
+    dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(dependencies)
+}
+
+/// Append the dependencies which are not declared yet to `Cargo.toml`.
+/// The file is edited as text, so the formatting and comments of the user are kept.
+pub fn add_missing_dependencies(cargo_toml: &Path, dependencies: &[Dependency]) -> Result<()> {
+    let content = fs::read_to_string(cargo_toml)
+        .with_context(|| format!("Cannot read {}", cargo_toml.display()))?;
+    let manifest: toml::Table = toml::from_str(&content)
+        .with_context(|| format!("Cannot parse {}", cargo_toml.display()))?;
+    let declared = manifest.get("dependencies").and_then(|d| d.as_table());
+
+    let missing: Vec<String> = dependencies
+        .iter()
+        .filter(|d| !declared.is_some_and(|t| t.contains_key(&d.name)))
+        .map(|d| format!("{} = {}", d.name, d.spec))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines: Vec<&str> = content.lines().collect();
+    let header = lines.iter().position(|l| l.trim() == "[dependencies]");
+    let new_content = match header {
+        Some(header) => {
+            // insert at the end of the section, before the blank lines leading to the next one
+            let mut end = lines[header + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map_or(lines.len(), |p| p + header + 1);
+            while end > header + 1 && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            for (i, line) in missing.iter().enumerate() {
+                lines.insert(end + i, line);
+            }
+            lines.join("\n") + "\n"
+        }
+        None => format!(
+            "{}\n[dependencies]\n{}\n",
+            content.trim_end(),
+            missing.join("\n")
+        ),
+    };
+    fs::write(cargo_toml, new_content)?;
+    Ok(())
+}
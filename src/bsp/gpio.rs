@@ -0,0 +1,223 @@
+use anyhow::{bail, Result};
+use std::fmt::Write;
+
+use crate::bsp::pinmap::{PinConfig, PinMode, Pull, Speed};
+
+/// Register and field layout used to enable the clock of a GPIO port
+struct GpioClock {
+    register: &'static str,
+    field_prefix: &'static str,
+}
+
+fn gpio_clock(family: &str) -> Result<GpioClock> {
+    let (register, field_prefix) = match family {
+        "STM32C0" | "STM32G0" | "STM32U0" => ("iopenr", "gpio"),
+        "STM32L0" => ("iopenr", "iop"),
+        "STM32F0" | "STM32F3" => ("ahbenr", "iop"),
+        "STM32F1" => ("apb2enr", "iop"),
+        "STM32L1" => ("ahbenr", "gpiop"),
+        "STM32F2" | "STM32F4" | "STM32F7" => ("ahb1enr", "gpio"),
+        "STM32G4" | "STM32L4" | "STM32L4+" | "STM32L5" | "STM32WB" | "STM32WL" | "STM32H5"
+        | "STM32WBA" => ("ahb2enr", "gpio"),
+        "STM32U5" | "STM32U3" => ("ahb2enr1", "gpio"),
+        "STM32H7" => ("ahb4enr", "gpio"),
+        s => bail!("Family `{}` is not supported by the BSP generator yet!", s),
+    };
+    Ok(GpioClock {
+        register,
+        field_prefix,
+    })
+}
+
+/// Emit the body of `pins::init`, pins must be sorted by port
+pub fn render_pin_init(family: &str, pins: &[PinConfig]) -> Result<String> {
+    let mut code = String::new();
+    if pins.is_empty() {
+        return Ok(code);
+    }
+    let clock = gpio_clock(family)?;
+    let mut ports: Vec<char> = pins.iter().map(|p| p.port).collect();
+    ports.dedup();
+
+    writeln!(code, "    // Enable the clock of the used GPIO ports")?;
+    writeln!(code, "    dp.RCC.{}().modify(|_, w| {{", clock.register)?;
+    writeln!(code, "        w")?;
+    for port in &ports {
+        writeln!(
+            code,
+            "            .{}{}en()",
+            clock.field_prefix,
+            port.to_ascii_lowercase()
+        )?;
+        writeln!(code, "            .set_bit()")?;
+    }
+    writeln!(code, "    }});")?;
+
+    for port in ports {
+        let port_pins: Vec<&PinConfig> = pins.iter().filter(|p| p.port == port).collect();
+        writeln!(code)?;
+        for pin in &port_pins {
+            writeln!(code, "    // {}: {}", pin.pin_name(), pin.name)?;
+        }
+        if family == "STM32F1" {
+            render_port_f1(&mut code, port, &port_pins)?;
+        } else {
+            render_port(&mut code, port, &port_pins)?;
+        }
+    }
+    Ok(code)
+}
+
+/// Accumulate the bit fields of one register of a port
+#[derive(Default)]
+struct RegisterUpdate {
+    mask: u32,
+    value: u32,
+}
+
+impl RegisterUpdate {
+    fn set(&mut self, offset: u32, width: u32, value: u32) {
+        let field = ((1u32 << width) - 1) << offset;
+        self.mask |= field;
+        self.value = (self.value & !field) | (value << offset);
+    }
+
+    fn render(&self, code: &mut String, port: char, register: &str) -> Result<()> {
+        if self.mask == 0 {
+            return Ok(());
+        }
+        writeln!(
+            code,
+            "    dp.GPIO{port}.{register}().modify(|r, w| unsafe {{ w.bits((r.bits() & !{:#010x}) | {:#010x}) }});",
+            self.mask, self.value
+        )?;
+        Ok(())
+    }
+}
+
+fn render_port(code: &mut String, port: char, pins: &[&PinConfig]) -> Result<()> {
+    let mut moder = RegisterUpdate::default();
+    let mut otyper = RegisterUpdate::default();
+    let mut ospeedr = RegisterUpdate::default();
+    let mut pupdr = RegisterUpdate::default();
+    let mut afrl = RegisterUpdate::default();
+    let mut afrh = RegisterUpdate::default();
+    let mut set_bits = 0u32;
+
+    for pin in pins {
+        let index = pin.index as u32;
+        let mode = match pin.mode {
+            PinMode::Input => 0b00,
+            PinMode::Output => 0b01,
+            PinMode::Alternate(_) => 0b10,
+            PinMode::Analog => 0b11,
+        };
+        if let PinMode::Alternate(af) = pin.mode {
+            let Some(af) = af else {
+                bail!(
+                    "The alternate function number of pin `{}` ({}) is unknown, pls set `af` or `signal`",
+                    pin.name,
+                    pin.pin_name()
+                );
+            };
+            if index < 8 {
+                afrl.set(index * 4, 4, af as u32);
+            } else {
+                afrh.set((index - 8) * 4, 4, af as u32);
+            }
+        }
+        if matches!(pin.mode, PinMode::Output | PinMode::Alternate(_)) {
+            otyper.set(index, 1, pin.open_drain as u32);
+            let speed = match pin.speed {
+                Speed::Low => 0b00,
+                Speed::Medium => 0b01,
+                Speed::High => 0b10,
+                Speed::VeryHigh => 0b11,
+            };
+            ospeedr.set(index * 2, 2, speed);
+        }
+        let pull = match pin.pull {
+            Pull::None => 0b00,
+            Pull::Up => 0b01,
+            Pull::Down => 0b10,
+        };
+        pupdr.set(index * 2, 2, pull);
+        if pin.mode == PinMode::Output && pin.initial_high {
+            set_bits |= 1 << index;
+        }
+        moder.set(index * 2, 2, mode);
+    }
+
+    // the output level and the alternate function are set before the mode,
+    // so the pin never drives an unexpected level
+    if set_bits != 0 {
+        writeln!(
+            code,
+            "    dp.GPIO{port}.bsrr().write(|w| unsafe {{ w.bits({set_bits:#010x}) }});"
+        )?;
+    }
+    otyper.render(code, port, "otyper")?;
+    ospeedr.render(code, port, "ospeedr")?;
+    pupdr.render(code, port, "pupdr")?;
+    afrl.render(code, port, "afrl")?;
+    afrh.render(code, port, "afrh")?;
+    moder.render(code, port, "moder")?;
+    Ok(())
+}
+
+/// STM32F1 uses the CRL/CRH layout, 4 bits per pin
+fn render_port_f1(code: &mut String, port: char, pins: &[&PinConfig]) -> Result<()> {
+    let mut crl = RegisterUpdate::default();
+    let mut crh = RegisterUpdate::default();
+    let mut set_bits = 0u32;
+    let mut reset_bits = 0u32;
+
+    for pin in pins {
+        let index = pin.index as u32;
+        let output_mode = match pin.speed {
+            Speed::Low => 0b10,
+            Speed::Medium => 0b01,
+            Speed::High | Speed::VeryHigh => 0b11,
+        };
+        let config = match pin.mode {
+            PinMode::Analog => 0b0000,
+            PinMode::Input if pin.pull == Pull::None => 0b0100,
+            PinMode::Input => {
+                // the pull direction is selected by the output register
+                if pin.pull == Pull::Up {
+                    set_bits |= 1 << index;
+                } else {
+                    reset_bits |= 1 << index;
+                }
+                0b1000
+            }
+            PinMode::Output => {
+                if pin.initial_high {
+                    set_bits |= 1 << index;
+                }
+                ((pin.open_drain as u32) << 2) | output_mode
+            }
+            PinMode::Alternate(Some(_)) => bail!(
+                "Pin `{}`: STM32F1 has no alternate function number, pls remove `af`",
+                pin.name
+            ),
+            PinMode::Alternate(None) => (0b10 | pin.open_drain as u32) << 2 | output_mode,
+        };
+        if index < 8 {
+            crl.set(index * 4, 4, config);
+        } else {
+            crh.set((index - 8) * 4, 4, config);
+        }
+    }
+
+    if set_bits != 0 || reset_bits != 0 {
+        writeln!(
+            code,
+            "    dp.GPIO{port}.bsrr().write(|w| unsafe {{ w.bits({:#010x}) }});",
+            set_bits | reset_bits << 16
+        )?;
+    }
+    crl.render(code, port, "crl")?;
+    crh.render(code, port, "crh")?;
+    Ok(())
+}
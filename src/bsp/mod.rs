@@ -0,0 +1,532 @@
+//! Generate the board support package of a project from `.stm32bs.toml`
+
+/// Cargo dependencies requested by the `[driver]` and `[middleware]` tables
+pub mod dependencies;
+
+/// Code emission for the GPIO configuration
+pub mod gpio;
+
+/// Parse the `[pinmap]` table
+pub mod pinmap;
+
+use anyhow::{bail, Result};
+use console::style;
+use log::warn;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::database;
+use crate::project_config::ProjectConfig;
+use crate::stm32_device::chip_info::{peripheral_kind, ChipInfo};
+use dependencies::Dependency;
+use pinmap::{is_identifier, PinConfig};
+
+/// Directory of the generated module, relative to the project root
+pub const BSP_DIR: &str = "src/bsp";
+
+const GENERATED_HEADER: &str = "\
+// This file is generated by cargo-stm32bs from `.stm32bs.toml`.
+// Changes will be lost when the BSP is generated again.
+";
+
+/// Everything the BSP generator needs to know about the board
+pub struct BspConfig {
+    pub peripherals: Vec<String>,
+    pub pins: Vec<PinConfig>,
+    pub drivers: Vec<Dependency>,
+    pub middlewares: Vec<Dependency>,
+}
+
+impl TryFrom<&ProjectConfig> for BspConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(project_config: &ProjectConfig) -> Result<Self> {
+        Ok(Self {
+            peripherals: parse_peripherals(&project_config.peripheral)?,
+            pins: project_config
+                .pinmap
+                .as_ref()
+                .map(pinmap::parse_pinmap)
+                .transpose()?
+                .unwrap_or_default(),
+            drivers: dependencies::parse_dependencies("driver", &project_config.driver)?,
+            middlewares: dependencies::parse_dependencies(
+                "middleware",
+                &project_config.middleware,
+            )?,
+        })
+    }
+}
+
+impl BspConfig {
+    /// Check the pin map against the package of the part, see `pinmap::validate_pinmap`.
+    /// Databases without pin data can not check it, only a warning is given.
+    pub fn check_pins(&mut self, chip_info: &ChipInfo) -> Result<()> {
+        if self.pins.is_empty() {
+            return Ok(());
+        }
+        match database::pin::get_pins(&chip_info.refname)? {
+            Some(package_pins) => pinmap::validate_pinmap(&mut self.pins, &package_pins),
+            None => {
+                warn!(
+                    "{}",
+                    style(format!(
+                        "The database has no pin data of {}, the [pinmap] is not checked!",
+                        chip_info.refname
+                    ))
+                    .bold()
+                    .yellow()
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Check that every instance of `[peripheral]` exists on the part. Databases without
+    /// a peripheral inventory can not check it, only a warning is given.
+    pub fn check_peripherals(&self, chip_info: &ChipInfo) -> Result<()> {
+        if self.peripherals.is_empty() {
+            return Ok(());
+        }
+        if chip_info.peripherals.is_empty() {
+            warn!(
+                "{}",
+                style(format!(
+                    "The database has no peripheral inventory of {}, the [peripheral] table is not checked!",
+                    chip_info.refname
+                ))
+                .bold()
+                .yellow()
+            );
+            return Ok(());
+        }
+        let mut errors = Vec::new();
+        for instance in self.peripherals.iter() {
+            if chip_info.peripheral(instance).is_some() {
+                continue;
+            }
+            let others: Vec<&str> = chip_info
+                .peripherals_of_kind(peripheral_kind(instance))
+                .iter()
+                .map(|p| p.instance.as_str())
+                .collect();
+            errors.push(if others.is_empty() {
+                format!("{} has no {}", chip_info.refname, instance)
+            } else {
+                format!(
+                    "{} has no {}, available instances: {}",
+                    chip_info.refname,
+                    instance,
+                    others.join(", ")
+                )
+            });
+        }
+        if !errors.is_empty() {
+            bail!("Invalid [peripheral]:\n  {}", errors.join("\n  "));
+        }
+        Ok(())
+    }
+}
+
+/// Read the `[peripheral]` table, every enabled instance is handed over by `bsp::init`
+fn parse_peripherals(table: &Option<HashMap<String, toml::Value>>) -> Result<Vec<String>> {
+    let mut peripherals = Vec::new();
+    if let Some(table) = table {
+        for (name, value) in table.iter() {
+            if let toml::Value::Boolean(false) = value {
+                continue;
+            }
+            if !is_identifier(name) {
+                bail!("Peripheral `{}` is not a valid peripheral instance", name);
+            }
+            let name = name.to_uppercase();
+            if name == "RCC" || name.starts_with("GPIO") {
+                bail!(
+                    "Peripheral `{}` is used by the BSP itself and can not be listed in [peripheral]",
+                    name
+                );
+            }
+            peripherals.push(name);
+        }
+    }
+    peripherals.sort();
+    Ok(peripherals)
+}
+
+/// Write the `bsp` module into the project and add the requested crates to `Cargo.toml`.
+/// Return the list of generated files.
+pub fn generate(
+    chip_info: &ChipInfo,
+    project_config: &ProjectConfig,
+    project_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let mut bsp_config = BspConfig::try_from(project_config)?;
+    bsp_config.check_peripherals(chip_info)?;
+    bsp_config.check_pins(chip_info)?;
+
+    let bsp_dir = project_dir.join(BSP_DIR);
+    fs::create_dir_all(&bsp_dir)?;
+    let files = vec![
+        (bsp_dir.join("mod.rs"), render_mod(chip_info, &bsp_config)?),
+        (
+            bsp_dir.join("pins.rs"),
+            render_pins(chip_info, &bsp_config)?,
+        ),
+    ];
+    for (path, content) in files.iter() {
+        fs::write(path, content)?;
+    }
+
+    let cargo_toml = project_dir.join("Cargo.toml");
+    if cargo_toml.exists() {
+        let mut crates = bsp_config.drivers.clone();
+        crates.extend(bsp_config.middlewares.iter().cloned());
+        dependencies::add_missing_dependencies(&cargo_toml, &crates)?;
+    }
+
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Render `src/bsp/mod.rs`
+pub fn render_mod(chip_info: &ChipInfo, bsp_config: &BspConfig) -> Result<String> {
+    let mut code = String::from(GENERATED_HEADER);
+    writeln!(code)?;
+    writeln!(
+        code,
+        "//! Board support package for {} ({}, {})",
+        chip_info.cpn, chip_info.family, chip_info.core
+    )?;
+    writeln!(code)?;
+    writeln!(code, "pub mod pins;")?;
+    writeln!(code)?;
+    writeln!(
+        code,
+        "pub use {} as pac;",
+        chip_info.pac_name.replace('-', "_")
+    )?;
+    for (title, crates) in [
+        ("Drivers", &bsp_config.drivers),
+        ("Middlewares", &bsp_config.middlewares),
+    ] {
+        if crates.is_empty() {
+            continue;
+        }
+        writeln!(code)?;
+        writeln!(code, "// {title}")?;
+        for dependency in crates {
+            writeln!(code, "pub use {};", dependency.crate_ident())?;
+        }
+    }
+
+    if let Ok(sysclk) = chip_info.reset_clock() {
+        writeln!(code)?;
+        writeln!(code, "/// System clock frequency after reset, in Hz")?;
+        writeln!(code, "pub const SYSCLK_HZ: u32 = {sysclk};")?;
+    }
+
+    writeln!(code)?;
+    writeln!(code, "/// Peripherals handed over to the application")?;
+    if bsp_config.peripherals.is_empty() {
+        writeln!(code, "pub struct Board {{}}")?;
+    } else {
+        writeln!(code, "pub struct Board {{")?;
+        for peripheral in &bsp_config.peripherals {
+            writeln!(
+                code,
+                "    pub {}: pac::{},",
+                peripheral.to_lowercase(),
+                peripheral
+            )?;
+        }
+        writeln!(code, "}}")?;
+    }
+
+    writeln!(code)?;
+    writeln!(code, "/// Initialize the board and return the peripherals")?;
+    writeln!(code, "pub fn init(dp: pac::Peripherals) -> Board {{")?;
+    writeln!(code, "    pins::init(&dp);")?;
+    writeln!(code)?;
+    if bsp_config.peripherals.is_empty() {
+        writeln!(code, "    Board {{}}")?;
+    } else {
+        writeln!(code, "    Board {{")?;
+        for peripheral in &bsp_config.peripherals {
+            writeln!(
+                code,
+                "        {}: dp.{},",
+                peripheral.to_lowercase(),
+                peripheral
+            )?;
+        }
+        writeln!(code, "    }}")?;
+    }
+    writeln!(code, "}}")?;
+    Ok(code)
+}
+
+/// Render `src/bsp/pins.rs`
+pub fn render_pins(chip_info: &ChipInfo, bsp_config: &BspConfig) -> Result<String> {
+    let mut code = String::from(GENERATED_HEADER);
+    writeln!(code)?;
+    writeln!(code, "//! Pin assignment of the board")?;
+    writeln!(code)?;
+    writeln!(code, "use super::pac;")?;
+    writeln!(code)?;
+    writeln!(code, "/// A GPIO pin, port letter and pin number")?;
+    writeln!(code, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]")?;
+    writeln!(code, "pub struct Pin {{")?;
+    writeln!(code, "    pub port: char,")?;
+    writeln!(code, "    pub index: u8,")?;
+    writeln!(code, "}}")?;
+    for pin in &bsp_config.pins {
+        writeln!(code)?;
+        writeln!(code, "/// {}", pin.pin_name())?;
+        writeln!(
+            code,
+            "pub const {}: Pin = Pin {{ port: '{}', index: {} }};",
+            pin.name.to_uppercase(),
+            pin.port,
+            pin.index
+        )?;
+    }
+    writeln!(code)?;
+    writeln!(code, "/// Configure the GPIO ports")?;
+    if bsp_config.pins.is_empty() {
+        writeln!(code, "pub fn init(_dp: &pac::Peripherals) {{}}")?;
+    } else {
+        writeln!(code, "pub fn init(dp: &pac::Peripherals) {{")?;
+        code.push_str(&gpio::render_pin_init(&chip_info.family, &bsp_config.pins)?);
+        writeln!(code, "}}")?;
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::pin::{PackagePin, PinSignal};
+    use crate::stm32_device::chip_info::{ArmCore, Peripheral};
+    use crate::utils::tmp_dir;
+    use pinmap::PinMode;
+
+    fn chip_info() -> ChipInfo {
+        ChipInfo::test_part("STM32G071CBT6TR", ArmCore::CortexM0Plus)
+    }
+
+    fn project_config(content: &str) -> ProjectConfig {
+        ProjectConfig::try_from(content.to_string()).unwrap()
+    }
+
+    #[test]
+    fn it_renders_pins_and_peripherals() {
+        let config = project_config(
+            r#"
+            [peripheral]
+            USART2 = true
+            tim3 = {}
+            I2C1 = false
+            [pinmap]
+            LED = "PA5"
+            BUTTON = { pin = "PC13", mode = "input", pull = "up" }
+            TX = { pin = "PA2", af = 1 }
+            "#,
+        );
+        let bsp_config = BspConfig::try_from(&config).unwrap();
+        assert_eq!(bsp_config.peripherals, vec!["TIM3", "USART2"]);
+
+        let code = render_mod(&chip_info(), &bsp_config).unwrap();
+        assert!(code.contains("pub use stm32g0 as pac;"));
+        assert!(code.contains("    pub usart2: pac::USART2,"));
+        assert!(code.contains("        tim3: dp.TIM3,"));
+        assert!(!code.contains("I2C1"));
+        assert!(code.contains("pub const SYSCLK_HZ: u32 = 16000000;"));
+
+        let code = render_pins(&chip_info(), &bsp_config).unwrap();
+        assert!(code.contains("pub const LED: Pin = Pin { port: 'A', index: 5 };"));
+        assert!(code.contains(".gpioaen()"));
+        assert!(code.contains(".gpiocen()"));
+        // PA2 AF1 and PA5 output
+        assert!(code.contains(
+            "dp.GPIOA.afrl().modify(|r, w| unsafe { w.bits((r.bits() & !0x00000f00) | 0x00000100) });"
+        ));
+        assert!(code.contains(
+            "dp.GPIOA.moder().modify(|r, w| unsafe { w.bits((r.bits() & !0x00000c30) | 0x00000420) });"
+        ));
+        assert!(code.contains(
+            "dp.GPIOC.pupdr().modify(|r, w| unsafe { w.bits((r.bits() & !0x0c000000) | 0x04000000) });"
+        ));
+    }
+
+    #[test]
+    fn it_renders_the_clock_after_reset() {
+        let bsp_config = BspConfig::try_from(&project_config("")).unwrap();
+        for (cpn, core, sysclk) in [
+            ("STM32L073RZT6", ArmCore::CortexM0Plus, 2_097_000),
+            ("STM32L476RGT6", ArmCore::CortexM4, 4_000_000),
+            ("STM32C031C6T6", ArmCore::CortexM0Plus, 12_000_000),
+            ("STM32F407VGT6", ArmCore::CortexM4, 16_000_000),
+        ] {
+            let code = render_mod(&ChipInfo::test_part(cpn, core), &bsp_config).unwrap();
+            assert!(code.contains(&format!("pub const SYSCLK_HZ: u32 = {sysclk};")));
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_pinmap() {
+        let config = project_config(
+            r#"
+            [pinmap]
+            LED = "PA5"
+            LED2 = "pa5"
+            "#,
+        );
+        let err = BspConfig::try_from(&config).err().unwrap();
+        assert!(err.to_string().contains("Pin PA5 is assigned to both"));
+
+        let config = project_config(
+            r#"
+            [pinmap]
+            LED = "PZ5"
+            "#,
+        );
+        assert!(BspConfig::try_from(&config).is_err());
+
+        let config = project_config(
+            r#"
+            [pinmap]
+            TX = { pin = "PA2", mode = "af" }
+            "#,
+        );
+        let bsp_config = BspConfig::try_from(&config).unwrap();
+        assert!(render_pins(&chip_info(), &bsp_config).is_err());
+    }
+
+    #[test]
+    fn it_checks_the_peripherals_of_the_part() {
+        let config = project_config(
+            r#"
+            [peripheral]
+            USART2 = true
+            USART3 = true
+            TIM3 = true
+            "#,
+        );
+        let bsp_config = BspConfig::try_from(&config).unwrap();
+        let mut chip_info = chip_info();
+        // without inventory nothing is checked
+        assert!(bsp_config.check_peripherals(&chip_info).is_ok());
+
+        let peripheral = |instance: &str| Peripheral {
+            instance: instance.to_string(),
+            name: instance.trim_end_matches(char::is_numeric).to_string(),
+            version: String::new(),
+            bus: None,
+            enable_register: None,
+            enable_bit: None,
+            irqs: Vec::new(),
+            dma_requests: Vec::new(),
+        };
+        chip_info.peripherals = vec![peripheral("USART1"), peripheral("USART2")];
+        let error = bsp_config
+            .check_peripherals(&chip_info)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("STM32G071CBTx has no TIM3\n"), "{error}");
+        assert!(
+            error.contains("STM32G071CBTx has no USART3, available instances: USART1, USART2"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn it_validates_the_pinmap_against_the_package() {
+        let signal = |name: &str, af| PinSignal {
+            name: name.to_string(),
+            af,
+        };
+        let package_pins = vec![
+            PackagePin {
+                name: "PA2".to_string(),
+                position: "12".to_string(),
+                signals: vec![signal("USART2_TX", Some(1)), signal("ADC1_IN2", None)],
+            },
+            PackagePin {
+                name: "PA5".to_string(),
+                position: "15".to_string(),
+                signals: vec![signal("SPI1_SCK", Some(0))],
+            },
+        ];
+        let pins = |content: &str| BspConfig::try_from(&project_config(content)).unwrap().pins;
+
+        let mut valid = pins(
+            r#"
+            [pinmap]
+            TX = { pin = "PA2", signal = "usart2_tx" }
+            SCK = { pin = "PA5", af = 0 }
+            "#,
+        );
+        pinmap::validate_pinmap(&mut valid, &package_pins).unwrap();
+        assert_eq!(valid[0].mode, PinMode::Alternate(Some(1)));
+
+        let mut invalid = pins(
+            r#"
+            [pinmap]
+            LED = "PB9"
+            TX = { pin = "PA2", signal = "USART2_TX", af = 7 }
+            TX2 = { pin = "PA5", signal = "USART2_TX" }
+            "#,
+        );
+        let err = pinmap::validate_pinmap(&mut invalid, &package_pins)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Pin `LED`: PB9 is not available in the package"));
+        assert!(err.contains("Pin `TX`: signal USART2_TX is AF1 on PA2, not AF7"));
+        assert!(
+            err.contains("Pin `TX2`: signal USART2_TX is not available on PA5, possible pins: PA2")
+        );
+        assert!(err.contains("Signal USART2_TX is assigned to both `TX` and `TX2`"));
+
+        let mut invalid = pins(
+            r#"
+            [pinmap]
+            SCK = { pin = "PA5", af = 5 }
+            "#,
+        );
+        let err = pinmap::validate_pinmap(&mut invalid, &package_pins)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("PA5 has no AF5, possible functions: AF0 SPI1_SCK"));
+    }
+
+    #[test]
+    fn it_adds_missing_dependencies() {
+        let dir = tmp_dir().unwrap();
+        let cargo_toml = dir.path().join("Cargo.toml");
+        fs::write(
+            &cargo_toml,
+            "[package]\nname = \"board\"\n\n[dependencies]\n# keep me\nssd1306 = \"0.8\"\n\n[profile.release]\nlto = true\n",
+        )
+        .unwrap();
+        let config = project_config(
+            r#"
+            [driver]
+            ssd1306 = "0.9"
+            [middleware]
+            rtt-target = { version = "0.6" }
+            "#,
+        );
+
+        generate(&chip_info(), &config, dir.path()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&cargo_toml).unwrap(),
+            "[package]\nname = \"board\"\n\n[dependencies]\n# keep me\nssd1306 = \"0.8\"\nrtt-target = { version = \"0.6\" }\n\n[profile.release]\nlto = true\n"
+        );
+        let code = fs::read_to_string(dir.path().join(BSP_DIR).join("mod.rs")).unwrap();
+        assert!(code.contains("pub use rtt_target;"));
+        assert!(code.contains("pub use ssd1306;"));
+        assert!(dir.path().join(BSP_DIR).join("pins.rs").exists());
+    }
+}
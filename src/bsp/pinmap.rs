@@ -0,0 +1,287 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+use crate::database::pin::{pins_of_signal, PackagePin};
+
+/// Electrical configuration of a single pin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinMode {
+    Input,
+    Output,
+    Analog,
+    Alternate(Option<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pull {
+    None,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    Low,
+    Medium,
+    High,
+    VeryHigh,
+}
+
+/// One entry of the `[pinmap]` table of `.stm32bs.toml`
+///
+/// The short form `LED = "PA5"` is a push-pull output, the table form
+/// `TX = { pin = "PA2", mode = "af", af = 1 }` allows to set every option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinConfig {
+    pub name: String,
+    pub port: char,
+    pub index: u8,
+    pub mode: PinMode,
+    pub pull: Pull,
+    pub open_drain: bool,
+    pub speed: Speed,
+    pub initial_high: bool,
+    pub signal: Option<String>,
+}
+
+impl PinConfig {
+    /// Pin name as written in the datasheet, e.g. `PA5`
+    pub fn pin_name(&self) -> String {
+        format!("P{}{}", self.port, self.index)
+    }
+}
+
+/// Split a pin name such as `PA5` or `pc13` into its port letter and index
+pub fn parse_pin_name(pin: &str) -> Result<(char, u8)> {
+    let pin = pin.trim().to_uppercase();
+    let mut chars = pin.chars();
+    if chars.next() != Some('P') {
+        bail!("`{}` is not a valid pin name, eg. PA5", pin);
+    }
+    let port = match chars.next() {
+        Some(port @ 'A'..='K') => port,
+        _ => bail!("`{}` is not a valid pin name, eg. PA5", pin),
+    };
+    let index = match chars.as_str().parse::<u8>() {
+        Ok(index) if index < 16 => index,
+        _ => bail!("`{}` is not a valid pin name, eg. PA5", pin),
+    };
+    Ok((port, index))
+}
+
+/// Read the `[pinmap]` table, the pins are sorted by port and index
+pub fn parse_pinmap(pinmap: &HashMap<String, toml::Value>) -> Result<Vec<PinConfig>> {
+    let mut pins = Vec::with_capacity(pinmap.len());
+    for (name, value) in pinmap.iter() {
+        if !is_identifier(name) {
+            bail!("Pin name `{}` in [pinmap] is not a valid identifier", name);
+        }
+        let pin = match value {
+            toml::Value::String(pin) => {
+                let (port, index) = parse_pin_name(pin)?;
+                PinConfig {
+                    name: name.to_string(),
+                    port,
+                    index,
+                    mode: PinMode::Output,
+                    pull: Pull::None,
+                    open_drain: false,
+                    speed: Speed::Low,
+                    initial_high: false,
+                    signal: None,
+                }
+            }
+            toml::Value::Table(table) => parse_pin_table(name, table)?,
+            _ => bail!("Pin `{}` in [pinmap] should be a pin name or a table", name),
+        };
+        if let Some(other) = pins
+            .iter()
+            .find(|p: &&PinConfig| p.port == pin.port && p.index == pin.index)
+        {
+            bail!(
+                "Pin {} is assigned to both `{}` and `{}`",
+                pin.pin_name(),
+                other.name,
+                pin.name
+            );
+        }
+        pins.push(pin);
+    }
+    pins.sort_by(|a, b| (a.port, a.index, &a.name).cmp(&(b.port, b.index, &b.name)));
+    Ok(pins)
+}
+
+fn parse_pin_table(name: &str, table: &toml::Table) -> Result<PinConfig> {
+    let get_str = |key: &str| -> Result<Option<&str>> {
+        match table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s.as_str())),
+            Some(_) => bail!("`{}` of pin `{}` should be a string", key, name),
+        }
+    };
+    let get_bool = |key: &str| -> Result<bool> {
+        match table.get(key) {
+            None => Ok(false),
+            Some(toml::Value::Boolean(b)) => Ok(*b),
+            Some(_) => bail!("`{}` of pin `{}` should be a boolean", key, name),
+        }
+    };
+
+    let Some(pin) = get_str("pin")? else {
+        bail!("Pin `{}` in [pinmap] has no `pin` field", name);
+    };
+    let (port, index) = parse_pin_name(pin)?;
+    let af = match table.get("af") {
+        None => None,
+        Some(toml::Value::Integer(af @ 0..=15)) => Some(*af as u8),
+        Some(_) => bail!("`af` of pin `{}` should be an integer from 0 to 15", name),
+    };
+    let signal = get_str("signal")?.map(|s| s.to_uppercase());
+    let mode = match get_str("mode")? {
+        Some("input") => PinMode::Input,
+        Some("output") => PinMode::Output,
+        Some("analog") => PinMode::Analog,
+        Some("af") | Some("alternate") => PinMode::Alternate(af),
+        // a pin with a signal or an AF number but without a mode is an alternate function pin
+        None if af.is_some() || signal.is_some() => PinMode::Alternate(af),
+        None => PinMode::Output,
+        Some(s) => bail!(
+            "Unknown mode `{}` of pin `{}`, possible values are `input`, `output`, `analog` and `af`",
+            s,
+            name
+        ),
+    };
+    if af.is_some() && !matches!(mode, PinMode::Alternate(_)) {
+        bail!("Pin `{}` has an `af` number but is not in `af` mode", name);
+    }
+    let pull = match get_str("pull")? {
+        None | Some("none") => Pull::None,
+        Some("up") => Pull::Up,
+        Some("down") => Pull::Down,
+        Some(s) => bail!(
+            "Unknown pull `{}` of pin `{}`, possible values are `none`, `up` and `down`",
+            s,
+            name
+        ),
+    };
+    let speed = match get_str("speed")? {
+        None | Some("low") => Speed::Low,
+        Some("medium") => Speed::Medium,
+        Some("high") => Speed::High,
+        Some("very_high") => Speed::VeryHigh,
+        Some(s) => bail!(
+            "Unknown speed `{}` of pin `{}`, possible values are `low`, `medium`, `high` and `very_high`",
+            s,
+            name
+        ),
+    };
+
+    Ok(PinConfig {
+        name: name.to_string(),
+        port,
+        index,
+        mode,
+        pull,
+        open_drain: get_bool("open_drain")?,
+        speed,
+        initial_high: get_bool("initial_high")?,
+        signal,
+    })
+}
+
+/// Check the pin map against the pins of the package of the part.
+///
+/// Every pin must be bonded out, a `signal` must be available on its pin and an `af`
+/// number must select a function of the pin, or the one of the signal. A signal can
+/// only be routed to one pin. A missing `af` is taken from the signal.
+/// All the problems are reported at once.
+pub fn validate_pinmap(pins: &mut [PinConfig], package_pins: &[PackagePin]) -> Result<()> {
+    let mut errors = Vec::new();
+    for pin in pins.iter_mut() {
+        let pin_name = pin.pin_name();
+        let Some(package_pin) = package_pins.iter().find(|p| p.name == pin_name) else {
+            errors.push(format!(
+                "Pin `{}`: {} is not available in the package",
+                pin.name, pin_name
+            ));
+            continue;
+        };
+        let PinMode::Alternate(af) = pin.mode else {
+            continue;
+        };
+        match (&pin.signal, af) {
+            (Some(signal), af) => {
+                let Some(pin_signal) = package_pin.signal(signal) else {
+                    let others = pins_of_signal(package_pins, signal);
+                    errors.push(if others.is_empty() {
+                        format!("Pin `{}`: signal {} does not exist", pin.name, signal)
+                    } else {
+                        format!(
+                            "Pin `{}`: signal {} is not available on {}, possible pins: {}",
+                            pin.name,
+                            signal,
+                            pin_name,
+                            others
+                                .iter()
+                                .map(|(name, _)| *name)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    });
+                    continue;
+                };
+                match (af, pin_signal.af) {
+                    (Some(af), Some(signal_af)) if af != signal_af => errors.push(format!(
+                        "Pin `{}`: signal {} is AF{} on {}, not AF{}",
+                        pin.name, signal, signal_af, pin_name, af
+                    )),
+                    (None, Some(signal_af)) => pin.mode = PinMode::Alternate(Some(signal_af)),
+                    _ => {}
+                }
+            }
+            (None, Some(af)) => {
+                let functions: Vec<String> = package_pin
+                    .signals
+                    .iter()
+                    .filter_map(|s| s.af.map(|af| format!("AF{} {}", af, s.name)))
+                    .collect();
+                if !functions.is_empty() && !package_pin.signals.iter().any(|s| s.af == Some(af)) {
+                    errors.push(format!(
+                        "Pin `{}`: {} has no AF{}, possible functions: {}",
+                        pin.name,
+                        pin_name,
+                        af,
+                        functions.join(", ")
+                    ));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    let mut signals: Vec<(&str, &str)> = pins
+        .iter()
+        .filter_map(|p| p.signal.as_deref().map(|s| (s, p.name.as_str())))
+        .collect();
+    signals.sort();
+    for pair in signals.windows(2) {
+        if pair[0].0 == pair[1].0 {
+            errors.push(format!(
+                "Signal {} is assigned to both `{}` and `{}`",
+                pair[0].0, pair[0].1, pair[1].1
+            ));
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!("Invalid [pinmap]:\n  {}", errors.join("\n  "));
+    }
+    Ok(())
+}
+
+/// Check that a name from `.stm32bs.toml` can be used as a rust identifier
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
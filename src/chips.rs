@@ -0,0 +1,171 @@
+use anyhow::{bail, Result};
+use console::style;
+use log::warn;
+
+use crate::args::{ChipsArgs, ClockTreeArgs, InfoArgs, OutputFormat};
+use crate::database::cpn::{cpn_query, cpn_query_filtered, get_refname, CpnEntry, CpnFilter};
+use crate::database::resource::get_resource;
+use crate::stm32_device::clock::{self, ClockRequest};
+
+/// List the parts of the database which match the filters of `cargo stm32bs chips`
+pub fn list_chips(args: &ChipsArgs) -> Result<()> {
+    let filter = CpnFilter {
+        family: args.family.clone(),
+        core: args.core.clone(),
+        min_flash: args.min_flash,
+        min_ram: args.min_ram,
+        package: args.package.clone(),
+    };
+    let list = cpn_query_filtered(args.pattern.as_deref().unwrap_or(""), &filter)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&list)?);
+    } else if list.is_empty() {
+        warn!("{}", style("No part matches the filters").bold().yellow());
+    } else {
+        print!("{}", render_table(&list));
+    }
+    Ok(())
+}
+
+/// Print the `ChipInfo` of a part for `cargo stm32bs info`
+pub fn show_chip_info(args: &InfoArgs) -> Result<()> {
+    let chip_pn = resolve_chip_pn(&args.chip_pn)?;
+    let chip_info = get_resource(&chip_pn)?;
+    match args.format {
+        OutputFormat::Human => println!("{chip_info}"),
+        OutputFormat::Toml => print!("{}", toml::to_string(&chip_info)?),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&chip_info)?),
+    }
+    Ok(())
+}
+
+/// Print the solved clock tree of a part for `cargo stm32bs clock`
+pub fn show_clock_tree(args: &ClockTreeArgs) -> Result<()> {
+    let chip_pn = resolve_chip_pn(&args.chip_pn)?;
+    let chip_info = get_resource(&chip_pn)?;
+    let config = clock::solve(&chip_info, &ClockRequest::from(&args.clock))?;
+    match args.format {
+        OutputFormat::Human => println!("{config}"),
+        OutputFormat::Toml => print!("{}", toml::to_string(&config)?),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&config)?),
+    }
+    Ok(())
+}
+
+/// The part number must be complete, or match a single part
+pub(crate) fn resolve_chip_pn(chip_pn: &str) -> Result<String> {
+    let chip_pn = chip_pn.trim().to_uppercase();
+    if get_refname(&chip_pn).is_ok() {
+        return Ok(chip_pn);
+    }
+    let list = cpn_query(&chip_pn)?;
+    match list.len() {
+        0 => bail!(
+            "{} {}",
+            style(format!("`{chip_pn}`")).bold().yellow(),
+            style("is not a supported part number").bold().red()
+        ),
+        1 => Ok(list.into_iter().next().unwrap()),
+        n => bail!(
+            "{} {}\n{}",
+            style(format!("{n} parts match")).bold().red(),
+            style(format!("`{chip_pn}`")).bold().yellow(),
+            list.iter()
+                .take(10)
+                .map(|pn| format!("  - {pn}"))
+                .chain((n > 10).then(|| "  - ...".to_string()))
+                .collect::<Vec<String>>()
+                .join("\n")
+        ),
+    }
+}
+
+/// Render the parts as a table with aligned columns
+fn render_table(list: &[CpnEntry]) -> String {
+    let header = [
+        "Part number",
+        "Refname",
+        "Family",
+        "Core",
+        "Flash",
+        "RAM",
+        "Package",
+    ];
+    let rows: Vec<[String; 7]> = list
+        .iter()
+        .map(|entry| {
+            [
+                entry.cpn.clone(),
+                entry.refname.clone(),
+                entry.family.clone(),
+                match &entry.core2 {
+                    Some(core2) => format!("{}/{}", entry.core, core2),
+                    None => entry.core.clone(),
+                },
+                format!("{}K", entry.flash),
+                format!("{}K", entry.ram),
+                entry.package.clone().unwrap_or_else(|| "-".into()),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let format_row = |cells: &mut dyn Iterator<Item = &str>| -> String {
+        let line = cells
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<String>>()
+            .join("  ");
+        format!("{}\n", line.trim_end())
+    };
+
+    let mut table = format_row(&mut header.into_iter());
+    for row in rows.iter() {
+        table.push_str(&format_row(&mut row.iter().map(String::as_str)));
+    }
+    table.push_str(&format!("{} parts\n", list.len()));
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_an_aligned_table() {
+        let list = vec![
+            CpnEntry {
+                cpn: "STM32G071CBT6".into(),
+                refname: "STM32G071CBTx".into(),
+                family: "STM32G0".into(),
+                core: "Cortex-M0+".into(),
+                core2: None,
+                flash: 128,
+                ram: 36,
+                package: Some("LQFP48".into()),
+            },
+            CpnEntry {
+                cpn: "STM32H745ZIT6".into(),
+                refname: "STM32H745ZITx".into(),
+                family: "STM32H7".into(),
+                core: "Cortex-M7".into(),
+                core2: Some("Cortex-M4".into()),
+                flash: 2048,
+                ram: 1024,
+                package: None,
+            },
+        ];
+        assert_eq!(
+            render_table(&list),
+            "Part number    Refname        Family   Core                 Flash  RAM    Package\n\
+             STM32G071CBT6  STM32G071CBTx  STM32G0  Cortex-M0+           128K   36K    LQFP48\n\
+             STM32H745ZIT6  STM32H745ZITx  STM32H7  Cortex-M7/Cortex-M4  2048K  1024K  -\n\
+             2 parts\n"
+        );
+    }
+}
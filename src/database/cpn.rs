@@ -1,54 +1,286 @@
 use anyhow::{bail, Result};
+use serde::Serialize;
 
-use crate::database::DB_FILE_PATH;
+use crate::database::pin::get_pins;
+use crate::database::{self, column, optional_column};
+use crate::stm32_device::chip_info::{ArmCore, ChipStatus};
+use crate::stm32_device::chip_pn::{match_score, normalize_pn, package_from_refname};
 
-pub fn cpn_query<T: ToString>(cpn: T) -> Result<Vec<String>> {
-    let binding = DB_FILE_PATH.lock();
-    let binding2 = binding.as_ref().unwrap().borrow();
-    let path = binding2.as_ref();
-    if path.is_none() {
-        bail!("The database has not been initialized!");
+/// Filters of `cpn_query_filtered`, a filter which is not set matches every part
+#[derive(Debug, Default, Clone)]
+pub struct CpnFilter {
+    pub family: Option<String>,
+    pub core: Option<String>,
+    pub min_flash: Option<u32>,
+    pub min_ram: Option<u32>,
+    pub package: Option<String>,
+}
+
+/// A part number with the main resources of the chip
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CpnEntry {
+    pub cpn: String,
+    pub refname: String,
+    pub family: String,
+    pub core: String,
+    pub core2: Option<String>,
+    /// flash size in KB
+    pub flash: u32,
+    /// RAM size in KB
+    pub ram: u32,
+    pub package: Option<String>,
+}
+
+/// A reference name with its part numbers which match a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpnGroup {
+    pub refname: String,
+    /// best match first
+    pub cpns: Vec<String>,
+    /// best score of the part numbers, see `match_score`
+    pub score: u32,
+}
+
+/// Search the part numbers ranked by `match_score`, grouped by reference name, the best
+/// group first. Only the best kind of match is kept: when some part numbers start with
+/// the search, the ones which merely contain it or are near it are left out.
+pub fn cpn_search<T: ToString>(search: T) -> Result<Vec<CpnGroup>> {
+    let search = normalize_pn(&search.to_string());
+    let db = database::open()?;
+    let mut sta = db.prepare("select cpn, refname from cpn;")?;
+    let mut matches: Vec<(u32, String, String)> = Vec::new();
+    while let Ok(sqlite::State::Row) = sta.next() {
+        let cpn = column::<String>(&sta, "cpn")?;
+        if let Some(score) = match_score(&search, &cpn) {
+            matches.push((score, cpn, column::<String>(&sta, "refname")?));
+        }
+    }
+    let Some(best) = matches.iter().map(|(score, _, _)| score / 100).min() else {
+        return Ok(Vec::new());
+    };
+    matches.retain(|(score, _, _)| score / 100 == best);
+    matches.sort();
+
+    let mut groups: Vec<CpnGroup> = Vec::new();
+    for (score, cpn, refname) in matches {
+        match groups.iter_mut().find(|g| g.refname == refname) {
+            Some(group) => group.cpns.push(cpn),
+            None => groups.push(CpnGroup {
+                refname,
+                cpns: vec![cpn],
+                score,
+            }),
+        }
     }
+    Ok(groups)
+}
+
+pub fn cpn_query<T: ToString>(cpn: T) -> Result<Vec<String>> {
+    let db = database::open()?;
     let query_data = format!("%{}%", cpn.to_string());
     let query = "select cpn from cpn where cpn like ?;";
-    let db = sqlite::open(path.unwrap()).unwrap();
     let mut sta = db.prepare(query)?;
     sta.bind((1, query_data.as_str()))?;
     let mut list: Vec<String> = Vec::new();
     while let Ok(sqlite::State::Row) = sta.next() {
-        let cpn = sta.read::<String, _>("cpn").unwrap();
+        let cpn = column::<String>(&sta, "cpn")?;
         list.push(cpn);
     }
     Ok(list)
 }
 
-pub fn get_refname<T: ToString>(cpn: T) -> Result<String> {
-    let binding = DB_FILE_PATH.lock();
-    let binding2 = binding.as_ref().unwrap().borrow();
-    let path = binding2.as_ref();
-    if path.is_none() {
-        bail!("The database has not been initialized!");
+/// Query the parts which contain `cpn` and match all filters, sorted by part number
+pub fn cpn_query_filtered<T: ToString>(cpn: T, filter: &CpnFilter) -> Result<Vec<CpnEntry>> {
+    let db = database::open()?;
+    let query_data = format!("%{}%", cpn.to_string().to_uppercase());
+    let family = filter.family.as_deref().map(normalize_family);
+    let query = r###"
+        SELECT *
+        from cpn, resource
+        where cpn.refname = resource.refname
+        and cpn.cpn like ?
+        and (? = '' or upper(family) = ?)
+        and flash >= ?
+        and ram >= ?
+        order by cpn.cpn;
+    "###;
+    let mut sta = db.prepare(query)?;
+    sta.bind((1, query_data.as_str()))?;
+    sta.bind((2, family.as_deref().unwrap_or("")))?;
+    sta.bind((3, family.as_deref().unwrap_or("")))?;
+    sta.bind((4, filter.min_flash.unwrap_or(0) as i64))?;
+    sta.bind((5, filter.min_ram.unwrap_or(0) as i64))?;
+    let core = filter.core.as_deref().map(normalize_core);
+    let mut list: Vec<CpnEntry> = Vec::new();
+    while let Ok(sqlite::State::Row) = sta.next() {
+        let core_str = column::<String>(&sta, "core")?;
+        let core2_str = column::<Option<String>>(&sta, "core_second")?.unwrap_or_default();
+        if let Some(core) = &core {
+            if core_str != *core && core2_str != *core {
+                continue;
+            }
+        }
+        let refname = column::<String>(&sta, "refname")?;
+        // databases before schema version 3 have no package
+        let package = match optional_column::<String>(&sta, "package")? {
+            Some(package) if !package.is_empty() => Some(package),
+            _ => package_from_refname(&refname),
+        };
+        if let Some(wanted) = &filter.package {
+            if !package.as_ref().is_some_and(|p| package_matches(p, wanted)) {
+                continue;
+            }
+        }
+        list.push(CpnEntry {
+            cpn: column::<String>(&sta, "cpn")?,
+            refname,
+            family: column::<String>(&sta, "family")?,
+            core: ArmCore::try_from_short(core_str)?.to_string(),
+            core2: match core2_str.as_str() {
+                "" => None,
+                s => Some(ArmCore::try_from_short(s)?.to_string()),
+            },
+            flash: column::<i64>(&sta, "flash")? as u32,
+            ram: column::<i64>(&sta, "ram")? as u32,
+            package,
+        });
     }
+    Ok(list)
+}
+
+/// `g0`, `STM32G0` and `stm32g0` are the same family
+fn normalize_family(family: &str) -> String {
+    let family = family.trim().to_uppercase();
+    if family.starts_with("STM32") {
+        family
+    } else {
+        format!("STM32{family}")
+    }
+}
+
+/// Turn `M0+`, `cortex-m4` or `4` into the short core name of the database
+fn normalize_core(core: &str) -> String {
+    let core = core.trim().to_uppercase();
+    let core = core.strip_prefix("CORTEX").unwrap_or(&core);
+    let core = core.trim_start_matches(['-', ' ']);
+    core.strip_prefix('M').unwrap_or(core).to_string()
+}
+
+/// A package filter matches the package type alone, e.g. `LQFP`, or with the pin count, e.g. `LQFP48`
+fn package_matches(package: &str, wanted: &str) -> bool {
+    let wanted = wanted.trim().to_uppercase();
+    package == wanted || package.trim_end_matches(|c: char| c.is_ascii_digit()) == wanted
+}
+
+pub fn get_refname<T: ToString>(cpn: T) -> Result<String> {
+    let db = database::open()?;
     let query_data = cpn.to_string();
     let query = "select refname from cpn where cpn = ?;";
-    let db = sqlite::open(path.unwrap()).unwrap();
     let mut sta = db.prepare(query)?;
     sta.bind((1, query_data.as_str()))?;
     if let Ok(sqlite::State::Row) = sta.next() {
-        let refname = sta.read::<String, _>("refname").unwrap();
+        let refname = column::<String>(&sta, "refname")?;
         Ok(refname)
     } else {
         bail!("No record be found!");
     }
 }
 
+/// Status of a part, `Unknown` if the database has no status
+pub fn get_status<T: ToString>(cpn: T) -> Result<ChipStatus> {
+    let db = database::open()?;
+    let query = r###"
+        SELECT *
+        from cpn, resource
+        where cpn.refname = resource.refname
+        and cpn.cpn = ?;
+    "###;
+    let mut sta = db.prepare(query)?;
+    sta.bind((1, cpn.to_string().as_str()))?;
+    if let Ok(sqlite::State::Row) = sta.next() {
+        match optional_column::<String>(&sta, "status")? {
+            Some(status) => ChipStatus::try_from_str(status),
+            None => Ok(ChipStatus::Unknown),
+        }
+    } else {
+        bail!("No record be found!");
+    }
+}
+
+/// Active parts which can replace `cpn` on the same board, one part number per reference
+/// name. They are of the same family and package, and if the database has the pins of
+/// both parts, every pin of `cpn` is at the same position of the replacement.
+pub fn pin_compatible_parts<T: ToString>(cpn: T) -> Result<Vec<String>> {
+    let refname = get_refname(cpn)?;
+    let db = database::open()?;
+    let query = r###"
+        SELECT *
+        from cpn, resource
+        where cpn.refname = resource.refname
+        and resource.family = (SELECT family from resource where refname = ?)
+        order by cpn.cpn;
+    "###;
+    let mut sta = db.prepare(query)?;
+    sta.bind((1, refname.as_str()))?;
+    let package_of = |sta: &sqlite::Statement, refname: &str| -> Result<Option<String>> {
+        Ok(match optional_column::<String>(sta, "package")? {
+            Some(package) if !package.is_empty() => Some(package),
+            _ => package_from_refname(refname),
+        })
+    };
+    let mut package = None;
+    let mut candidates: Vec<(String, String, Option<String>)> = Vec::new();
+    while let Ok(sqlite::State::Row) = sta.next() {
+        let other = column::<String>(&sta, "refname")?;
+        if other == refname {
+            package = package_of(&sta, &other)?;
+            continue;
+        }
+        let status = match optional_column::<String>(&sta, "status")? {
+            Some(status) => ChipStatus::try_from_str(status)?,
+            None => ChipStatus::Unknown,
+        };
+        if status != ChipStatus::Active || candidates.iter().any(|(_, r, _)| *r == other) {
+            continue;
+        }
+        let other_package = package_of(&sta, &other)?;
+        candidates.push((column::<String>(&sta, "cpn")?, other, other_package));
+    }
+    let Some(package) = package else {
+        return Ok(Vec::new());
+    };
+
+    let pins = get_pins(&refname)?;
+    let mut list = Vec::new();
+    for (cpn, other, other_package) in candidates {
+        if other_package.as_ref() != Some(&package) {
+            continue;
+        }
+        if let (Some(pins), Some(other_pins)) = (&pins, get_pins(&other)?) {
+            let compatible = pins.iter().all(|pin| {
+                other_pins
+                    .iter()
+                    .any(|p| p.position == pin.position && p.name == pin.name)
+            });
+            if !compatible {
+                continue;
+            }
+        }
+        list.push(cpn);
+    }
+    Ok(list)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::database::db_init;
+    use crate::database::tests::{test_db, DB_TEST_LOCK};
 
     #[test]
     fn test_cpn_query_valid() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        database::DB_FILE_PATH.lock().unwrap().borrow_mut().take();
         // Call the function and expect an error
         let result = cpn_query("test_cpn");
         assert!(result.is_err());
@@ -66,7 +298,7 @@ mod tests {
         );
 
         // Create a mock database and table
-        let db = sqlite::open("test.db").unwrap();
+        let db = test_db("test.db");
         db.execute(
             "
             CREATE TABLE IF NOT EXISTS cpn (cpn TEXT);
@@ -89,7 +321,7 @@ mod tests {
         std::fs::remove_file("test.db").unwrap();
 
         // Create a mock database and table
-        let db = sqlite::open("test.db").unwrap();
+        let db = test_db("test.db");
         db.execute(
             "
             CREATE TABLE IF NOT EXISTS cpn (cpn TEXT);
@@ -109,7 +341,7 @@ mod tests {
         std::fs::remove_file("test.db").unwrap();
 
         // Create a mock database and table
-        let db = sqlite::open("test.db").unwrap();
+        let db = test_db("test.db");
         db.execute(
             "
             CREATE TABLE IF NOT EXISTS cpn (cpn TEXT, refname TEXT);
@@ -130,7 +362,7 @@ mod tests {
         std::fs::remove_file("test.db").unwrap();
 
         // Create a mock database and table
-        let db = sqlite::open("test.db").unwrap();
+        let db = test_db("test.db");
         db.execute(
             "
             CREATE TABLE IF NOT EXISTS cpn (cpn TEXT, refname TEXT);
@@ -148,5 +380,124 @@ mod tests {
 
         // Clean up
         std::fs::remove_file("test.db").unwrap();
+
+        // Create a mock database with the resources
+        let db = test_db("test.db");
+        db.execute(
+            "
+            CREATE TABLE IF NOT EXISTS cpn (cpn TEXT, refname TEXT);
+            CREATE TABLE IF NOT EXISTS resource (refname TEXT, family TEXT, core TEXT,
+                core_second TEXT, flash INTEGER, ram INTEGER, package TEXT);
+            INSERT INTO cpn (cpn, refname) VALUES ('STM32G071CBT6', 'STM32G071CBTx'),
+                ('STM32G071RBT6', 'STM32G071RBTx'), ('STM32G030F6P6', 'STM32G030F6Px'),
+                ('STM32H745ZIT6', 'STM32H745ZITx');
+            INSERT INTO resource VALUES ('STM32G071CBTx', 'STM32G0', '0+', '', 128, 36, NULL),
+                ('STM32G071RBTx', 'STM32G0', '0+', '', 128, 36, 'LQFP64'),
+                ('STM32G030F6Px', 'STM32G0', '0+', '', 32, 8, ''),
+                ('STM32H745ZITx', 'STM32H7', '7', '4', 2048, 1024, 'UFBGA176');
+            ",
+        )
+        .unwrap();
+
+        db_init("test.db").unwrap();
+
+        let cpns = |filter: &CpnFilter| -> Vec<String> {
+            cpn_query_filtered("STM32", filter)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.cpn)
+                .collect()
+        };
+        let filter = CpnFilter {
+            family: Some("g0".into()),
+            min_flash: Some(128),
+            ..Default::default()
+        };
+        assert_eq!(cpns(&filter), vec!["STM32G071CBT6", "STM32G071RBT6"]);
+        let filter = CpnFilter {
+            package: Some("lqfp48".into()),
+            ..Default::default()
+        };
+        assert_eq!(cpns(&filter), vec!["STM32G071CBT6"]);
+        let filter = CpnFilter {
+            package: Some("TSSOP".into()),
+            ..Default::default()
+        };
+        assert_eq!(cpns(&filter), vec!["STM32G030F6P6"]);
+        // the package of the database comes before the one of the reference name
+        let filter = CpnFilter {
+            package: Some("UFBGA".into()),
+            ..Default::default()
+        };
+        assert_eq!(cpns(&filter), vec!["STM32H745ZIT6"]);
+        let filter = CpnFilter {
+            core: Some("Cortex-M4".into()),
+            ..Default::default()
+        };
+        assert_eq!(cpns(&filter), vec!["STM32H745ZIT6"]);
+        let filter = CpnFilter {
+            min_ram: Some(2048),
+            ..Default::default()
+        };
+        assert!(cpns(&filter).is_empty());
+
+        let groups = cpn_search("g071").unwrap();
+        assert_eq!(
+            groups
+                .iter()
+                .map(|g| g.refname.as_str())
+                .collect::<Vec<_>>(),
+            vec!["STM32G071CBTx", "STM32G071RBTx"]
+        );
+        assert_eq!(cpn_search("g071cb").unwrap().len(), 1);
+        assert_eq!(cpn_search("g017cb").unwrap()[0].cpns, vec!["STM32G071CBT6"]);
+        assert!(cpn_search("l476").unwrap().is_empty());
+
+        let entry = cpn_query_filtered("H745", &CpnFilter::default()).unwrap();
+        assert_eq!(entry[0].core, "Cortex-M7");
+        assert_eq!(entry[0].core2.as_deref(), Some("Cortex-M4"));
+        assert_eq!(entry[0].package.as_deref(), Some("UFBGA176"));
+
+        // Clean up
+        std::fs::remove_file("test.db").unwrap();
+    }
+
+    #[test]
+    fn it_suggests_pin_compatible_active_parts() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        let db = test_db("test_status.db");
+        db.execute(
+            "
+            CREATE TABLE IF NOT EXISTS cpn (cpn TEXT, refname TEXT);
+            CREATE TABLE IF NOT EXISTS resource (refname TEXT, family TEXT, package TEXT,
+                status TEXT);
+            CREATE TABLE IF NOT EXISTS pin (refname TEXT, pin TEXT, position TEXT);
+            CREATE TABLE IF NOT EXISTS pin_signal (refname TEXT, pin TEXT, signal TEXT,
+                af INTEGER);
+            INSERT INTO cpn (cpn, refname) VALUES ('STM32F401RBT6', 'STM32F401RBTx'),
+                ('STM32F401RCT6', 'STM32F401RCTx'), ('STM32F401RCT7', 'STM32F401RCTx'),
+                ('STM32F401RET6', 'STM32F401RETx'), ('STM32F411RET6', 'STM32F411RETx'),
+                ('STM32F401VCT6', 'STM32F401VCTx'), ('STM32G071RBT6', 'STM32G071RBTx');
+            INSERT INTO resource VALUES ('STM32F401RBTx', 'STM32F4', 'LQFP64', 'NRND'),
+                ('STM32F401RCTx', 'STM32F4', 'LQFP64', 'Active'),
+                ('STM32F401RETx', 'STM32F4', 'LQFP64', 'Obsolete'),
+                ('STM32F411RETx', 'STM32F4', 'LQFP64', 'Active'),
+                ('STM32F401VCTx', 'STM32F4', 'LQFP100', 'Active'),
+                ('STM32G071RBTx', 'STM32G0', 'LQFP64', 'Active');
+            INSERT INTO pin VALUES ('STM32F401RBTx', 'PA2', '16'),
+                ('STM32F401RCTx', 'PA2', '16'), ('STM32F411RETx', 'PA3', '16');
+            ",
+        )
+        .unwrap();
+        db_init("test_status.db").unwrap();
+
+        assert_eq!(get_status("STM32F401RBT6").unwrap(), ChipStatus::Nrnd);
+        assert_eq!(get_status("STM32F401RCT7").unwrap(), ChipStatus::Active);
+        // other status, package, family or pins, and one part number per reference name
+        assert_eq!(
+            pin_compatible_parts("STM32F401RBT6").unwrap(),
+            vec!["STM32F401RCT6"]
+        );
+        std::fs::remove_file("test_status.db").unwrap();
     }
 }
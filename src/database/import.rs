@@ -0,0 +1,796 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::database::{column, has_table, SCHEMA_VERSION};
+use crate::stm32_device::chip_info::{ArmCore, Fpu};
+
+/// Tables of the database, created when they are missing
+const SCHEMA: &str = r###"
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT);
+    CREATE TABLE IF NOT EXISTS cpn (cpn TEXT, refname TEXT);
+    CREATE TABLE IF NOT EXISTS resource (refname TEXT, family TEXT, core TEXT, core_second TEXT,
+        frequency INTEGER, flash INTEGER, ram INTEGER, ccmram INTEGER, pac INTEGER, package TEXT,
+        fpu TEXT, description TEXT, status TEXT, co_type TEXT, co_freq INTEGER);
+    CREATE TABLE IF NOT EXISTS pac_content (id INTEGER, pac_name TEXT, pac_ver TEXT,
+        pac_feature TEXT);
+    CREATE TABLE IF NOT EXISTS memory (refname TEXT, name TEXT, kind TEXT, origin INTEGER,
+        size INTEGER, core TEXT);
+    CREATE TABLE IF NOT EXISTS peripheral (refname TEXT, instance TEXT, name TEXT, version TEXT,
+        bus TEXT, enable_register TEXT, enable_bit INTEGER);
+    CREATE TABLE IF NOT EXISTS peripheral_irq (refname TEXT, instance TEXT, irq TEXT,
+        number INTEGER);
+    CREATE TABLE IF NOT EXISTS peripheral_dma (refname TEXT, instance TEXT, signal TEXT,
+        controller TEXT, channel INTEGER, request INTEGER);
+    CREATE TABLE IF NOT EXISTS pin (refname TEXT, pin TEXT, position TEXT);
+    CREATE TABLE IF NOT EXISTS pin_signal (refname TEXT, pin TEXT, signal TEXT, af INTEGER);
+    CREATE TABLE IF NOT EXISTS oscillator (family TEXT, name TEXT, frequency INTEGER);
+"###;
+
+/// A part of `families.xml` with the details of its MCU file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedMcu {
+    pub refname: String,
+    pub family: String,
+    pub package: String,
+    /// short names of the cores, as in table `resource`, e.g. `0+`
+    pub cores: Vec<String>,
+    pub frequency: u32,
+    /// frequency of the second core of the multicore parts
+    pub co_frequency: Option<u32>,
+    pub flash: u32,
+    pub ram: u32,
+    pub ccmram: u32,
+    /// `(instance, name, version)` of the IPs, e.g. `("USART2", "USART", "sci3_v1_1_Cube")`
+    pub peripherals: Vec<(String, String, String)>,
+    pub pins: Vec<ImportedPin>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedPin {
+    /// GPIO name, e.g. `PA2`
+    pub name: String,
+    pub position: String,
+    /// signals of the pin with their alternate function number, if the family has them
+    pub signals: Vec<(String, Option<u8>)>,
+}
+
+/// Number of rows written by `import`
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub parts: usize,
+    pub skipped: Vec<String>,
+    /// imported parts without rows in table `memory`, they get the classic memory layout
+    pub without_memory: Vec<String>,
+}
+
+/// Build or refresh the database `db_path` from a local copy of ST's open pin data.
+///
+/// `source` is the repository or its `mcu` directory, which holds `families.xml`, one XML
+/// file per MCU and the GPIO modes in `IP`. Only the families in `families` are imported,
+/// all of them when it is empty. Rows of other parts are kept.
+///
+/// The open pin data has no PAC versions, memory regions, oscillators, description or
+/// status of the parts, and no interrupts or DMA requests of the peripherals. The rows of
+/// the database are kept for them: tables `pac_content`, `memory` and `oscillator` are
+/// never deleted from, and the description and status of a part survive its re-import.
+/// The bus, clock enable bit, interrupts and DMA requests of a peripheral are filled in by
+/// hand and kept as long as the part has the instance. Families without oscillators use
+/// the built-in HSI frequency, parts without memory regions are listed in the summary.
+pub fn import(source: &Path, db_path: &Path, families: &[String]) -> Result<ImportSummary> {
+    let mcu_dir = mcu_dir(source)?;
+    let (mcus, skipped) = read_mcus(&mcu_dir, families)?;
+    if mcus.is_empty() {
+        bail!("No part has been found in {}", mcu_dir.display());
+    }
+    let db = sqlite::open(db_path)
+        .with_context(|| format!("Cannot open the database {}", db_path.display()))?;
+    db.execute("BEGIN;")?;
+    let result = write_mcus(&db, &mcus);
+    if result.is_err() {
+        db.execute("ROLLBACK;")?;
+    } else {
+        db.execute("COMMIT;")?;
+    }
+    result?;
+    let mut without_memory = Vec::new();
+    for mcu in mcus.iter() {
+        let mut sta = db.prepare("SELECT count(*) AS regions FROM memory WHERE refname = ?;")?;
+        sta.bind((1, mcu.refname.as_str()))?;
+        sta.next()?;
+        if column::<i64>(&sta, "regions")? == 0 {
+            without_memory.push(mcu.refname.clone());
+        }
+    }
+    Ok(ImportSummary {
+        parts: mcus.len(),
+        skipped,
+        without_memory,
+    })
+}
+
+/// The directory of `families.xml`, given the repository or the directory itself
+fn mcu_dir(source: &Path) -> Result<PathBuf> {
+    for dir in [source.join("mcu"), source.to_path_buf()] {
+        if dir.join("families.xml").exists() {
+            return Ok(dir);
+        }
+    }
+    bail!(
+        "`families.xml` is not found in {}, pls give the directory of the open pin data",
+        source.display()
+    );
+}
+
+/// Read the parts of `families.xml` and their MCU files.
+/// Parts with a core which is not a Cortex-M supported by rust are skipped.
+pub fn read_mcus(mcu_dir: &Path, families: &[String]) -> Result<(Vec<ImportedMcu>, Vec<String>)> {
+    let content = read_xml_file(&mcu_dir.join("families.xml"))?;
+    let doc = roxmltree::Document::parse(&content).context("Cannot parse families.xml")?;
+    let mut gpio_modes: HashMap<String, HashMap<(String, String), u8>> = HashMap::new();
+    let mut mcus = Vec::new();
+    let mut skipped = Vec::new();
+    for family in doc.descendants().filter(|n| n.has_tag_name("Family")) {
+        let family_name = family.attribute("Name").unwrap_or_default().to_string();
+        if !families.is_empty()
+            && !families
+                .iter()
+                .any(|f| f.eq_ignore_ascii_case(&family_name))
+        {
+            continue;
+        }
+        for mcu in family.descendants().filter(|n| n.has_tag_name("Mcu")) {
+            let (Some(refname), Some(file_name)) =
+                (mcu.attribute("RefName"), mcu.attribute("Name"))
+            else {
+                continue;
+            };
+            let cores: Option<Vec<String>> = children_text(&mcu, "Core")
+                .iter()
+                .map(|c| core_short_name(c))
+                .collect();
+            let Some(cores) = cores.filter(|c| !c.is_empty()) else {
+                skipped.push(refname.to_string());
+                continue;
+            };
+            let number = |tag: &str| -> u32 {
+                children_text(&mcu, tag)
+                    .first()
+                    .and_then(|v| v.trim().parse::<f64>().ok())
+                    .map_or(0, |v| v as u32)
+            };
+
+            let (peripherals, pins, gpio_version) = read_mcu_file(mcu_dir, file_name)?;
+            let modes = match gpio_version {
+                Some(version) => {
+                    if !gpio_modes.contains_key(&version) {
+                        let modes = read_gpio_modes(mcu_dir, &version)?;
+                        gpio_modes.insert(version.clone(), modes);
+                    }
+                    gpio_modes.get(&version)
+                }
+                None => None,
+            };
+            let pins = pins
+                .into_iter()
+                .map(|(name, position, signals)| ImportedPin {
+                    signals: signals
+                        .into_iter()
+                        .map(|signal| {
+                            let af = modes
+                                .and_then(|m| m.get(&(name.clone(), signal.clone())))
+                                .copied();
+                            (signal, af)
+                        })
+                        .collect(),
+                    name,
+                    position,
+                })
+                .collect();
+
+            mcus.push(ImportedMcu {
+                refname: refname.to_string(),
+                family: family_name.clone(),
+                package: mcu.attribute("PackageName").unwrap_or_default().to_string(),
+                cores,
+                frequency: number("Frequency"),
+                co_frequency: children_text(&mcu, "Frequency")
+                    .get(1)
+                    .and_then(|v| v.trim().parse::<f64>().ok())
+                    .map(|v| v as u32),
+                flash: number("Flash"),
+                ram: number("Ram"),
+                ccmram: number("CCMRam"),
+                peripherals,
+                pins,
+            });
+        }
+    }
+    Ok((mcus, skipped))
+}
+
+type McuFile = (
+    Vec<(String, String, String)>,
+    Vec<(String, String, Vec<String>)>,
+    Option<String>,
+);
+
+/// Read the IPs and the GPIO pins of a MCU file, with the version of its GPIO modes file
+fn read_mcu_file(mcu_dir: &Path, file_name: &str) -> Result<McuFile> {
+    let path = mcu_dir.join(format!("{file_name}.xml"));
+    let content = read_xml_file(&path)?;
+    let doc = roxmltree::Document::parse(&content)
+        .with_context(|| format!("Cannot parse {}", path.display()))?;
+    let mut peripherals = Vec::new();
+    let mut gpio_version = None;
+    for ip in doc.descendants().filter(|n| n.has_tag_name("IP")) {
+        let name = ip.attribute("Name").unwrap_or_default();
+        let version = ip.attribute("Version").unwrap_or_default();
+        if name == "GPIO" {
+            gpio_version = Some(version.to_string());
+        }
+        peripherals.push((
+            ip.attribute("InstanceName").unwrap_or(name).to_string(),
+            name.to_string(),
+            version.to_string(),
+        ));
+    }
+    let mut pins = Vec::new();
+    for pin in doc.descendants().filter(|n| n.has_tag_name("Pin")) {
+        if pin.attribute("Type") != Some("I/O") {
+            continue;
+        }
+        let Some(name) = pin.attribute("Name").and_then(gpio_name) else {
+            continue;
+        };
+        let signals = pin
+            .children()
+            .filter(|n| n.has_tag_name("Signal"))
+            .filter_map(|n| n.attribute("Name"))
+            .filter(|s| *s != "GPIO")
+            .map(str::to_string)
+            .collect();
+        let position = pin.attribute("Position").unwrap_or_default().to_string();
+        pins.push((name, position, signals));
+    }
+    Ok((peripherals, pins, gpio_version))
+}
+
+/// Read the alternate function numbers of `IP/GPIO-<version>_Modes.xml`, by pin and signal.
+/// Families without alternate function numbers, such as STM32F1, give an empty map.
+fn read_gpio_modes(mcu_dir: &Path, version: &str) -> Result<HashMap<(String, String), u8>> {
+    let path = mcu_dir.join("IP").join(format!("GPIO-{version}_Modes.xml"));
+    let mut modes = HashMap::new();
+    if !path.exists() {
+        return Ok(modes);
+    }
+    let content = read_xml_file(&path)?;
+    let doc = roxmltree::Document::parse(&content)
+        .with_context(|| format!("Cannot parse {}", path.display()))?;
+    let af_regex = Regex::new(r"^GPIO_AF(\d+)_").unwrap();
+    for pin in doc.descendants().filter(|n| n.has_tag_name("GPIO_Pin")) {
+        let Some(pin_name) = pin.attribute("Name").and_then(gpio_name) else {
+            continue;
+        };
+        for signal in pin.children().filter(|n| n.has_tag_name("PinSignal")) {
+            let Some(signal_name) = signal.attribute("Name") else {
+                continue;
+            };
+            let af = signal
+                .descendants()
+                .filter(|n| n.has_tag_name("PossibleValue"))
+                .filter_map(|n| n.text())
+                .find_map(|v| af_regex.captures(v.trim()))
+                .and_then(|c| c[1].parse::<u8>().ok());
+            if let Some(af) = af {
+                modes.insert((pin_name.clone(), signal_name.to_string()), af);
+            }
+        }
+    }
+    Ok(modes)
+}
+
+fn read_xml_file(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))
+}
+
+fn children_text(node: &roxmltree::Node, tag: &str) -> Vec<String> {
+    node.children()
+        .filter(|n| n.has_tag_name(tag))
+        .filter_map(|n| n.text())
+        .map(|t| t.trim().to_string())
+        .collect()
+}
+
+static GPIO_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^P[A-K]\d{1,2}").unwrap());
+
+/// `PC14-OSC32_IN (PC14)` is the GPIO `PC14`
+fn gpio_name(name: &str) -> Option<String> {
+    GPIO_NAME.find(name).map(|m| m.as_str().to_string())
+}
+
+/// `Arm Cortex-M0+` is written `0+` in table `resource`
+fn core_short_name(core: &str) -> Option<String> {
+    let short = core.trim().strip_prefix("Arm Cortex-M")?;
+    matches!(short, "0" | "0+" | "3" | "4" | "7" | "33" | "55").then(|| short.to_string())
+}
+
+/// Feature of the PAC crate guessed from the reference name, e.g. `stm32g071`
+fn pac_feature(refname: &str) -> String {
+    refname.chars().take(9).collect::<String>().to_lowercase()
+}
+
+fn write_mcus(db: &sqlite::Connection, mcus: &[ImportedMcu]) -> Result<()> {
+    db.execute(SCHEMA)?;
+    // databases of an older schema miss the columns added later
+    for (table, column, column_type) in [
+        ("resource", "package", "TEXT"),
+        ("resource", "fpu", "TEXT"),
+        ("resource", "description", "TEXT"),
+        ("resource", "status", "TEXT"),
+        ("resource", "co_type", "TEXT"),
+        ("resource", "co_freq", "INTEGER"),
+        ("peripheral", "bus", "TEXT"),
+        ("peripheral", "enable_register", "TEXT"),
+        ("peripheral", "enable_bit", "INTEGER"),
+    ] {
+        if !table_columns(db, table)?.iter().any(|c| c == column) {
+            db.execute(format!(
+                "ALTER TABLE {table} ADD COLUMN {column} {column_type};"
+            ))?;
+        }
+    }
+    db.execute(format!(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('schema_version', '{SCHEMA_VERSION}');"
+    ))?;
+
+    for mcu in mcus.iter() {
+        let pac = pac_id(db, mcu)?;
+        // the open pin data has no description and no status, the ones of the database
+        // are kept
+        let mut sta = db.prepare("SELECT description, status FROM resource WHERE refname = ?;")?;
+        sta.bind((1, mcu.refname.as_str()))?;
+        let (description, status) = if let Ok(sqlite::State::Row) = sta.next() {
+            (
+                column::<Option<String>>(&sta, "description")?,
+                column::<Option<String>>(&sta, "status")?,
+            )
+        } else {
+            (None, None)
+        };
+        // the open pin data has no bus and no clock enable bit either, the ones of the
+        // database are kept by instance
+        let mut sta = db.prepare(
+            "SELECT instance, bus, enable_register, enable_bit FROM peripheral \
+            WHERE refname = ?;",
+        )?;
+        sta.bind((1, mcu.refname.as_str()))?;
+        let mut clocks = HashMap::new();
+        while let Ok(sqlite::State::Row) = sta.next() {
+            clocks.insert(
+                column::<String>(&sta, "instance")?,
+                (
+                    column::<Option<String>>(&sta, "bus")?,
+                    column::<Option<String>>(&sta, "enable_register")?,
+                    column::<Option<i64>>(&sta, "enable_bit")?,
+                ),
+            );
+        }
+        // the interrupts and DMA requests are filled in by hand, the rows of the instances
+        // the part still has are written back
+        let instances: Vec<&str> = mcu.peripherals.iter().map(|(i, _, _)| i.as_str()).collect();
+        let mut sta =
+            db.prepare("SELECT instance, irq, number FROM peripheral_irq WHERE refname = ?;")?;
+        sta.bind((1, mcu.refname.as_str()))?;
+        let mut irqs = Vec::new();
+        while let Ok(sqlite::State::Row) = sta.next() {
+            irqs.push((
+                column::<String>(&sta, "instance")?,
+                column::<String>(&sta, "irq")?,
+                column::<Option<i64>>(&sta, "number")?,
+            ));
+        }
+        let mut sta = db.prepare(
+            "SELECT instance, signal, controller, channel, request FROM peripheral_dma \
+            WHERE refname = ?;",
+        )?;
+        sta.bind((1, mcu.refname.as_str()))?;
+        let mut dmas = Vec::new();
+        while let Ok(sqlite::State::Row) = sta.next() {
+            dmas.push((
+                column::<String>(&sta, "instance")?,
+                column::<String>(&sta, "signal")?,
+                column::<String>(&sta, "controller")?,
+                column::<Option<i64>>(&sta, "channel")?,
+                column::<Option<i64>>(&sta, "request")?,
+            ));
+        }
+        for table in [
+            "resource",
+            "peripheral",
+            "peripheral_irq",
+            "peripheral_dma",
+            "pin",
+            "pin_signal",
+        ] {
+            let mut sta = db.prepare(format!("DELETE FROM {table} WHERE refname = ?;"))?;
+            sta.bind((1, mcu.refname.as_str()))?;
+            sta.next()?;
+        }
+
+        let mut sta = db.prepare(
+            "INSERT INTO resource (refname, family, core, core_second, frequency, flash, ram, \
+            ccmram, pac, package, fpu, description, status, co_type, co_freq) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);",
+        )?;
+        sta.bind((1, mcu.refname.as_str()))?;
+        sta.bind((2, mcu.family.as_str()))?;
+        sta.bind((3, mcu.cores[0].as_str()))?;
+        sta.bind((4, mcu.cores.get(1).map_or("", |c| c.as_str())))?;
+        sta.bind((5, mcu.frequency as i64))?;
+        sta.bind((6, mcu.flash as i64))?;
+        sta.bind((7, mcu.ram as i64))?;
+        sta.bind((8, mcu.ccmram as i64))?;
+        sta.bind((9, pac))?;
+        sta.bind((10, mcu.package.as_str()))?;
+        sta.bind((11, Fpu::of_part(&mcu.family, &mcu.refname).short_name()))?;
+        sta.bind((12, description.as_deref()))?;
+        sta.bind((13, status.as_deref()))?;
+        let co_type = mcu
+            .cores
+            .get(1)
+            .map(ArmCore::try_from_short)
+            .transpose()?
+            .map(|core| core.to_string());
+        sta.bind((14, co_type.as_deref()))?;
+        sta.bind((15, mcu.co_frequency.map(|f| f as i64)))?;
+        sta.next()?;
+
+        // the open pin data has no order codes, a part without one in the database is
+        // listed under its reference name, e.g. `STM32G071CBTx`
+        let mut sta = db.prepare("SELECT cpn FROM cpn WHERE refname = ?;")?;
+        sta.bind((1, mcu.refname.as_str()))?;
+        if let Ok(sqlite::State::Done) = sta.next() {
+            let mut sta = db.prepare("INSERT INTO cpn (cpn, refname) VALUES (?, ?);")?;
+            sta.bind((1, mcu.refname.as_str()))?;
+            sta.bind((2, mcu.refname.as_str()))?;
+            sta.next()?;
+        }
+
+        for (instance, name, version) in mcu.peripherals.iter() {
+            let mut sta = db.prepare(
+                "INSERT INTO peripheral (refname, instance, name, version, bus, \
+                enable_register, enable_bit) VALUES (?, ?, ?, ?, ?, ?, ?);",
+            )?;
+            sta.bind((1, mcu.refname.as_str()))?;
+            sta.bind((2, instance.as_str()))?;
+            sta.bind((3, name.as_str()))?;
+            sta.bind((4, version.as_str()))?;
+            let (bus, enable_register, enable_bit) = clocks.remove(instance).unwrap_or_default();
+            sta.bind((5, bus.as_deref()))?;
+            sta.bind((6, enable_register.as_deref()))?;
+            sta.bind((7, enable_bit))?;
+            sta.next()?;
+        }
+
+        for (instance, irq, number) in irqs.iter() {
+            if !instances.contains(&instance.as_str()) {
+                continue;
+            }
+            let mut sta = db.prepare(
+                "INSERT INTO peripheral_irq (refname, instance, irq, number) VALUES (?, ?, ?, ?);",
+            )?;
+            sta.bind((1, mcu.refname.as_str()))?;
+            sta.bind((2, instance.as_str()))?;
+            sta.bind((3, irq.as_str()))?;
+            sta.bind((4, *number))?;
+            sta.next()?;
+        }
+        for (instance, signal, controller, channel, request) in dmas.iter() {
+            if !instances.contains(&instance.as_str()) {
+                continue;
+            }
+            let mut sta = db.prepare(
+                "INSERT INTO peripheral_dma (refname, instance, signal, controller, channel, \
+                request) VALUES (?, ?, ?, ?, ?, ?);",
+            )?;
+            sta.bind((1, mcu.refname.as_str()))?;
+            sta.bind((2, instance.as_str()))?;
+            sta.bind((3, signal.as_str()))?;
+            sta.bind((4, controller.as_str()))?;
+            sta.bind((5, *channel))?;
+            sta.bind((6, *request))?;
+            sta.next()?;
+        }
+
+        for pin in mcu.pins.iter() {
+            let mut sta =
+                db.prepare("INSERT INTO pin (refname, pin, position) VALUES (?, ?, ?);")?;
+            sta.bind((1, mcu.refname.as_str()))?;
+            sta.bind((2, pin.name.as_str()))?;
+            sta.bind((3, pin.position.as_str()))?;
+            sta.next()?;
+            for (signal, af) in pin.signals.iter() {
+                let mut sta = db.prepare(
+                    "INSERT INTO pin_signal (refname, pin, signal, af) VALUES (?, ?, ?, ?);",
+                )?;
+                sta.bind((1, mcu.refname.as_str()))?;
+                sta.bind((2, pin.name.as_str()))?;
+                sta.bind((3, signal.as_str()))?;
+                match af {
+                    Some(af) => sta.bind((4, *af as i64))?,
+                    None => sta.bind((4, ()))?,
+                }
+                sta.next()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The PAC of a part: the one it already has, a PAC with the same feature, or a new entry.
+/// New entries take the version of the PAC of the family, without one the version is `-`
+/// and has to be filled in before the part can be used.
+fn pac_id(db: &sqlite::Connection, mcu: &ImportedMcu) -> Result<i64> {
+    let mut sta = db.prepare("SELECT pac FROM resource WHERE refname = ?;")?;
+    sta.bind((1, mcu.refname.as_str()))?;
+    if let Ok(sqlite::State::Row) = sta.next() {
+        return column::<i64>(&sta, "pac");
+    }
+
+    let pac_name = mcu.family.to_lowercase().replace('+', "");
+    let feature = pac_feature(&mcu.refname);
+    let mut sta =
+        db.prepare("SELECT id FROM pac_content WHERE pac_name = ? AND pac_feature = ?;")?;
+    sta.bind((1, pac_name.as_str()))?;
+    sta.bind((2, feature.as_str()))?;
+    if let Ok(sqlite::State::Row) = sta.next() {
+        return column::<i64>(&sta, "id");
+    }
+
+    let mut sta = db.prepare(
+        "SELECT pac_ver FROM pac_content WHERE pac_name = ? AND pac_ver != '-' LIMIT 1;",
+    )?;
+    sta.bind((1, pac_name.as_str()))?;
+    let pac_ver = match sta.next() {
+        Ok(sqlite::State::Row) => column::<String>(&sta, "pac_ver")?,
+        _ => "-".to_string(),
+    };
+    let mut sta = db.prepare("SELECT coalesce(max(id), 0) + 1 AS id FROM pac_content;")?;
+    sta.next()?;
+    let id = column::<i64>(&sta, "id")?;
+    let mut sta = db.prepare(
+        "INSERT INTO pac_content (id, pac_name, pac_ver, pac_feature) VALUES (?, ?, ?, ?);",
+    )?;
+    sta.bind((1, id))?;
+    sta.bind((2, pac_name.as_str()))?;
+    sta.bind((3, pac_ver.as_str()))?;
+    sta.bind((4, feature.as_str()))?;
+    sta.next()?;
+    Ok(id)
+}
+
+fn table_columns(db: &sqlite::Connection, table: &str) -> Result<Vec<String>> {
+    if !has_table(db, table)? {
+        return Ok(Vec::new());
+    }
+    let mut sta = db.prepare(format!("SELECT * FROM {table} LIMIT 0;"))?;
+    sta.next()?;
+    Ok(sta.column_names().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tmp_dir;
+
+    const FAMILIES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Families>
+  <Family Name="STM32G0">
+    <SubFamily Name="STM32G0x1">
+      <Mcu Name="STM32G071C(6-8-B)Tx" PackageName="LQFP48" RefName="STM32G071CBTx" RPN="STM32G071CB">
+        <Core>Arm Cortex-M0+</Core>
+        <Frequency>64</Frequency>
+        <Ram>36</Ram>
+        <IONb>44</IONb>
+        <Flash>128</Flash>
+        <Temperature Max="125" Min="-40"/>
+      </Mcu>
+    </SubFamily>
+  </Family>
+  <Family Name="STM32MP1">
+    <SubFamily Name="STM32MP151">
+      <Mcu Name="STM32MP151AACx" PackageName="UFBGA176" RefName="STM32MP151AACx" RPN="STM32MP151A">
+        <Core>Arm Cortex-A7</Core>
+        <Core>Arm Cortex-M4</Core>
+      </Mcu>
+    </SubFamily>
+  </Family>
+</Families>
+"#;
+
+    const MCU_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Mcu ClockTree="STM32G0" Family="STM32G0" Line="STM32G0x1" Package="LQFP48" RefName="STM32G071C(6-8-B)Tx" xmlns="http://mcd.rou.st.com/modules.php?name=mcu">
+  <Core>Arm Cortex-M0+</Core>
+  <IP InstanceName="USART2" Name="USART" Version="sci3_v1_1_Cube"/>
+  <IP ConfigFile="GPIO-STM32G0" InstanceName="GPIO" Name="GPIO" Version="STM32G07x_gpio_v1_0"/>
+  <Pin Name="VDD" Position="4" Type="Power"/>
+  <Pin Name="PC14-OSC32_IN (PC14)" Position="2" Type="I/O">
+    <Signal Name="RCC_OSC32_IN"/>
+  </Pin>
+  <Pin Name="PA2" Position="12" Type="I/O">
+    <Signal Name="USART2_TX"/>
+    <Signal IOModes="Input,Output,Analog" Name="GPIO"/>
+  </Pin>
+</Mcu>
+"#;
+
+    const GPIO_MODES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<IP Name="GPIO" Version="STM32G07x_gpio_v1_0" xmlns="http://mcd.rou.st.com/modules.php?name=mcu">
+  <GPIO_Pin PortName="PA" Name="PA2">
+    <PinSignal Name="USART2_TX">
+      <SpecificParameter Name="GPIO_AF">
+        <PossibleValue>GPIO_AF1_USART2</PossibleValue>
+      </SpecificParameter>
+    </PinSignal>
+  </GPIO_Pin>
+</IP>
+"#;
+
+    fn open_pin_data() -> tempfile::TempDir {
+        let dir = tmp_dir().unwrap();
+        let mcu_dir = dir.path().join("mcu");
+        fs::create_dir_all(mcu_dir.join("IP")).unwrap();
+        fs::write(mcu_dir.join("families.xml"), FAMILIES_XML).unwrap();
+        fs::write(mcu_dir.join("STM32G071C(6-8-B)Tx.xml"), MCU_XML).unwrap();
+        fs::write(
+            mcu_dir.join("IP/GPIO-STM32G07x_gpio_v1_0_Modes.xml"),
+            GPIO_MODES_XML,
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn it_reads_the_open_pin_data() {
+        let dir = open_pin_data();
+        let (mcus, skipped) = read_mcus(&dir.path().join("mcu"), &[]).unwrap();
+        assert_eq!(skipped, vec!["STM32MP151AACx"]);
+        assert_eq!(mcus.len(), 1);
+        let mcu = &mcus[0];
+        assert_eq!(mcu.refname, "STM32G071CBTx");
+        assert_eq!(mcu.family, "STM32G0");
+        assert_eq!(mcu.package, "LQFP48");
+        assert_eq!(mcu.cores, vec!["0+"]);
+        assert_eq!(
+            (mcu.frequency, mcu.flash, mcu.ram, mcu.ccmram),
+            (64, 128, 36, 0)
+        );
+        assert_eq!(mcu.peripherals.len(), 2);
+        assert_eq!(
+            mcu.pins,
+            vec![
+                ImportedPin {
+                    name: "PC14".into(),
+                    position: "2".into(),
+                    signals: vec![("RCC_OSC32_IN".into(), None)],
+                },
+                ImportedPin {
+                    name: "PA2".into(),
+                    position: "12".into(),
+                    signals: vec![("USART2_TX".into(), Some(1))],
+                },
+            ]
+        );
+
+        let (mcus, _) = read_mcus(&dir.path().join("mcu"), &["stm32f4".into()]).unwrap();
+        assert!(mcus.is_empty());
+    }
+
+    #[test]
+    fn it_builds_and_refreshes_a_database() {
+        let dir = open_pin_data();
+        let db_path = dir.path().join("stm32bs.db");
+        let db = sqlite::open(&db_path).unwrap();
+        db.execute(
+            "
+            CREATE TABLE cpn (cpn TEXT, refname TEXT);
+            CREATE TABLE pac_content (id INTEGER, pac_name TEXT, pac_ver TEXT, pac_feature TEXT);
+            INSERT INTO cpn VALUES ('STM32G071CBT6TR', 'STM32G071CBTx');
+            INSERT INTO pac_content VALUES (1, 'stm32g0', '0.15.1', 'stm32g030');
+            ",
+        )
+        .unwrap();
+
+        let summary = import(dir.path(), &db_path, &[]).unwrap();
+        assert_eq!(summary.parts, 1);
+        assert_eq!(summary.without_memory, vec!["STM32G071CBTx"]);
+        db.execute(
+            "
+            UPDATE resource SET status = 'NRND', description = 'Mainstream'
+                WHERE refname = 'STM32G071CBTx';
+            UPDATE peripheral SET bus = 'APB1', enable_bit = 17 WHERE instance = 'USART2';
+            INSERT INTO memory VALUES ('STM32G071CBTx', 'FLASH', 'flash', 134217728, 128, NULL),
+                ('STM32G071CBTx', 'RAM', 'ram', 536870912, 36, NULL);
+            INSERT INTO oscillator VALUES ('STM32G0', 'HSI', 16000000);
+            INSERT INTO peripheral_irq VALUES ('STM32G071CBTx', 'USART2', 'USART2_LPUART2', 28),
+                ('STM32G071CBTx', 'I2C9', 'I2C9', 99);
+            INSERT INTO peripheral_dma VALUES ('STM32G071CBTx', 'USART2', 'USART2_TX', 'DMA1',
+                NULL, 53), ('STM32G071CBTx', 'I2C9', 'I2C9_TX', 'DMA1', NULL, 99);
+            ",
+        )
+        .unwrap();
+        // a second import replaces the rows of the part, but keeps its status, its
+        // description, its memory regions, the oscillators of its family and the clock,
+        // interrupts and DMA requests of the peripherals it still has
+        let summary = import(dir.path(), &db_path, &[]).unwrap();
+        assert!(summary.without_memory.is_empty());
+
+        let count = |query: &str| -> i64 {
+            let mut sta = db.prepare(query).unwrap();
+            sta.next().unwrap();
+            sta.read::<i64, _>(0).unwrap()
+        };
+        assert_eq!(count("SELECT count(*) FROM resource"), 1);
+        // the part keeps the order code of the database, none is made up
+        assert_eq!(count("SELECT count(*) FROM cpn"), 1);
+        assert_eq!(count("SELECT count(*) FROM pin"), 2);
+        assert_eq!(count("SELECT count(*) FROM memory"), 2);
+        assert_eq!(count("SELECT count(*) FROM oscillator"), 1);
+        assert_eq!(
+            count("SELECT number FROM peripheral_irq WHERE refname = 'STM32G071CBTx'"),
+            28
+        );
+        assert_eq!(count("SELECT count(*) FROM peripheral_irq"), 1);
+        assert_eq!(
+            count("SELECT request FROM peripheral_dma WHERE refname = 'STM32G071CBTx'"),
+            53
+        );
+        assert_eq!(count("SELECT count(*) FROM peripheral_dma"), 1);
+        assert_eq!(
+            count("SELECT enable_bit FROM peripheral WHERE instance = 'USART2'"),
+            17
+        );
+        assert_eq!(
+            count("SELECT af FROM pin_signal WHERE pin = 'PA2' AND signal = 'USART2_TX'"),
+            1
+        );
+        assert_eq!(
+            count("SELECT pac FROM resource WHERE refname = 'STM32G071CBTx'"),
+            2
+        );
+        let mut sta = db
+            .prepare("SELECT pac_ver, pac_feature FROM pac_content WHERE id = 2")
+            .unwrap();
+        sta.next().unwrap();
+        assert_eq!(sta.read::<String, _>(0).unwrap(), "0.15.1");
+        assert_eq!(sta.read::<String, _>(1).unwrap(), "stm32g071");
+        let mut sta = db
+            .prepare("SELECT status, fpu, co_type, description FROM resource")
+            .unwrap();
+        sta.next().unwrap();
+        assert_eq!(sta.read::<String, _>(0).unwrap(), "NRND");
+        assert_eq!(sta.read::<String, _>(1).unwrap(), "none");
+        assert_eq!(sta.read::<Option<String>, _>(2).unwrap(), None);
+        assert_eq!(sta.read::<String, _>(3).unwrap(), "Mainstream");
+        let mut sta = db
+            .prepare("SELECT value FROM meta WHERE key = 'schema_version'")
+            .unwrap();
+        sta.next().unwrap();
+        assert_eq!(
+            sta.read::<String, _>(0).unwrap(),
+            SCHEMA_VERSION.to_string()
+        );
+    }
+
+    #[test]
+    fn it_decodes_the_names_of_the_open_pin_data() {
+        assert_eq!(gpio_name("PC14-OSC32_IN (PC14)").as_deref(), Some("PC14"));
+        assert_eq!(gpio_name("VDD"), None);
+        assert_eq!(core_short_name("Arm Cortex-M33").as_deref(), Some("33"));
+        assert_eq!(core_short_name("Arm Cortex-A7"), None);
+    }
+}
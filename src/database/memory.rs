@@ -0,0 +1,98 @@
+use anyhow::Result;
+use log::warn;
+
+use crate::database::{self, column, has_table};
+use crate::stm32_device::chip_info::{MemoryKind, MemoryRegion};
+
+/// Query the memory regions of a part from table `memory`, in the order of the database.
+/// The first flash and the first RAM region are the ones a program is linked to.
+///
+/// Columns: `refname`, `name` (e.g. `DTCM`), `kind` (`flash`, `ram`, `otp`, `option_bytes`
+/// or `flash_bank`), `origin`, `size` in KB and `core` (e.g. `CM4`, empty for a region
+/// shared by all cores). A region of an unknown kind is skipped with a warning.
+///
+/// Databases without the table, or without rows of the part as after an `import`, only
+/// know the sizes of table `resource`, in that case `None` is returned and the caller
+/// falls back to the classic STM32 layout.
+pub fn get_memory_regions<T: ToString>(refname: T) -> Result<Option<Vec<MemoryRegion>>> {
+    let db = database::open()?;
+    if !has_table(&db, "memory")? {
+        return Ok(None);
+    }
+    let query = r###"
+        SELECT name, kind, origin, size, core
+        from memory
+        where refname = ?
+        order by rowid;
+    "###;
+    let refname = refname.to_string();
+    let mut sta = db.prepare(query)?;
+    sta.bind((1, refname.as_str()))?;
+    let mut regions = Vec::new();
+    while let Ok(sqlite::State::Row) = sta.next() {
+        let name = column::<String>(&sta, "name")?;
+        let kind = match MemoryKind::try_from_str(column::<String>(&sta, "kind")?) {
+            Ok(kind) => kind,
+            Err(error) => {
+                warn!("{} Region {} of {} is skipped.", error, name, refname);
+                continue;
+            }
+        };
+        let core = column::<Option<String>>(&sta, "core")?.filter(|c| !c.is_empty());
+        regions.push(MemoryRegion {
+            name,
+            kind,
+            origin: column::<i64>(&sta, "origin")? as u32,
+            size: column::<i64>(&sta, "size")? as u32,
+            core,
+        });
+    }
+    Ok((!regions.is_empty()).then_some(regions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db_init;
+    use crate::database::tests::{test_db, DB_TEST_LOCK};
+
+    #[test]
+    fn test_get_memory_regions() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        let db = test_db("test_memory.db");
+        db.execute(
+            "
+            CREATE TABLE IF NOT EXISTS memory (refname TEXT, name TEXT, kind TEXT,
+                origin INTEGER, size INTEGER, core TEXT);
+            INSERT INTO memory VALUES ('STM32H745ZITx', 'FLASH', 'flash', 134217728, 1024, 'CM7'),
+                ('STM32H745ZITx', 'FLASH_CM4', 'flash', 135266304, 1024, 'CM4'),
+                ('STM32H745ZITx', 'AXISRAM', 'ram', 603979776, 512, ''),
+                ('STM32H745ZITx', 'DTCM', 'ram', 536870912, 128, 'CM7'),
+                ('STM32H745ZITx', 'OTP', 'otp', 536854528, 1, ''),
+                ('STM32H745ZITx', 'ITCM', 'tcm', 0, 64, 'CM7');
+            ",
+        )
+        .unwrap();
+        db_init("test_memory.db").unwrap();
+
+        let regions = get_memory_regions("STM32H745ZITx").unwrap().unwrap();
+        // the region of the unknown kind `tcm` is skipped
+        assert_eq!(regions.len(), 5);
+        assert_eq!(regions[4].kind, MemoryKind::Otp);
+        assert_eq!(regions[0].name, "FLASH");
+        assert_eq!(regions[0].kind, MemoryKind::Flash);
+        assert_eq!(regions[0].core.as_deref(), Some("CM7"));
+        assert_eq!(regions[2].origin, 0x2400_0000);
+        assert_eq!(regions[2].core, None);
+        assert_eq!(get_memory_regions("STM32G071CBTx").unwrap(), None);
+        std::fs::remove_file("test_memory.db").unwrap();
+
+        // a database without the table
+        let db = test_db("test_memory.db");
+        db.execute("CREATE TABLE IF NOT EXISTS cpn (cpn TEXT, refname TEXT);")
+            .unwrap();
+        db_init("test_memory.db").unwrap();
+        assert_eq!(get_memory_regions("STM32G071CBTx").unwrap(), None);
+        std::fs::remove_file("test_memory.db").unwrap();
+    }
+}
@@ -4,23 +4,216 @@ pub mod cpn;
 /// Query table resource from database
 pub mod resource;
 
+/// Query table memory from database
+pub mod memory;
+
+/// Query tables pin and pin_signal from database
+pub mod pin;
+
+/// Query table oscillator from database
+pub mod oscillator;
+
+/// Query tables peripheral, peripheral_irq and peripheral_dma from database
+pub mod peripheral;
+
+/// Build the database from ST's open pin data
+pub mod import;
+
 use std::cell::RefCell;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Mutex;
 
 use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
 //use sqlite;
 
 pub static DB_FILE_PATH: Mutex<RefCell<Option<PathBuf>>> = Mutex::new(RefCell::new(None));
 
+/// Schema version of the databases this binary is written for, stored in table `meta`.
+/// Version 1 is the schema without `meta` with the tables `cpn`, `resource` and `pac_content`,
+/// version 2 adds the tables `meta` and `memory`,
+/// version 3 adds `resource.package` and the tables `peripheral`, `pin` and `pin_signal`,
+/// version 4 adds the table `oscillator`, version 5 adds `resource.fpu`, version 6 adds
+/// `resource.description`, `resource.status`, `resource.co_type` and `resource.co_freq`,
+/// version 7 adds `peripheral.bus`, `peripheral.enable_register`, `peripheral.enable_bit`
+/// and the tables `peripheral_irq` and `peripheral_dma`.
+pub const SCHEMA_VERSION: u32 = 7;
+
+/// Oldest schema version which can still be read
+pub const MIN_SCHEMA_VERSION: u32 = 1;
+
+/// Tables of schema version 1, a database without `meta` and without one of these
+/// tables is older than any version this binary can read
+const FIRST_VERSION_TABLES: [&str; 3] = ["cpn", "resource", "pac_content"];
+
 pub fn db_init<T: AsRef<Path>>(path: T) -> Result<()> {
     let db_path: &Path = path.as_ref();
     if !db_path.exists() {
         bail!("Database file is not exists!");
     }
+    let db = sqlite::open(db_path)
+        .with_context(|| format!("Cannot open the database {}", db_path.display()))?;
+    check_schema_version(schema_version(&db)?)?;
     let binding = DB_FILE_PATH.lock().unwrap();
     binding.borrow_mut().replace(db_path.to_path_buf());
     Ok(())
 }
+
+/// Open the database given to `db_init`
+pub fn open() -> Result<sqlite::Connection> {
+    let binding = DB_FILE_PATH.lock().unwrap();
+    let binding2 = binding.borrow();
+    let Some(path) = binding2.as_ref() else {
+        bail!("The database has not been initialized!");
+    };
+    sqlite::open(path).with_context(|| format!("Cannot open the database {}", path.display()))
+}
+
+/// Read a column of the current row, a missing column or a value of the wrong type is
+/// reported as an outdated database instead of a panic
+pub fn column<T: sqlite::ReadableWithIndex>(sta: &sqlite::Statement, name: &str) -> Result<T> {
+    sta.read::<T, _>(name).with_context(|| {
+        format!("Cannot read column `{name}` of the database, pls update the template!")
+    })
+}
+
+/// Read a column added by a later schema version, `None` if the database has no such
+/// column or the value is NULL
+pub fn optional_column<T>(sta: &sqlite::Statement, name: &str) -> Result<Option<T>>
+where
+    Option<T>: sqlite::ReadableWithIndex,
+{
+    if !sta.column_names().iter().any(|c| c == name) {
+        return Ok(None);
+    }
+    column::<Option<T>>(sta, name)
+}
+
+/// Schema version stored in table `meta`. Databases without the table have version 1
+/// if they have the tables of that version, else version 0.
+fn schema_version(db: &sqlite::Connection) -> Result<u32> {
+    if !has_table(db, "meta")? {
+        for table in FIRST_VERSION_TABLES {
+            if !has_table(db, table)? {
+                return Ok(0);
+            }
+        }
+        return Ok(1);
+    }
+    let mut sta = db.prepare("select value from meta where key = 'schema_version';")?;
+    if !matches!(sta.next(), Ok(sqlite::State::Row)) {
+        bail!("The database has no schema version, pls update the template!");
+    }
+    let version = column::<String>(&sta, "value")?;
+    version
+        .trim()
+        .parse()
+        .with_context(|| format!("`{version}` is not a valid schema version of the database"))
+}
+
+fn check_schema_version(version: u32) -> Result<()> {
+    if version > SCHEMA_VERSION {
+        bail!(
+            "The database of the template has schema version {}, this version of cargo-stm32bs \
+            supports up to version {}, pls update cargo-stm32bs!",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    if version < MIN_SCHEMA_VERSION {
+        bail!(
+            "The database of the template is too old, it has schema version {} and this \
+            version of cargo-stm32bs needs at least version {}, pls update the template!",
+            version,
+            MIN_SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Check if the database has a table, tables added after the first release are optional
+pub fn has_table(db: &sqlite::Connection, table: &str) -> Result<bool> {
+    let mut sta =
+        db.prepare("select name from sqlite_master where type = 'table' and name = ?;")?;
+    sta.bind((1, table))?;
+    Ok(matches!(sta.next(), Ok(sqlite::State::Row)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The database path is global, tests using a database must hold this lock
+    pub(crate) static DB_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Open a database of the current schema version, the tests create the tables they need
+    pub(crate) fn test_db(path: &str) -> sqlite::Connection {
+        let db = sqlite::open(path).unwrap();
+        db.execute(format!(
+            "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT);
+            INSERT OR REPLACE INTO meta VALUES ('schema_version', '{SCHEMA_VERSION}');"
+        ))
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn it_checks_the_schema_version() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        let db_file = "test_schema.db";
+
+        // a database without `meta` and without the tables of the first version is too old
+        let db = sqlite::open(db_file).unwrap();
+        db.execute("CREATE TABLE cpn (cpn TEXT, refname TEXT);")
+            .unwrap();
+        assert_eq!(schema_version(&db).unwrap(), 0);
+        let error = db_init(db_file).unwrap_err().to_string();
+        assert!(error.contains("is too old"), "{error}");
+        assert!(error.contains("pls update the template"), "{error}");
+
+        // with them it is a database of the first version
+        db.execute(
+            "CREATE TABLE resource (refname TEXT, family TEXT);
+            CREATE TABLE pac_content (id INTEGER, pac_name TEXT);",
+        )
+        .unwrap();
+        assert_eq!(schema_version(&db).unwrap(), 1);
+        assert!(db_init(db_file).is_ok());
+
+        db.execute("CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT);")
+            .unwrap();
+        assert!(schema_version(&db).is_err());
+        db.execute(format!(
+            "INSERT INTO meta VALUES ('schema_version', '{SCHEMA_VERSION}');"
+        ))
+        .unwrap();
+        assert_eq!(schema_version(&db).unwrap(), SCHEMA_VERSION);
+        assert!(db_init(db_file).is_ok());
+
+        db.execute(format!(
+            "UPDATE meta SET value = '{}' WHERE key = 'schema_version';",
+            SCHEMA_VERSION + 1
+        ))
+        .unwrap();
+        let error = db_init(db_file).unwrap_err().to_string();
+        assert!(error.contains("pls update cargo-stm32bs"), "{error}");
+
+        assert!(check_schema_version(0).is_err());
+        std::fs::remove_file(db_file).unwrap();
+    }
+
+    #[test]
+    fn it_reports_missing_columns() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        let db = sqlite::open(":memory:").unwrap();
+        db.execute("CREATE TABLE cpn (cpn TEXT); INSERT INTO cpn VALUES ('STM32G071CBT6');")
+            .unwrap();
+        let mut sta = db.prepare("select * from cpn;").unwrap();
+        sta.next().unwrap();
+        assert_eq!(column::<String>(&sta, "cpn").unwrap(), "STM32G071CBT6");
+        let error = column::<String>(&sta, "refname").unwrap_err().to_string();
+        assert!(error.contains("Cannot read column `refname`"), "{error}");
+    }
+}
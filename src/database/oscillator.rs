@@ -0,0 +1,81 @@
+use anyhow::Result;
+
+use crate::database::{self, column, has_table};
+use crate::stm32_device::chip_info::Oscillator;
+
+/// Query the oscillators of a family from table `oscillator`, in the order of the database.
+///
+/// Columns: `family`, `name` (e.g. `HSI`, `MSI`, `LSI` or `HSE` for the usual crystal)
+/// and `frequency` in Hz.
+///
+/// Databases without the table, or without rows of the family, give `None` and the
+/// caller falls back to the built-in HSI table.
+pub fn get_oscillators<T: ToString>(family: T) -> Result<Option<Vec<Oscillator>>> {
+    let db = database::open()?;
+    if !has_table(&db, "oscillator")? {
+        return Ok(None);
+    }
+    let query = r###"
+        SELECT name, frequency
+        from oscillator
+        where family = ?
+        order by rowid;
+    "###;
+    let mut sta = db.prepare(query)?;
+    sta.bind((1, family.to_string().as_str()))?;
+    let mut oscillators = Vec::new();
+    while let Ok(sqlite::State::Row) = sta.next() {
+        oscillators.push(Oscillator {
+            name: column::<String>(&sta, "name")?.to_uppercase(),
+            frequency: column::<i64>(&sta, "frequency")? as u32,
+        });
+    }
+    Ok((!oscillators.is_empty()).then_some(oscillators))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db_init;
+    use crate::database::tests::{test_db, DB_TEST_LOCK};
+
+    #[test]
+    fn test_get_oscillators() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        let db = test_db("test_oscillator.db");
+        db.execute(
+            "
+            CREATE TABLE IF NOT EXISTS oscillator (family TEXT, name TEXT, frequency INTEGER);
+            INSERT INTO oscillator VALUES ('STM32U5', 'HSI', 16000000),
+                ('STM32U5', 'msi', 4000000),
+                ('STM32U5', 'LSI', 32000);
+            ",
+        )
+        .unwrap();
+        db_init("test_oscillator.db").unwrap();
+
+        let oscillators = get_oscillators("STM32U5").unwrap().unwrap();
+        assert_eq!(oscillators.len(), 3);
+        assert_eq!(oscillators[1].name, "MSI");
+        assert_eq!(oscillators[1].frequency, 4_000_000);
+        assert_eq!(get_oscillators("STM32G0").unwrap(), None);
+        std::fs::remove_file("test_oscillator.db").unwrap();
+    }
+
+    #[test]
+    fn it_falls_back_to_the_hsi_of_the_family() {
+        for (family, frequency) in [
+            ("STM32L0", 16_000_000),
+            ("STM32WL", 16_000_000),
+            ("STM32C0", 48_000_000),
+            ("STM32H7", 64_000_000),
+        ] {
+            let oscillators = Oscillator::legacy_oscillators(family);
+            assert_eq!(oscillators.len(), 1);
+            assert_eq!(
+                (oscillators[0].name.as_str(), oscillators[0].frequency),
+                ("HSI", frequency)
+            );
+        }
+    }
+}
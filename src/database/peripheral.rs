@@ -0,0 +1,145 @@
+use anyhow::Result;
+
+use crate::database::{self, column, has_table, optional_column};
+use crate::stm32_device::chip_info::{DmaRequest, Peripheral, PeripheralIrq};
+
+/// Query the peripheral instances of a part from table `peripheral`, with their interrupts
+/// from `peripheral_irq` and their DMA requests from `peripheral_dma`, in the order of
+/// the database.
+///
+/// Columns of `peripheral`: `refname`, `instance`, `name` and `version` of the IP, and
+/// since schema version 7 `bus`, `enable_register` and `enable_bit` of the RCC.
+/// `peripheral_irq` has `refname`, `instance`, `irq` and `number`, `peripheral_dma` has
+/// `refname`, `instance`, `signal`, `controller`, `channel` and `request`. The open pin
+/// data has neither, both tables are filled in by hand and kept by `import`.
+///
+/// `None` is returned when the database has no peripheral of the part.
+pub fn get_peripherals<T: ToString>(refname: T) -> Result<Option<Vec<Peripheral>>> {
+    let db = database::open()?;
+    if !has_table(&db, "peripheral")? {
+        return Ok(None);
+    }
+    let refname = refname.to_string();
+    let query = r###"
+        SELECT *
+        from peripheral
+        where refname = ?
+        order by rowid;
+    "###;
+    let mut sta = db.prepare(query)?;
+    sta.bind((1, refname.as_str()))?;
+    let mut peripherals = Vec::new();
+    while let Ok(sqlite::State::Row) = sta.next() {
+        peripherals.push(Peripheral {
+            instance: column::<String>(&sta, "instance")?.to_uppercase(),
+            name: column::<String>(&sta, "name")?,
+            version: column::<String>(&sta, "version")?,
+            bus: optional_column::<String>(&sta, "bus")?.filter(|b| !b.is_empty()),
+            enable_register: optional_column::<String>(&sta, "enable_register")?
+                .filter(|r| !r.is_empty()),
+            enable_bit: optional_column::<i64>(&sta, "enable_bit")?.map(|b| b as u8),
+            irqs: Vec::new(),
+            dma_requests: Vec::new(),
+        });
+    }
+    if peripherals.is_empty() {
+        return Ok(None);
+    }
+
+    if has_table(&db, "peripheral_irq")? {
+        let query = r###"
+            SELECT instance, irq, number
+            from peripheral_irq
+            where refname = ?
+            order by rowid;
+        "###;
+        let mut sta = db.prepare(query)?;
+        sta.bind((1, refname.as_str()))?;
+        while let Ok(sqlite::State::Row) = sta.next() {
+            let instance = column::<String>(&sta, "instance")?;
+            let irq = PeripheralIrq {
+                name: column::<String>(&sta, "irq")?,
+                number: column::<i64>(&sta, "number")? as u16,
+            };
+            if let Some(peripheral) = find_instance(&mut peripherals, &instance) {
+                peripheral.irqs.push(irq);
+            }
+        }
+    }
+
+    if has_table(&db, "peripheral_dma")? {
+        let query = r###"
+            SELECT instance, signal, controller, channel, request
+            from peripheral_dma
+            where refname = ?
+            order by rowid;
+        "###;
+        let mut sta = db.prepare(query)?;
+        sta.bind((1, refname.as_str()))?;
+        while let Ok(sqlite::State::Row) = sta.next() {
+            let instance = column::<String>(&sta, "instance")?;
+            let request = DmaRequest {
+                signal: column::<String>(&sta, "signal")?,
+                controller: column::<String>(&sta, "controller")?,
+                channel: column::<Option<i64>>(&sta, "channel")?.map(|c| c as u8),
+                request: column::<Option<i64>>(&sta, "request")?.map(|r| r as u16),
+            };
+            if let Some(peripheral) = find_instance(&mut peripherals, &instance) {
+                peripheral.dma_requests.push(request);
+            }
+        }
+    }
+    Ok(Some(peripherals))
+}
+
+fn find_instance<'a>(
+    peripherals: &'a mut [Peripheral],
+    instance: &str,
+) -> Option<&'a mut Peripheral> {
+    peripherals
+        .iter_mut()
+        .find(|p| p.instance.eq_ignore_ascii_case(instance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db_init;
+    use crate::database::tests::{test_db, DB_TEST_LOCK};
+
+    #[test]
+    fn test_get_peripherals() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        let db = test_db("test_peripheral.db");
+        db.execute(
+            "
+            CREATE TABLE IF NOT EXISTS peripheral (refname TEXT, instance TEXT, name TEXT,
+                version TEXT, bus TEXT, enable_register TEXT, enable_bit INTEGER);
+            CREATE TABLE IF NOT EXISTS peripheral_irq (refname TEXT, instance TEXT, irq TEXT,
+                number INTEGER);
+            CREATE TABLE IF NOT EXISTS peripheral_dma (refname TEXT, instance TEXT,
+                signal TEXT, controller TEXT, channel INTEGER, request INTEGER);
+            INSERT INTO peripheral VALUES
+                ('STM32G071CBTx', 'USART2', 'USART', 'sci3_v1_1_Cube', 'APB1', 'APBENR1', 17),
+                ('STM32G071CBTx', 'SPI1', 'SPI', 'spi2s1_v1_1_Cube', NULL, NULL, NULL);
+            INSERT INTO peripheral_irq VALUES ('STM32G071CBTx', 'USART2', 'USART2_LPUART2', 28);
+            INSERT INTO peripheral_dma VALUES
+                ('STM32G071CBTx', 'USART2', 'USART2_TX', 'DMA1', NULL, 53);
+            ",
+        )
+        .unwrap();
+        db_init("test_peripheral.db").unwrap();
+
+        let peripherals = get_peripherals("STM32G071CBTx").unwrap().unwrap();
+        assert_eq!(peripherals.len(), 2);
+        assert_eq!(peripherals[0].kind(), "USART");
+        assert_eq!(peripherals[0].bus.as_deref(), Some("APB1"));
+        assert_eq!(peripherals[0].enable_bit, Some(17));
+        assert_eq!(peripherals[0].irqs[0].number, 28);
+        assert_eq!(peripherals[0].dma_requests[0].request, Some(53));
+        assert_eq!(peripherals[1].bus, None);
+        assert!(peripherals[1].irqs.is_empty());
+        assert_eq!(get_peripherals("STM32F407VGTx").unwrap(), None);
+        std::fs::remove_file("test_peripheral.db").unwrap();
+    }
+}
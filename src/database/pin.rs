@@ -0,0 +1,125 @@
+use anyhow::Result;
+
+use crate::database::{self, column, has_table};
+
+/// A GPIO pin of the package of a part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackagePin {
+    /// GPIO name, e.g. `PA2`
+    pub name: String,
+    /// Position in the package, e.g. `12` or `B3`
+    pub position: String,
+    pub signals: Vec<PinSignal>,
+}
+
+/// A signal which can be routed to a pin, e.g. `USART2_TX`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinSignal {
+    pub name: String,
+    /// Alternate function number, `None` for the families without, such as STM32F1,
+    /// and for the additional functions like ADC inputs
+    pub af: Option<u8>,
+}
+
+impl PackagePin {
+    pub fn signal(&self, name: &str) -> Option<&PinSignal> {
+        self.signals
+            .iter()
+            .find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Query the GPIO pins of a part with their signals from the tables `pin` and `pin_signal`,
+/// as written by `cargo stm32bs import`.
+///
+/// `None` is returned when the database has no pin data of the part, the pin map can not
+/// be checked in that case.
+pub fn get_pins<T: ToString>(refname: T) -> Result<Option<Vec<PackagePin>>> {
+    let db = database::open()?;
+    if !has_table(&db, "pin")? || !has_table(&db, "pin_signal")? {
+        return Ok(None);
+    }
+    let refname = refname.to_string();
+    let query = r###"
+        SELECT pin, position
+        from pin
+        where refname = ?
+        order by rowid;
+    "###;
+    let mut sta = db.prepare(query)?;
+    sta.bind((1, refname.as_str()))?;
+    let mut pins = Vec::new();
+    while let Ok(sqlite::State::Row) = sta.next() {
+        pins.push(PackagePin {
+            name: column::<String>(&sta, "pin")?,
+            position: column::<String>(&sta, "position")?,
+            signals: Vec::new(),
+        });
+    }
+    if pins.is_empty() {
+        return Ok(None);
+    }
+
+    let query = r###"
+        SELECT pin, signal, af
+        from pin_signal
+        where refname = ?
+        order by rowid;
+    "###;
+    let mut sta = db.prepare(query)?;
+    sta.bind((1, refname.as_str()))?;
+    while let Ok(sqlite::State::Row) = sta.next() {
+        let pin_name = column::<String>(&sta, "pin")?;
+        let signal = PinSignal {
+            name: column::<String>(&sta, "signal")?,
+            af: column::<Option<i64>>(&sta, "af")?.map(|af| af as u8),
+        };
+        if let Some(pin) = pins.iter_mut().find(|p| p.name == pin_name) {
+            pin.signals.push(signal);
+        }
+    }
+    Ok(Some(pins))
+}
+
+/// The pins of the package which can carry `signal`, as `(pin, af)`
+pub fn pins_of_signal<'a>(pins: &'a [PackagePin], signal: &str) -> Vec<(&'a str, Option<u8>)> {
+    pins.iter()
+        .filter_map(|p| p.signal(signal).map(|s| (p.name.as_str(), s.af)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db_init;
+    use crate::database::tests::{test_db, DB_TEST_LOCK};
+
+    #[test]
+    fn test_get_pins() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        let db = test_db("test_pin.db");
+        db.execute(
+            "
+            CREATE TABLE IF NOT EXISTS pin (refname TEXT, pin TEXT, position TEXT);
+            CREATE TABLE IF NOT EXISTS pin_signal (refname TEXT, pin TEXT, signal TEXT,
+                af INTEGER);
+            INSERT INTO pin VALUES ('STM32G071CBTx', 'PA2', '12'),
+                ('STM32G071CBTx', 'PA5', '15');
+            INSERT INTO pin_signal VALUES ('STM32G071CBTx', 'PA2', 'USART2_TX', 1),
+                ('STM32G071CBTx', 'PA2', 'ADC1_IN2', NULL),
+                ('STM32G071CBTx', 'PA5', 'SPI1_SCK', 0);
+            ",
+        )
+        .unwrap();
+        db_init("test_pin.db").unwrap();
+
+        let pins = get_pins("STM32G071CBTx").unwrap().unwrap();
+        assert_eq!(pins.len(), 2);
+        assert_eq!(pins[0].position, "12");
+        assert_eq!(pins[0].signal("usart2_tx").unwrap().af, Some(1));
+        assert_eq!(pins[0].signal("ADC1_IN2").unwrap().af, None);
+        assert_eq!(pins_of_signal(&pins, "SPI1_SCK"), vec![("PA5", Some(0))]);
+        assert_eq!(get_pins("STM32F407VGTx").unwrap(), None);
+        std::fs::remove_file("test_pin.db").unwrap();
+    }
+}
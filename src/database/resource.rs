@@ -1,17 +1,14 @@
 use anyhow::{bail, Result};
 
 use crate::database;
-use crate::database::DB_FILE_PATH;
-use crate::stm32_device::chip_info::{ArmCore, ChipInfo};
+use crate::database::{column, optional_column};
+use crate::stm32_device::chip_info::{
+    ArmCore, ChipInfo, ChipStatus, Fpu, MemoryRegion, Oscillator,
+};
+use crate::stm32_device::chip_pn::package_from_refname;
 
 pub fn get_resource<T: ToString + Clone>(cpn: T) -> Result<ChipInfo> {
     let refname = database::cpn::get_refname(cpn.clone())?;
-    let binding = DB_FILE_PATH.lock();
-    let binding2 = binding.as_ref().unwrap().borrow();
-    let path = binding2.as_ref();
-    if path.is_none() {
-        bail!("The database has not been initialized!");
-    }
     let query_data = refname.clone();
     let query = r###"
         SELECT * 
@@ -19,42 +16,42 @@ pub fn get_resource<T: ToString + Clone>(cpn: T) -> Result<ChipInfo> {
         where resource.pac = pac_content.id 
         and refname = ?;
     "###;
-    let db = sqlite::open(path.unwrap()).unwrap();
+    let db = database::open()?;
     let mut sta = db.prepare(query)?;
     sta.bind((1, query_data.as_str()))?;
     if let Ok(sqlite::State::Row) = sta.next() {
-        let family = sta.read::<String, _>("family").unwrap();
-        let core_str = sta.read::<String, _>("core").unwrap();
-        let core2_raw = sta.read::<String, _>("core_second");
+        let family = column::<String>(&sta, "family")?;
+        let core_str = column::<String>(&sta, "core")?;
+        let core2_raw = column::<Option<String>>(&sta, "core_second")?;
         let core = ArmCore::try_from_short(core_str)?;
-        let core2 = if let Ok(core2_str) = core2_raw {
-            if core2_str.as_str() == "" {
-                None
-            } else {
-                Some(ArmCore::try_from_short(core2_str)?)
-            }
-        } else {
-            None
+        let core2 = match core2_raw.as_deref() {
+            None | Some("") => None,
+            Some(core2_str) => Some(ArmCore::try_from_short(core2_str)?),
+        };
+        let freq = column::<i64>(&sta, "frequency")?;
+        let flash = column::<i64>(&sta, "flash")?;
+        let ram = column::<i64>(&sta, "ram")?;
+        let ccmram = column::<i64>(&sta, "ccmram")?;
+        // databases before schema version 5 have no FPU
+        let fpu = match optional_column::<String>(&sta, "fpu")? {
+            Some(fpu) if !fpu.is_empty() => Fpu::try_from_str(fpu)?,
+            _ => Fpu::of_part(&family, &refname),
+        };
+        let description = optional_column::<String>(&sta, "description")?.unwrap_or_default();
+        let status = match optional_column::<String>(&sta, "status")? {
+            Some(status) => ChipStatus::try_from_str(status)?,
+            None => ChipStatus::Unknown,
         };
-        let freq = sta.read::<i64, _>("frequency").unwrap();
-        let flash = sta.read::<i64, _>("flash").unwrap();
-        let ram = sta.read::<i64, _>("ram").unwrap();
-        let ccmram = sta.read::<i64, _>("ccmram").unwrap();
-        let target = match family.as_str() {
-            "STM32F0" | "STM32G0" | "STM32L0" | "STM32C0" | "STM32U0" | "STM32WL3" | "STM32WB0" => {
-                "thumbv6m-none-eabi".to_string()
-            }
-            "STM32F1" | "STM32F2" | "STM32L1" => "thumbv7m-none-eabi".to_string(),
-            "STM32F3" | "STM32F4" | "STM32F7" | "STM32G4" | "STM32H7" | "STM32L4" | "STM32L4+"
-            | "STM32WB" | "STM32WL" => "thumbv7em-none-eabi".to_string(),
-            "STM32L5" | "STM32U5" | "STM32H5" | "STM32WBA" | "STM32N6" | "STM32U3" => {
-                "thumbv8m.main-none-eabihf".to_string()
-            }
-            s => bail!("Family `{}` has not been related rust target!", s),
+        let package = match optional_column::<String>(&sta, "package")? {
+            Some(package) if !package.is_empty() => package,
+            _ => package_from_refname(&refname).unwrap_or_default(),
         };
-        let pac_name = sta.read::<String, _>("pac_name").unwrap();
-        let pac_ver = sta.read::<String, _>("pac_ver").unwrap();
-        let pac_feature = sta.read::<String, _>("pac_feature").unwrap();
+        let co_type = optional_column::<String>(&sta, "co_type")?.filter(|c| !c.is_empty());
+        let co_freq = optional_column::<i64>(&sta, "co_freq")?.map(|f| f as u32);
+        let target = core.target(fpu != Fpu::None).to_string();
+        let pac_name = column::<String>(&sta, "pac_name")?;
+        let pac_ver = column::<String>(&sta, "pac_ver")?;
+        let pac_feature = column::<String>(&sta, "pac_feature")?;
         if pac_name.eq("-") {
             bail!("PAC info is absent, pls update database!");
         }
@@ -64,13 +61,28 @@ pub fn get_resource<T: ToString + Clone>(cpn: T) -> Result<ChipInfo> {
         if pac_feature.eq("-") {
             bail!("PAC info is absent, pls update database!");
         }
+        let memory_regions = match database::memory::get_memory_regions(&refname)? {
+            Some(regions) => regions,
+            None => MemoryRegion::legacy_regions(flash as u32, ram as u32, ccmram as u32),
+        };
+        let oscillators = match database::oscillator::get_oscillators(&family)? {
+            Some(oscillators) => oscillators,
+            None => Oscillator::legacy_oscillators(&family),
+        };
+        let peripherals = database::peripheral::get_peripherals(&refname)?.unwrap_or_default();
         Ok(ChipInfo {
             cpn: cpn.to_string(),
             refname: refname.clone(),
             family,
+            description,
+            status,
+            package,
             core,
             core2,
             freq: freq as u32,
+            fpu,
+            co_type,
+            co_freq,
             flash: flash as u32,
             ram: ram as u32,
             ccmram: ccmram as u32,
@@ -78,6 +90,9 @@ pub fn get_resource<T: ToString + Clone>(cpn: T) -> Result<ChipInfo> {
             pac_name,
             pac_ver,
             pac_feature,
+            memory_regions,
+            oscillators,
+            peripherals,
         })
     } else {
         bail!("No record be found!");
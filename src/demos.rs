@@ -0,0 +1,316 @@
+use anyhow::{bail, Result};
+use console::style;
+use log::warn;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::args::DemosArgs;
+use crate::bsp::dependencies::{parse_dependencies, Dependency};
+use crate::chips::resolve_chip_pn;
+use crate::database::resource::get_resource;
+use crate::stm32_device::chip_info::ChipInfo;
+use crate::template::TemplateFile;
+use crate::template_config::{self, Config, DemoConfig, DemoRequirements};
+
+/// List the demos of the template for `cargo stm32bs demos`, with their description,
+/// difficulty, tags and hardware, filtered by the arguments
+pub fn list_demos(args: &DemosArgs, config: &Config) -> Result<()> {
+    let mut demos = config.get_demo_list();
+    if demos.is_empty() {
+        warn!("{}", style("The template has no demo").bold().yellow());
+        return Ok(());
+    }
+    demos.sort();
+    let chip_info = match &args.chip {
+        Some(chip_pn) => Some(get_resource(resolve_chip_pn(chip_pn)?)?),
+        None => None,
+    };
+    if let Some(chip_info) = &chip_info {
+        demos.retain(|demo| unmet_demo_requirements(config, demo, chip_info).is_empty());
+    }
+    let default_demo = DemoConfig::default();
+    let demos: Vec<(String, &DemoConfig)> = demos
+        .into_iter()
+        .map(|name| {
+            let demo = config.get_demo(&name).unwrap_or(&default_demo);
+            (name, demo)
+        })
+        .filter(|(name, demo)| matches_filters(args, name, demo))
+        .collect();
+    if let Some(chip_info) = &chip_info {
+        for (name, _) in demos.iter() {
+            warn_unverified_requirements(config, name, chip_info);
+        }
+    }
+    if demos.is_empty() {
+        warn!("{}", style("No demo matches the filters").bold().yellow());
+    } else {
+        print!("{}", render_list(&demos));
+    }
+    Ok(())
+}
+
+fn matches_filters(args: &DemosArgs, name: &str, demo: &DemoConfig) -> bool {
+    if let Some(pattern) = &args.pattern {
+        let pattern = pattern.to_lowercase();
+        if !name.to_lowercase().contains(&pattern)
+            && !demo
+                .description
+                .as_ref()
+                .is_some_and(|d| d.to_lowercase().contains(&pattern))
+        {
+            return false;
+        }
+    }
+    if args.difficulty.is_some() && demo.difficulty != args.difficulty {
+        return false;
+    }
+    args.tags.iter().all(|tag| demo.has_tag(tag))
+}
+
+/// One line per demo with its difficulty and description, the tags and the hardware
+/// on the following lines
+fn render_list(demos: &[(String, &DemoConfig)]) -> String {
+    let width = demos.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut list = String::new();
+    for (name, demo) in demos {
+        let line = format!(
+            "{:<width$}  {:<12}  {}",
+            name,
+            demo.difficulty.map(|d| d.to_string()).unwrap_or_default(),
+            demo.description.as_deref().unwrap_or_default()
+        );
+        list.push_str(line.trim_end());
+        list.push('\n');
+        for (title, values) in [("tags", &demo.tags), ("hardware", &demo.hardware)] {
+            if let Some(values) = values.as_ref().filter(|v| !v.is_empty()) {
+                list.push_str(&format!(
+                    "{:<width$}  {}: {}\n",
+                    "",
+                    title,
+                    values.join(", ")
+                ));
+            }
+        }
+    }
+    list
+}
+
+/// Entry of a demo in the demo chooser, its name with the summary of its table
+pub fn demo_label(config: &Config, name: &str, width: usize) -> String {
+    match config.get_demo(name).map(DemoConfig::summary) {
+        Some(summary) if !summary.is_empty() => format!("{name:<width$}  {summary}"),
+        _ => name.to_string(),
+    }
+}
+
+/// The files of a demo, they replace the files of the template with the same path.
+///
+/// A demo is either the file `demo/<name>.rs`, which becomes `src/main.rs`, or the
+/// directory `demo/<name>/` laid out like the project, e.g. `demo/<name>/src/main.rs`,
+/// `demo/<name>/src/display.rs` or `demo/<name>/build.rs`. The `include` list of the
+/// demo table adds files of the template to `include_files`.
+pub fn demo_files(
+    template_dir: &Path,
+    config: &Config,
+    demo_name: &str,
+    include_files: &mut Vec<String>,
+) -> Result<Vec<TemplateFile>> {
+    let demo_dir = template_dir.join("demo").join(demo_name);
+    let demo_file = template_dir.join("demo").join(format!("{demo_name}.rs"));
+    let mut files = Vec::new();
+    if demo_dir.is_dir() {
+        for source in files_of_dir(&demo_dir)? {
+            let target = source
+                .strip_prefix(&demo_dir)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            files.push(TemplateFile { target, source });
+        }
+    } else if demo_file.exists() {
+        files.push(TemplateFile {
+            target: "src/main.rs".to_string(),
+            source: demo_file,
+        });
+    } else {
+        bail!(
+            "Demo not found: neither {} nor {} exists",
+            demo_dir.display(),
+            demo_file.display()
+        );
+    }
+    if let Some(demo) = config.get_demo(demo_name) {
+        template_config::replenish_include_file(template_dir, include_files, &demo.include)?;
+    }
+    Ok(files)
+}
+
+/// Every file of a directory and its sub directories, sorted
+fn files_of_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The crates the demo table adds to `Cargo.toml`
+pub fn demo_dependencies(config: &Config, demo_name: &str) -> Result<Vec<Dependency>> {
+    let dependencies = config
+        .get_demo(demo_name)
+        .and_then(|d| d.dependencies.clone());
+    parse_dependencies(&format!("demo.{demo_name}.dependencies"), &dependencies)
+}
+
+/// Demos of the template split by the part: the ones it can run, and the other ones with
+/// the reasons why not. Both are sorted by name.
+pub fn demos_for_part(
+    config: &Config,
+    chip_info: &ChipInfo,
+) -> (Vec<String>, Vec<(String, Vec<String>)>) {
+    let mut available = Vec::new();
+    let mut unavailable = Vec::new();
+    let mut demos = config.get_demo_list();
+    demos.sort();
+    for demo in demos {
+        let reasons = unmet_demo_requirements(config, &demo, chip_info);
+        if reasons.is_empty() {
+            available.push(demo);
+        } else {
+            unavailable.push((demo, reasons));
+        }
+    }
+    (available, unavailable)
+}
+
+/// Fail if the part can not run the demo
+pub fn check_demo(config: &Config, demo: &str, chip_info: &ChipInfo) -> Result<()> {
+    let reasons = unmet_demo_requirements(config, demo, chip_info);
+    if !reasons.is_empty() {
+        bail!(
+            "Demo `{}` is not available for {}: {}",
+            demo,
+            chip_info.cpn,
+            reasons.join(", ")
+        );
+    }
+    warn_unverified_requirements(config, demo, chip_info);
+    Ok(())
+}
+
+/// Warn when the peripherals the demo needs can not be checked, the database has no
+/// peripheral inventory of the part
+pub fn warn_unverified_requirements(config: &Config, demo: &str, chip_info: &ChipInfo) {
+    let Some(peripherals) = config
+        .get_demo(demo)
+        .and_then(|demo| demo.requires.as_ref())
+        .and_then(|requirements| requirements.peripherals.as_ref())
+        .filter(|peripherals| !peripherals.is_empty())
+    else {
+        return;
+    };
+    if chip_info.peripherals.is_empty() {
+        warn!(
+            "{}",
+            style(format!(
+                "Demo `{}` needs {}, the database has no peripherals of {} to check them",
+                demo,
+                peripherals
+                    .iter()
+                    .map(|p| p.to_uppercase())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                chip_info.cpn
+            ))
+            .yellow()
+        );
+    }
+}
+
+fn unmet_demo_requirements(config: &Config, demo: &str, chip_info: &ChipInfo) -> Vec<String> {
+    config
+        .get_demo(demo)
+        .and_then(|demo| demo.requires.as_ref())
+        .map(|requirements| unmet_requirements(requirements, chip_info))
+        .unwrap_or_default()
+}
+
+/// The requirements of a demo the part does not meet, as readable reasons.
+/// Peripherals are only checked if the database has the peripheral inventory of the part,
+/// `warn_unverified_requirements` reports the other ones.
+pub fn unmet_requirements(requirements: &DemoRequirements, chip_info: &ChipInfo) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if let Some(families) = &requirements.families {
+        let family = chip_info.family.trim_start_matches("STM32");
+        if !families
+            .iter()
+            .any(|f| f.trim_start_matches("STM32").eq_ignore_ascii_case(family))
+        {
+            reasons.push(format!(
+                "the family is {}, not {}",
+                chip_info.family,
+                families.join(" or ")
+            ));
+        }
+    }
+    if let Some(cores) = &requirements.cores {
+        let part_cores: Vec<_> = std::iter::once(chip_info.core)
+            .chain(chip_info.core2)
+            .collect();
+        let matches = |name: &String| {
+            part_cores.iter().any(|core| {
+                name.eq_ignore_ascii_case(core.tag())
+                    || name.eq_ignore_ascii_case(&core.to_string())
+            })
+        };
+        if !cores.iter().any(matches) {
+            reasons.push(format!(
+                "the core is {}, not {}",
+                part_cores
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" + "),
+                cores.join(" or ")
+            ));
+        }
+    }
+    if let Some(peripherals) = &requirements.peripherals {
+        if !chip_info.peripherals.is_empty() {
+            for peripheral in peripherals {
+                if chip_info.peripheral(peripheral).is_none()
+                    && chip_info.peripherals_of_kind(peripheral).is_empty()
+                {
+                    reasons.push(format!("it has no {}", peripheral.to_uppercase()));
+                }
+            }
+        }
+    }
+    if let Some(min_flash) = requirements.min_flash {
+        if chip_info.flash < min_flash {
+            reasons.push(format!(
+                "it has {} KB of flash, {} KB are needed",
+                chip_info.flash, min_flash
+            ));
+        }
+    }
+    if let Some(min_ram) = requirements.min_ram {
+        if chip_info.ram < min_ram {
+            reasons.push(format!(
+                "it has {} KB of RAM, {} KB are needed",
+                chip_info.ram, min_ram
+            ));
+        }
+    }
+    reasons
+}
@@ -1,7 +1,7 @@
 use crate::project_variables::MSEntry;
 use crate::project_variables::{TemplateSlots, VarInfo};
 
-use anyhow::{Ok, Result};
+use anyhow::{bail, Ok, Result};
 use console::style;
 use dialoguer::{theme::ColorfulTheme, Input, MultiSelect, Select};
 use liquid_core::Value;
@@ -14,6 +14,89 @@ use std::{
 
 pub const LIST_SEP: &str = ",";
 
+/// Values which can not be resolved without a prompt.
+/// In silent mode they are collected, so a single error can report all of them.
+#[derive(Debug, Default)]
+pub struct MissingValues(Vec<(String, String)>);
+
+impl MissingValues {
+    pub fn add(&mut self, var_name: impl ToString, hint: impl ToString) {
+        self.0.push((var_name.to_string(), hint.to_string()));
+    }
+
+    /// The error of a single missing value, when nothing can be done without it
+    pub fn single(var_name: impl ToString, hint: impl ToString) -> anyhow::Error {
+        let mut missing = Self::default();
+        missing.add(var_name, hint);
+        missing.check().unwrap_err()
+    }
+
+    /// Fail if any value is missing
+    pub fn check(&self) -> Result<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let list = self
+            .0
+            .iter()
+            .map(|(name, hint)| format!("  - {}: {}", style(name).bold().yellow(), hint))
+            .collect::<Vec<String>>()
+            .join("\n");
+        bail!(
+            "⛔ {}\n{}",
+            style("Silent mode, the following values are missing (use --define or --values-file):")
+                .bold()
+                .red(),
+            list
+        );
+    }
+}
+
+/// The default value of a placeholder, used instead of a prompt in silent mode
+pub fn default_value(variable: &TemplateSlots) -> Option<Value> {
+    match &variable.var_info {
+        VarInfo::Bool { default } => default.map(|d| Value::Scalar(d.into())),
+        VarInfo::Integer { default, .. } => default.map(|d| Value::Scalar(d.into())),
+        VarInfo::String { default, .. } | VarInfo::Text { default, .. } => {
+            default.clone().map(|d| Value::Scalar(d.into()))
+        }
+        VarInfo::Select { default, .. } => default.clone().map(|d| Value::Scalar(d.into())),
+        VarInfo::MultiSelect { entry } => entry.default.as_ref().map(|d| {
+            Value::Array(
+                d.iter()
+                    .map(|s| Value::Scalar(s.to_string().into()))
+                    .collect(),
+            )
+        }),
+    }
+}
+
+/// Describe the value expected by a placeholder, for the error of silent mode
+pub fn expected_value(variable: &TemplateSlots) -> String {
+    match &variable.var_info {
+        VarInfo::Bool { .. } => "true or false".to_string(),
+        VarInfo::Integer {
+            range: Some((min, max)),
+            ..
+        } => format!("an integer from {min} to {max}"),
+        VarInfo::Integer { range: None, .. } => "an integer".to_string(),
+        VarInfo::String {
+            regex: Some(regex), ..
+        }
+        | VarInfo::Text {
+            regex: Some(regex), ..
+        } => {
+            format!("a string matching `{regex}`")
+        }
+        VarInfo::String { .. } | VarInfo::Text { .. } => "a string".to_string(),
+        VarInfo::Select { choices, .. } => format!("one of {}", choices.join(", ")),
+        VarInfo::MultiSelect { entry } => format!(
+            "a `{LIST_SEP}` separated list of {}",
+            entry.choices.join(", ")
+        ),
+    }
+}
+
 pub fn name() -> Result<String> {
     let valid_ident = regex::Regex::new(r"^([a-zA-Z][a-zA-Z0-9_-]+)$")?;
     let project_var = TemplateSlots {
@@ -21,7 +104,9 @@ pub fn name() -> Result<String> {
         prompt: "🤷 Project Name".into(),
         var_info: VarInfo::String {
             regex: Some(valid_ident),
+            default: None,
         },
+        peripheral: None,
     };
     prompt_and_check_variable(&project_var)
 }
@@ -33,7 +118,9 @@ pub fn chip_pn() -> Result<String> {
         prompt: "🤷 Chip Part Number (eg. stm32g071cbt6)".into(),
         var_info: VarInfo::String {
             regex: Some(valid_ident),
+            default: None,
         },
+        peripheral: None,
     };
     prompt_and_check_variable(&project_var)
 }
@@ -46,12 +133,34 @@ pub fn select(choices: &Vec<&str>, prompt: &str, default: Option<String>) -> Res
     )
 }
 
-pub fn user_question(prompt: &String, qtype: usize) -> Result<String> {
+/// Lines shown at once by `select_page`, the list scrolls for the other ones
+const PAGE_SIZE: usize = 10;
+
+/// Select an item of a long list, page by page. The index of the item is returned.
+pub fn select_page(items: &[String], prompt: &str) -> Result<usize> {
+    Select::with_theme(&ColorfulTheme::default())
+        .items(items)
+        .with_prompt(prompt)
+        .default(0)
+        .max_length(PAGE_SIZE)
+        .interact()
+        .map_err(Into::<anyhow::Error>::into)
+}
+
+/// Text to filter a list with
+pub fn filter() -> Result<String> {
+    user_question(&"🔍 Filter".to_string(), 0, &None)
+}
+
+pub fn user_question(prompt: &String, qtype: usize, default: &Option<String>) -> Result<String> {
     match qtype {
-        0 => Input::<String>::new()
-            .with_prompt(prompt)
-            .interact()
-            .map_err(Into::<anyhow::Error>::into),
+        0 => {
+            let mut input = Input::<String>::new().with_prompt(prompt);
+            if let Some(default) = default {
+                input = input.default(default.clone());
+            }
+            input.interact().map_err(Into::<anyhow::Error>::into)
+        }
         1 => {
             println!("{} (press Ctrl+d to stop reading)", prompt);
             let mut buffer = String::new();
@@ -67,13 +176,15 @@ pub fn user_question(prompt: &String, qtype: usize) -> Result<String> {
 pub fn prompt_and_check_variable(variable: &TemplateSlots) -> Result<String> {
     match &variable.var_info {
         VarInfo::Bool { default } => handle_bool_input(&variable.prompt, default),
-        VarInfo::Integer { range } => {
-            handle_integer_input(&variable.var_name, range, &variable.prompt)
+        VarInfo::Integer { range, default } => {
+            handle_integer_input(&variable.var_name, range, default, &variable.prompt)
+        }
+        VarInfo::String { regex, default } => {
+            handle_string_input(&variable.var_name, regex, default, &variable.prompt)
         }
-        VarInfo::String { regex } => {
-            handle_string_input(&variable.var_name, regex, &variable.prompt)
+        VarInfo::Text { regex, .. } => {
+            handle_text_input(&variable.var_name, regex, &variable.prompt)
         }
-        VarInfo::Text { regex } => handle_text_input(&variable.var_name, regex, &variable.prompt),
         VarInfo::Select { choices, default } => {
             handle_choice_input(choices, default, &variable.prompt)
         }
@@ -112,14 +223,19 @@ pub fn variable(variable: &TemplateSlots) -> Result<Value> {
 fn handle_integer_input(
     var_name: &str,
     range: &Option<(i32, i32)>,
+    default: &Option<i32>,
     prompt: &String,
 ) -> Result<String> {
+    let input = || {
+        let mut input = Input::<i32>::new().with_prompt(prompt);
+        if let Some(default) = default {
+            input = input.default(*default);
+        }
+        input.interact().map_err(Into::<anyhow::Error>::into)
+    };
     match range {
         Some(range) => loop {
-            let user_entry = Input::<i32>::new()
-                .with_prompt(prompt)
-                .interact()
-                .map_err(Into::<anyhow::Error>::into)?;
+            let user_entry = input()?;
             if range.0 <= user_entry && user_entry <= range.1 {
                 break Ok(user_entry.to_string());
             }
@@ -132,17 +248,19 @@ fn handle_integer_input(
                     .red()
             );
         },
-        None => Ok(Input::<i32>::new()
-            .with_prompt(prompt)
-            .interact()?
-            .to_string()),
+        None => Ok(input()?.to_string()),
     }
 }
 
-fn handle_string_input(var_name: &str, regex: &Option<Regex>, prompt: &String) -> Result<String> {
+fn handle_string_input(
+    var_name: &str,
+    regex: &Option<Regex>,
+    default: &Option<String>,
+    prompt: &String,
+) -> Result<String> {
     match regex {
         Some(regex) => loop {
-            let user_entry = user_question(&prompt, 0)?;
+            let user_entry = user_question(prompt, 0, default)?;
             if regex.is_match(&user_entry) {
                 break Ok(user_entry);
             }
@@ -155,14 +273,14 @@ fn handle_string_input(var_name: &str, regex: &Option<Regex>, prompt: &String) -
                     .red()
             );
         },
-        None => Ok(user_question(&prompt, 0)?),
+        None => Ok(user_question(prompt, 0, default)?),
     }
 }
 
 fn handle_text_input(var_name: &str, regex: &Option<Regex>, prompt: &String) -> Result<String> {
     match regex {
         Some(regex) => loop {
-            let user_entry = user_question(&prompt, 1)?;
+            let user_entry = user_question(prompt, 1, &None)?;
             if regex.is_match(&user_entry) {
                 break Ok(user_entry);
             }
@@ -176,7 +294,7 @@ fn handle_text_input(var_name: &str, regex: &Option<Regex>, prompt: &String) ->
                     .red()
             );
         },
-        None => Ok(user_question(&prompt, 1)?),
+        None => Ok(user_question(prompt, 1, &None)?),
     }
 }
 
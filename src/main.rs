@@ -2,13 +2,18 @@
 mod absolute_path;
 mod app_log;
 mod args;
+mod bsp;
+mod chips;
 mod database;
+mod demos;
 mod interactive;
+mod multicore;
 mod progressbar;
 mod project_config;
 mod project_variables;
 mod stm32_device;
 mod template;
+mod template_cache;
 mod template_config;
 mod template_filters;
 mod template_variables;
@@ -17,9 +22,13 @@ mod utils;
 
 use app_log::log_env_init;
 use args::*;
-use interactive::LIST_SEP;
+use interactive::{MissingValues, LIST_SEP};
 use liquid::ValueView;
+use project_config::{ProjectConfig, PROJECT_CONFIG_FILE_NAME};
+use project_variables::TemplateSlots;
+use stm32_device::chip_info::ChipInfo;
 use stm32_device::chip_pn::get_chip_pn;
+use stm32_device::memory_x::render_memory_x;
 use template::{create_liquid_object, set_project_variables};
 use template_config::TemplateConfig;
 use template_config::{Config, CONFIG_FILE_NAME};
@@ -34,90 +43,244 @@ use console::style;
 use indexmap::IndexMap;
 use liquid_core::model::map::Entry;
 use liquid_core::Object;
-use log::{error, info};
+use log::{error, info, warn};
 use std::vec;
 use std::{
     collections::HashMap,
     env,
     path::{Path, PathBuf},
 };
+use tempfile::TempDir;
 
 const DEFAULT_TEMPLATE: &str = "https://github.com/AtlasHW/stm32bs-template-default";
 
 fn main() -> Result<()> {
     log_env_init();
     let args = resolve_args();
-    if !args.template_path.have_any_path() {
-        if let Ok(config_file) = project_config::check_config_file() {
-            // check stm32bs project type
-            // if the project is a bsp project, continue
-            // else, rise an error
-            let project_config = project_config::ProjectConfig::from_path(&config_file)?;
-            let prj_type = project_config
-                .project
-                .as_ref()
-                .and_then(|s| s.get("project_type"))
-                .and_then(|s| s.as_str());
-            if let Some(prj_type) = prj_type {
-                if prj_type == "Project with BSP" {
-                    info!("Project type: {}", prj_type);
-                    bail!("The function is developing, it will come soon!");
-                } else {
-                    error!("{} is not supported! for the function, you can use generate a BSP project!",
-                     prj_type);
-                    bail!("Error: Project type is not supported!");
-                }
-            } else {
-                bail!("Config file has been tampered!");
-            }
+    match args.command {
+        Some(command) => run_command(command),
+        // without a subcommand, a new project is generated
+        None => generate(args.new).map(|_| ()),
+    }
+}
+
+fn run_command(command: Command) -> Result<()> {
+    match command {
+        Command::New(args) => generate(*args).map(|_| ()),
+        Command::Update(args) => update_project(&args),
+        Command::Bsp(args) => add_bsp(&args),
+        Command::Check(args) => check_project(&args),
+        Command::Chips(chips_args) => {
+            let _template = fetch_template(&chips_args.template)?;
+            chips::list_chips(&chips_args)
         }
-        // cannot find any project config file, to generate a new project
-        else {
-            let mut args = args;
-            args.template_path.git = Some(DEFAULT_TEMPLATE.to_string());
-            generate(args)?;
+        Command::Info(info_args) => {
+            let _template = fetch_template(&info_args.template)?;
+            chips::show_chip_info(&info_args)
         }
-    } else {
-        // Create a new project from template
-        generate(args)?;
+        Command::Demos(demos_args) => {
+            let (_temp_dir, _template_dir, config) = fetch_template(&demos_args.template)?;
+            demos::list_demos(&demos_args, &config)
+        }
+        Command::Import(import_args) => import_database(&import_args),
+        Command::Clock(clock_args) => {
+            let _template = fetch_template(&clock_args.template)?;
+            chips::show_clock_tree(&clock_args)
+        }
+    }
+}
+
+/// Build or refresh a database from ST's open pin data, no template is needed
+fn import_database(args: &ImportArgs) -> Result<()> {
+    let summary = database::import::import(&args.source, &args.database, &args.family)?;
+    if !summary.skipped.is_empty() {
+        warn!(
+            "{} {}",
+            style("Skipped parts without a supported Cortex-M core:")
+                .bold()
+                .yellow(),
+            summary.skipped.join(", ")
+        );
+    }
+    if !summary.without_memory.is_empty() {
+        warn!(
+            "{} {}",
+            style("Parts without memory regions, they get the classic FLASH and RAM layout:")
+                .bold()
+                .yellow(),
+            summary.without_memory.join(", ")
+        );
     }
+    info!(
+        "✨ {} {} {}",
+        style("Done!").bold().green(),
+        style(format!("{} parts imported into", summary.parts)).bold(),
+        style(args.database.display()).underlined()
+    );
     Ok(())
 }
 
+/// Fetch the template and initialize its database for the subcommands,
+/// the default template is used if neither `--git` nor `--path` is given
+fn fetch_template(source: &TemplateSource) -> Result<(TempDir, PathBuf, Config)> {
+    let mut args = AppArgs::default();
+    args.template_path.git = source.git.clone();
+    args.template_path.path = source.path.clone();
+    args.offline = source.offline;
+    args.refresh = source.refresh;
+    prepare_template(&UserParsedInput::try_from_args(&with_default_template(
+        args,
+    ))?)
+}
+
+/// Use the default template when no template is given
+fn with_default_template(mut args: AppArgs) -> AppArgs {
+    if !args.template_path.have_any_path() {
+        args.template_path.git = Some(DEFAULT_TEMPLATE.to_string());
+    }
+    args
+}
+
 /// To generate a cargo project for stm32
 fn generate(args: AppArgs) -> Result<PathBuf> {
     // mash AppConfig and CLI arguments together into UserParsedInput
-    let user_parsed_input = UserParsedInput::try_from_args(&args);
+    let user_parsed_input = UserParsedInput::try_from_args(&with_default_template(args))?;
+    let (_temp_dir, template_dir, mut config) = prepare_template(&user_parsed_input)?;
+    let project_dir = expand_template(&template_dir, &mut config, &user_parsed_input)?;
+    info!(
+        "✨ {} {} {}",
+        style("Done!").bold().green(),
+        style("New project created").bold(),
+        style(&project_dir.display()).underlined()
+    );
+
+    Ok(project_dir)
+}
+
+/// Copy the template into a temporary directory, read its configuration and
+/// initialize the database shipped with it.
+/// The temporary directory is removed when the returned `TempDir` is dropped.
+fn prepare_template(user_parsed_input: &UserParsedInput) -> Result<(TempDir, PathBuf, Config)> {
     // copy the template files into a temporary directory
     let temp_dir = template::get_source_template_into_temp(user_parsed_input.location())?;
-    let template_dir = template::resolve_template_dir(&temp_dir)?;
+    let template_dir = template::resolve_template_dir(&temp_dir, user_parsed_input)?;
     // read configuration in the template
-    let mut config =
-        Config::from_path(&locate_template_file(CONFIG_FILE_NAME, &template_dir).ok())?;
+    let config = Config::from_path(&locate_template_file(CONFIG_FILE_NAME, &template_dir).ok())?;
     //Initialize Databas
     database::db_init(template_dir.join("stm32bs.db"))?;
-    //let pac_file = locate_template_file(PAC_INFO_FILE_NAME, &template_dir).unwrap();
     check_stm32bs_version(&config)?;
-    let project_dir = expand_template(
-        &template_dir,
-        &mut config,
-        &user_parsed_input,
-    )?;
+    Ok((temp_dir, template_dir, config))
+}
+
+/// Read the `.stm32bs.toml` of the project selected by `--project-dir` or the current directory
+fn open_project(args: &ProjectArgs) -> Result<(PathBuf, ProjectConfig)> {
+    let config_file = project_config::check_config_file(args.project_dir.as_deref())?;
+    let project_config = ProjectConfig::from_path(&config_file)?;
+    Ok((config_file, project_config))
+}
+
+/// Update the generated files of an existing project, according to its project type
+fn update_project(args: &ProjectArgs) -> Result<()> {
+    let (config_file, project_config) = open_project(args)?;
+    let Some(prj_type) = project_config.project_value("project_type") else {
+        bail!("Config file has been tampered!");
+    };
+    info!("Project type: {}", prj_type);
+    if prj_type != ProjectType::BSPProject.to_string() {
+        error!(
+            "{} has no generated files to update, use `cargo stm32bs bsp` to add a BSP to the project!",
+            prj_type
+        );
+        bail!("Error: Project type is not supported!");
+    }
+    update_bsp(args, &config_file, &project_config)
+}
+
+/// Turn an existing project into a BSP project, then generate its `bsp` module
+fn add_bsp(args: &ProjectArgs) -> Result<()> {
+    let (config_file, mut project_config) = open_project(args)?;
+    let bsp_type = ProjectType::BSPProject.to_string();
+    if project_config.project_value("project_type") != Some(bsp_type.as_str()) {
+        project_config
+            .project
+            .get_or_insert_with(HashMap::new)
+            .insert("project_type".into(), toml::Value::String(bsp_type));
+        project_config.peripheral.get_or_insert_with(HashMap::new);
+        project_config.pinmap.get_or_insert_with(HashMap::new);
+        project_config.driver.get_or_insert_with(HashMap::new);
+        project_config.middleware.get_or_insert_with(HashMap::new);
+        std::fs::write(&config_file, toml::to_string(&project_config)?)?;
+        info!(
+            "🔧 {}",
+            style(format!("Project type changed in {}", config_file.display())).bold()
+        );
+    }
+    update_bsp(args, &config_file, &project_config)
+}
+
+/// Check the `.stm32bs.toml` of a project against the database, without writing anything
+fn check_project(args: &ProjectArgs) -> Result<()> {
+    let (config_file, project_config) = open_project(args)?;
+    let Some(chip_pn) = project_config.project_value("chip") else {
+        bail!("Config file has no chip part number, pls add `chip` to the [project] table!");
+    };
+    let Some(prj_type) = project_config.project_value("project_type") else {
+        bail!("Config file has been tampered!");
+    };
+    let (_temp_dir, _template_dir, _config) = fetch_template(&args.template)?;
+    let chip_info = database::resource::get_resource(chip_pn)?;
+    if args.verbose {
+        info!("{}", chip_info);
+    }
+    if prj_type == ProjectType::BSPProject.to_string() {
+        let mut bsp_config = bsp::BspConfig::try_from(&project_config)?;
+        bsp_config.check_peripherals(&chip_info)?;
+        bsp_config.check_pins(&chip_info)?;
+    }
+    info!(
+        "✨ {} {}",
+        style(config_file.display()).underlined(),
+        style("is valid").bold().green()
+    );
+    Ok(())
+}
+
+/// Generate the `bsp` module of an existing project again, using the settings of
+/// its `.stm32bs.toml`
+fn update_bsp(
+    args: &ProjectArgs,
+    config_file: &Path,
+    project_config: &ProjectConfig,
+) -> Result<()> {
+    let Some(chip_pn) = project_config.project_value("chip") else {
+        bail!("Config file has no chip part number, pls add `chip` to the [project] table!");
+    };
+    let (_temp_dir, _template_dir, _config) = fetch_template(&args.template)?;
+    let chip_info = database::resource::get_resource(chip_pn)?;
+    if args.verbose {
+        info!("{}", chip_info);
+    }
+    let project_dir = config_file.parent().unwrap();
+    for file in bsp::generate(&chip_info, project_config, project_dir)? {
+        info!(
+            "🔧 {}",
+            style(format!("Generated: {}", file.display())).bold()
+        );
+    }
     info!(
         "✨ {} {} {}",
         style("Done!").bold().green(),
-        style("New project created").bold(),
-        style(&project_dir.display()).underlined()
+        style("BSP updated").bold(),
+        style(project_dir.display()).underlined()
     );
-
-    Ok(project_dir)
+    Ok(())
 }
 
 fn locate_template_file(name: &str, template_folder: impl AsRef<Path>) -> Result<PathBuf> {
     let search_folder = template_folder.as_ref().to_path_buf();
     let file_path = search_folder.join::<&str>(name);
     if file_path.exists() {
-        return Ok(file_path);
+        Ok(file_path)
     } else {
         bail!("{} not found within template", file_path.to_str().unwrap());
     }
@@ -131,13 +294,24 @@ fn expand_template(
     // create a liquid object with the template variables
     let mut liquid_object = create_liquid_object(user_parsed_input)?;
 
-    let project_name = get_project_name(user_parsed_input);
+    // in silent mode, values which would be prompted are collected here
+    let mut missing = MissingValues::default();
+
+    let project_name = get_project_name(user_parsed_input, &mut missing);
 
     // build a supported chip info list
-    let chip_pn = get_chip_pn(user_parsed_input)?;
-    let chip_info = database::resource::get_resource(&chip_pn)?;
-    if user_parsed_input.is_verbose() {
-        info!("{:?}", chip_info);
+    let chip_pn = get_chip_pn(user_parsed_input, &mut missing)?;
+    let mut chip_info = chip_pn
+        .as_ref()
+        .map(database::resource::get_resource)
+        .transpose()?;
+    if let Some(chip_info) = &mut chip_info {
+        if user_parsed_input.soft_float() {
+            chip_info.force_soft_float();
+        }
+        if user_parsed_input.is_verbose() {
+            info!("{}", chip_info);
+        }
     }
 
     // This files must be included in the each project:
@@ -154,27 +328,27 @@ fn expand_template(
         "memory.x".to_string(),
     ];
 
-    let project_type = get_project_type(user_parsed_input, config)?;
-
-    match &project_type {
-        ProjectType::BSPProject => {}
-        ProjectType::EmptyProject => {}
-        ProjectType::DemoProject(demo_name) => {
-            let demo_file = template_dir
-                .join("demo")
-                .join((&demo_name).to_string() + ".rs");
-            // Copy the demo file to the main.rs
-            if demo_file.exists() {
-                std::fs::copy(&demo_file, template_dir.join("src").join("main.rs"))?;
-            } else {
-                bail!("Demo file not found: {}", demo_file.display());
-            }
-            // expand the variable in the demo file
-        }
+    let project_type =
+        get_project_type(user_parsed_input, config, chip_info.as_ref(), &mut missing)?;
+
+    // the files of a demo replace the ones of the template with the same path
+    let (demo_files, demo_dependencies) = match &project_type {
+        Some(ProjectType::DemoProject(demo_name)) => (
+            demos::demo_files(template_dir, config, demo_name, &mut include_files)?,
+            demos::demo_dependencies(config, demo_name)?,
+        ),
+        _ => (Vec::new(), Vec::new()),
     };
     let destination = ProjectDir::try_from((&project_name, user_parsed_input))?;
-    destination.create(user_parsed_input.overwrite())?;
-    set_project_variables(&mut liquid_object, &chip_info, &project_name, &project_type)?;
+    if let (Some(chip_info), Some(project_type)) = (&chip_info, &project_type) {
+        set_project_variables(
+            &mut liquid_object,
+            chip_info,
+            &project_name,
+            project_type,
+            user_parsed_input.clock(),
+        )?;
+    }
 
     info!(
         "🔧 {}",
@@ -196,17 +370,33 @@ fn expand_template(
     fill_placeholders_and_merge_conditionals(
         config,
         &mut liquid_object,
-        user_parsed_input.template_values(),
+        chip_info.as_ref(),
+        user_parsed_input,
+        &mut missing,
     )?;
-    if let ProjectType::DemoProject(demo_name) = &project_type {
+    if let Some(ProjectType::DemoProject(demo_name)) = &project_type {
         fill_demo_variables(
             config,
             &mut liquid_object,
-            user_parsed_input.template_values(),
+            chip_info.as_ref(),
+            user_parsed_input,
+            &mut missing,
             demo_name.clone(),
         )?;
     }
 
+    // report every value which could not be resolved in silent mode at once,
+    // before anything is written
+    missing.check()?;
+    let (Some(chip_pn), Some(chip_info), Some(project_type)) = (chip_pn, chip_info, project_type)
+    else {
+        unreachable!("unresolved values are reported by `MissingValues::check`");
+    };
+    if user_parsed_input.multicore() && project_type == ProjectType::BSPProject {
+        bail!("The BSP is generated for single core projects, pls choose another project type with `--multicore`!");
+    }
+    destination.create(user_parsed_input.overwrite())?;
+
     add_missing_provided_values(&mut liquid_object, user_parsed_input.template_values())?;
 
     // walk/evaluate the template
@@ -217,24 +407,57 @@ fn expand_template(
         &mut include_files,
         &template_config.include,
     )?;
-    template::walk_dir(&include_files, template_dir, &mut liquid_object)?;
-
-    // copy the template files into the project directory
-    for filename in &include_files {
-        let src_path = template_dir.join(filename);
-        let dst_path = destination.as_ref().join(filename);
-        if !dst_path.exists() {
-            std::fs::create_dir_all(dst_path.parent().unwrap())?;
+    // the template is rendered into the destination, it is not modified
+    let template_files = template::template_files(template_dir, &include_files, &demo_files);
+    // directories of the generated crates
+    let mut crate_dirs = Vec::new();
+    if user_parsed_input.multicore() {
+        // a workspace with one crate per core
+        for core_crate in multicore::generate(
+            &template_files,
+            &liquid_object,
+            &chip_info,
+            &project_name,
+            destination.as_ref(),
+        )? {
+            info!(
+                "🔧 {}",
+                style(format!(
+                    "Crate `{}` for the {} ({})",
+                    core_crate.dir, core_crate.core, core_crate.target
+                ))
+                .bold()
+            );
+            crate_dirs.push(destination.as_ref().join(&core_crate.dir));
+        }
+    } else {
+        template::render_files(&template_files, destination.as_ref(), &mut liquid_object)?;
+        crate_dirs.push(destination.as_ref().to_path_buf());
+
+        if template_config.generate_memory_x.unwrap_or(false) {
+            std::fs::write(
+                destination.as_ref().join("memory.x"),
+                render_memory_x(&chip_info, chip_info.core)?,
+            )?;
+        }
+    }
+
+    if !demo_dependencies.is_empty() {
+        for crate_dir in crate_dirs.iter() {
+            let cargo_toml = crate_dir.join("Cargo.toml");
+            if cargo_toml.exists() {
+                bsp::dependencies::add_missing_dependencies(&cargo_toml, &demo_dependencies)?;
+            }
         }
-        std::fs::copy(src_path, dst_path)?;
     }
 
     // write the project config file
-    project_config::write_project_config_file(
-        &destination,
-        project_type.clone(),
-        // BSP settings, for BSP project, not implemented yet
-    )?;
+    project_config::write_project_config_file(&destination, project_type.clone(), &chip_pn)?;
+    if project_type == ProjectType::BSPProject {
+        let project_config =
+            ProjectConfig::from_path(destination.as_ref().join(PROJECT_CONFIG_FILE_NAME))?;
+        bsp::generate(&chip_info, &project_config, destination.as_ref())?;
+    }
     //    config.template.replace(template_config);
     Ok(destination.as_ref().to_owned())
 }
@@ -253,14 +476,12 @@ pub(crate) fn add_missing_provided_values(
         }
         // we have a value without a slot in the liquid object.
         // try to create the slot from the provided value
-        let value = match v {
-            toml::Value::String(content) => liquid_core::Value::Scalar(content.clone().into()),
-            toml::Value::Boolean(content) => liquid_core::Value::Scalar((*content).into()),
-            _ => anyhow::bail!(style(
-                "⛔ Unsupported value type. Only Strings and Booleans are supported."
-            )
+        let Some(value) = toml_to_liquid_value(v) else {
+            anyhow::bail!(style(format!(
+                "⛔ Unsupported value type of `{k}`. Tables are not supported."
+            ))
             .bold()
-            .red(),),
+            .red(),)
         };
         liquid_object.insert(k.clone().into(), value);
         Ok(())
@@ -268,6 +489,24 @@ pub(crate) fn add_missing_provided_values(
     Ok(())
 }
 
+/// Convert a provided value into a liquid value, keeping its type.
+/// Tables are not allowed, also inside of arrays.
+fn toml_to_liquid_value(value: &toml::Value) -> Option<liquid_core::Value> {
+    match value {
+        toml::Value::String(s) => Some(liquid_core::Value::Scalar(s.clone().into())),
+        toml::Value::Integer(i) => Some(liquid_core::Value::Scalar((*i).into())),
+        toml::Value::Float(f) => Some(liquid_core::Value::Scalar((*f).into())),
+        toml::Value::Boolean(b) => Some(liquid_core::Value::Scalar((*b).into())),
+        toml::Value::Datetime(d) => Some(liquid_core::Value::Scalar(d.to_string().into())),
+        toml::Value::Array(a) => a
+            .iter()
+            .map(toml_to_liquid_value)
+            .collect::<Option<Vec<_>>>()
+            .map(liquid_core::Value::Array),
+        toml::Value::Table(_) => None,
+    }
+}
+
 /// Turn things into strings that can be turned into strings
 /// Tables are not allowed and will be ignored
 /// arrays are allowed but will be flattened like so
@@ -289,25 +528,52 @@ fn extract_toml_string(value: &toml::Value) -> Option<String> {
     }
 }
 
+/// Resolve the value of a placeholder from the provided values, or ask the user for it.
+/// In silent mode the default of the placeholder is used, placeholders without a
+/// default are recorded as missing.
+fn resolve_placeholder(
+    slot: &TemplateSlots,
+    chip_info: Option<&ChipInfo>,
+    user_parsed_input: &UserParsedInput,
+    missing: &mut MissingValues,
+) -> Result<liquid_core::Value> {
+    let slot = &match chip_info {
+        Some(chip_info) => slot.restrict_to_peripherals(chip_info)?,
+        None => slot.clone(),
+    };
+    let provided_value = user_parsed_input
+        .template_values()
+        .get(&slot.var_name)
+        .and_then(extract_toml_string);
+    if let Some(define_value) =
+        project_variables::check_input_project_variables(slot, provided_value)
+    {
+        return Ok(define_value);
+    }
+    if !user_parsed_input.is_silent() {
+        return interactive::variable(slot);
+    }
+    if let Some(default_value) = interactive::default_value(slot) {
+        return Ok(default_value);
+    }
+    missing.add(&slot.var_name, interactive::expected_value(slot));
+    // a falsy value, so the conditionals can still be evaluated
+    Ok(liquid_core::Value::Scalar("".into()))
+}
+
 // Evaluate the configuration, adding defined placeholder variables to the liquid object.
 fn fill_placeholders_and_merge_conditionals(
     config: &mut Config,
     liquid_object: &mut Object,
-    template_values: &HashMap<String, toml::Value>,
+    chip_info: Option<&ChipInfo>,
+    user_parsed_input: &UserParsedInput,
+    missing: &mut MissingValues,
 ) -> Result<()> {
     let mut conditionals = config.conditional.take().unwrap_or_default();
     loop {
         // keep evaluating for placeholder variables as long new ones are added.
         project_variables::fill_project_variables(liquid_object, config, |slot| {
-            let provided_value = template_values
-                .get(&slot.var_name)
-                .and_then(extract_toml_string);
-            if let Some(define_value) =
-                project_variables::check_input_project_variables(slot, provided_value)
-            {
-                return Ok(define_value);
-            }
-            interactive::variable(slot)
+            resolve_placeholder(slot, chip_info, user_parsed_input, missing)
         })?;
 
         let placeholders_changed = conditionals
@@ -374,14 +640,14 @@ fn fill_placeholders_and_merge_conditionals(
 fn fill_demo_variables(
     config: &mut Config,
     liquid_object: &mut Object,
-    template_values: &HashMap<String, toml::Value>,
+    chip_info: Option<&ChipInfo>,
+    user_parsed_input: &UserParsedInput,
+    missing: &mut MissingValues,
     demo_name: String,
 ) -> Result<()> {
     let template_slots = config
-        .demo
-        .as_ref()
-        .and_then(|s| s.get(demo_name.as_str()))
-        .map(project_variables::map_to_template_slots)
+        .get_demo(&demo_name)
+        .map(|demo| project_variables::map_to_template_slots(&demo.placeholders))
         .unwrap_or_else(|| Ok(IndexMap::new()))?;
 
     for (&key, slot) in template_slots.iter() {
@@ -391,32 +657,13 @@ fn fill_demo_variables(
             }
             Entry::Vacant(entry) => {
                 // we don't have the file from the config but we can ask for it
-                let value = {
-                    let provided_value = template_values
-                        .get(&slot.var_name)
-                        .and_then(extract_toml_string);
-                    if let Some(define_value) =
-                        project_variables::check_input_project_variables(slot, provided_value)
-                    {
-                        define_value
-                    } else {
-                        interactive::variable(slot)?
-                    }
-                };
+                let value = resolve_placeholder(slot, chip_info, user_parsed_input, missing)?;
                 entry.insert(value);
             }
         }
     }
     project_variables::fill_project_variables(liquid_object, config, |slot| {
-        let provided_value = template_values
-            .get(&slot.var_name)
-            .and_then(extract_toml_string);
-        if let Some(define_value) =
-            project_variables::check_input_project_variables(slot, provided_value)
-        {
-            return Ok(define_value);
-        }
-        interactive::variable(slot)
+        resolve_placeholder(slot, chip_info, user_parsed_input, missing)
     })?;
     Ok(())
 }
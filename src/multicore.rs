@@ -0,0 +1,378 @@
+//! Generate a cargo workspace with one crate per core for the multicore parts
+
+use anyhow::{bail, Result};
+use liquid_core::{Object, Value};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::stm32_device::chip_info::{ArmCore, ChipInfo, MemoryKind, MemoryRegion};
+use crate::stm32_device::memory_x::render_core_memory_x;
+use crate::template::{self, TemplateFile};
+
+/// A crate of the workspace, e.g. `cm7` for the Cortex-M7 of a STM32H745
+#[derive(Debug, Clone)]
+pub struct CoreCrate {
+    pub core: ArmCore,
+    /// directory of the crate in the workspace
+    pub dir: String,
+    pub target: String,
+}
+
+/// The crates of the workspace, the one of the main core comes first
+pub fn core_crates(chip_info: &ChipInfo) -> Result<Vec<CoreCrate>> {
+    let Some(core2) = chip_info.core2 else {
+        bail!(
+            "{} has a single core, a workspace is only generated for multicore parts!",
+            chip_info.cpn
+        );
+    };
+    let core_crate = |core: ArmCore| CoreCrate {
+        core,
+        dir: core.tag().to_lowercase().replace('+', "plus"),
+        target: chip_info.target_of_core(core),
+    };
+    Ok(vec![core_crate(chip_info.core), core_crate(core2)])
+}
+
+/// RAM regions shared by the cores, used for the communication between them
+pub fn ipc_regions(chip_info: &ChipInfo) -> Vec<&MemoryRegion> {
+    chip_info
+        .memory_regions
+        .iter()
+        .filter(|r| r.kind == MemoryKind::Ram && r.core.is_none())
+        .collect()
+}
+
+/// Render the template once per core into `destination/<core>`, with the variables of
+/// the core, and write the workspace `Cargo.toml`.
+///
+/// Every crate gets the `memory.x` of its core and a `.cargo/config.toml` with its target
+/// if the template has none. The workspace gets one too when the cores share the target,
+/// so that it also builds from its root.
+pub fn generate(
+    template_files: &[TemplateFile],
+    liquid_object: &Object,
+    chip_info: &ChipInfo,
+    project_name: &str,
+    destination: &Path,
+) -> Result<Vec<CoreCrate>> {
+    let crates = core_crates(chip_info)?;
+    check_core_memories(chip_info, &crates)?;
+    for core_crate in crates.iter() {
+        let mut core_object = liquid_object.clone();
+        set_core_variables(&mut core_object, chip_info, core_crate, project_name)?;
+        let crate_dir = destination.join(&core_crate.dir);
+        template::render_files(template_files, &crate_dir, &mut core_object)?;
+        fs::write(
+            crate_dir.join("memory.x"),
+            render_core_memory_x(chip_info, core_crate.core)?,
+        )?;
+        let cargo_config = crate_dir.join(".cargo").join("config.toml");
+        if !cargo_config.exists() {
+            fs::create_dir_all(cargo_config.parent().unwrap())?;
+            fs::write(cargo_config, render_cargo_config(&core_crate.target))?;
+        }
+    }
+    fs::write(
+        destination.join("Cargo.toml"),
+        render_workspace_manifest(chip_info, &crates)?,
+    )?;
+    let cargo_config = destination.join(".cargo").join("config.toml");
+    if let Some(target) = workspace_target(&crates) {
+        if !cargo_config.exists() {
+            fs::create_dir_all(cargo_config.parent().unwrap())?;
+            fs::write(cargo_config, render_cargo_config(target))?;
+        }
+    }
+    Ok(crates)
+}
+
+/// The flash and the RAM of a core: its own RAM, or a shared one if it has none
+fn core_memory(chip_info: &ChipInfo, core: ArmCore) -> Result<(&MemoryRegion, &MemoryRegion)> {
+    let regions = chip_info.regions_of_core(core.tag());
+    let flash = regions.iter().find(|r| r.kind == MemoryKind::Flash);
+    let ram = regions
+        .iter()
+        .find(|r| r.kind == MemoryKind::Ram && r.core.is_some())
+        .or_else(|| regions.iter().find(|r| r.kind == MemoryKind::Ram));
+    match (flash, ram) {
+        (Some(flash), Some(ram)) => Ok((flash, ram)),
+        _ => bail!(
+            "{} has no memory region for {}, pls update database!",
+            chip_info.refname,
+            core
+        ),
+    }
+}
+
+/// The cores must not link their code or their data into the same memory
+fn check_core_memories(chip_info: &ChipInfo, crates: &[CoreCrate]) -> Result<()> {
+    let (flash, ram) = core_memory(chip_info, crates[0].core)?;
+    let (flash2, ram2) = core_memory(chip_info, crates[1].core)?;
+    for (first, second) in [(flash, flash2), (ram, ram2)] {
+        if overlaps(first, second) {
+            bail!(
+                "{} of {} and {} of {} overlap, pls update database!",
+                first.name,
+                crates[0].core,
+                second.name,
+                crates[1].core
+            );
+        }
+    }
+    Ok(())
+}
+
+fn overlaps(a: &MemoryRegion, b: &MemoryRegion) -> bool {
+    let end = |r: &MemoryRegion| r.origin as u64 + r.size as u64 * 1024;
+    (a.origin as u64) < end(b) && (b.origin as u64) < end(a)
+}
+
+/// The target of the workspace, if every core has the same one
+fn workspace_target(crates: &[CoreCrate]) -> Option<&str> {
+    let target = crates.first()?.target.as_str();
+    crates.iter().all(|c| c.target == target).then_some(target)
+}
+
+/// Feature of the PAC for a core. The PACs of the multicore parts have one feature per
+/// core, e.g. `stm32h747cm7` and `stm32h747cm4`, the one of the database is for either core.
+fn core_pac_feature(chip_info: &ChipInfo, core: ArmCore) -> String {
+    let suffix = |core: ArmCore| core.tag().to_lowercase().replace('+', "p");
+    std::iter::once(chip_info.core)
+        .chain(chip_info.core2)
+        .find_map(|c| chip_info.pac_feature.strip_suffix(&suffix(c)))
+        .map_or(chip_info.pac_feature.clone(), |base| {
+            format!("{}{}", base, suffix(core))
+        })
+}
+
+/// Variables of one core: `project-name` gets the directory of the crate as suffix,
+/// `target`, `pac_feature`, `memory_x`, `flash_*` and `ram_*` are the ones of the core.
+/// `core_name` and `core_tag` name the core, `ipc_regions` lists the shared RAM regions.
+fn set_core_variables(
+    liquid_object: &mut Object,
+    chip_info: &ChipInfo,
+    core_crate: &CoreCrate,
+    project_name: &str,
+) -> Result<()> {
+    let memory_x = render_core_memory_x(chip_info, core_crate.core)?;
+    let (flash, ram) = core_memory(chip_info, core_crate.core)?;
+
+    let insert = |object: &mut Object, key: &str, value: Value| {
+        object.insert(key.to_string().into(), value);
+    };
+    let scalar = |value: String| Value::Scalar(value.into());
+    insert(
+        liquid_object,
+        "project-name",
+        scalar(format!("{}-{}", project_name, core_crate.dir)),
+    );
+    insert(liquid_object, "target", scalar(core_crate.target.clone()));
+    insert(
+        liquid_object,
+        "pac_feature",
+        scalar(core_pac_feature(chip_info, core_crate.core)),
+    );
+    insert(
+        liquid_object,
+        "core_name",
+        scalar(core_crate.core.to_string()),
+    );
+    insert(
+        liquid_object,
+        "core_tag",
+        scalar(core_crate.core.tag().to_string()),
+    );
+    insert(liquid_object, "multicore", Value::Scalar(true.into()));
+    insert(liquid_object, "memory_x", scalar(memory_x));
+    insert(liquid_object, "flash_origin", scalar(flash.origin_hex()));
+    insert(
+        liquid_object,
+        "flash_size",
+        Value::Scalar(flash.size.into()),
+    );
+    insert(liquid_object, "ram_origin", scalar(ram.origin_hex()));
+    insert(liquid_object, "ram_size", Value::Scalar(ram.size.into()));
+    let ipc = ipc_regions(chip_info)
+        .into_iter()
+        .map(|region| {
+            let mut object = Object::new();
+            insert(&mut object, "name", scalar(region.name.clone()));
+            insert(
+                &mut object,
+                "section",
+                scalar(format!(".{}", region.name.to_lowercase())),
+            );
+            insert(&mut object, "origin", scalar(region.origin_hex()));
+            insert(&mut object, "size", Value::Scalar(region.size.into()));
+            Value::Object(object)
+        })
+        .collect();
+    insert(liquid_object, "ipc_regions", Value::Array(ipc));
+    Ok(())
+}
+
+/// `Cargo.toml` at the root of the workspace
+fn render_workspace_manifest(chip_info: &ChipInfo, crates: &[CoreCrate]) -> Result<String> {
+    let mut manifest = String::new();
+    writeln!(
+        manifest,
+        "# Workspace of {}, one crate per core, generated by cargo-stm32bs",
+        chip_info.cpn
+    )?;
+    writeln!(manifest, "[workspace]")?;
+    writeln!(manifest, "resolver = \"2\"")?;
+    writeln!(
+        manifest,
+        "members = [{}]",
+        crates
+            .iter()
+            .map(|c| format!("\"{}\"", c.dir))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )?;
+    Ok(manifest)
+}
+
+/// `.cargo/config.toml` of a crate, cargo reads it when it is run in the crate directory
+fn render_cargo_config(target: &str) -> String {
+    format!("[build]\ntarget = \"{target}\"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid_core::ValueView;
+
+    fn chip_info() -> ChipInfo {
+        let region = |name: &str, kind, origin, size, core: Option<&str>| MemoryRegion {
+            name: name.to_string(),
+            kind,
+            origin,
+            size,
+            core: core.map(str::to_string),
+        };
+        ChipInfo {
+            core2: Some(ArmCore::CortexM4),
+            co_type: Some("Cortex-M4".to_string()),
+            co_freq: Some(240),
+            freq: 480,
+            memory_regions: vec![
+                region("FLASH", MemoryKind::Flash, 0x0800_0000, 1024, Some("CM7")),
+                region(
+                    "FLASH_CM4",
+                    MemoryKind::Flash,
+                    0x0810_0000,
+                    1024,
+                    Some("CM4"),
+                ),
+                region("AXISRAM", MemoryKind::Ram, 0x2400_0000, 512, None),
+                region("DTCM", MemoryKind::Ram, 0x2000_0000, 128, Some("CM7")),
+                region("SRAM1", MemoryKind::Ram, 0x3000_0000, 128, Some("CM4")),
+                region("SRAM4", MemoryKind::Ram, 0x3800_0000, 64, None),
+            ],
+            pac_feature: "stm32h747cm7".to_string(),
+            ..ChipInfo::test_part("STM32H745ZIT6", ArmCore::CortexM7)
+        }
+    }
+
+    #[test]
+    fn it_names_a_crate_per_core() {
+        let chip_info = chip_info();
+        let crates = core_crates(&chip_info).unwrap();
+        assert_eq!(
+            crates
+                .iter()
+                .map(|c| (c.dir.as_str(), c.target.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("cm7", "thumbv7em-none-eabihf"),
+                ("cm4", "thumbv7em-none-eabihf")
+            ]
+        );
+        let mut soft_float = chip_info.clone();
+        soft_float.force_soft_float();
+        assert!(core_crates(&soft_float)
+            .unwrap()
+            .iter()
+            .all(|c| c.target == "thumbv7em-none-eabi"));
+        let names: Vec<&str> = ipc_regions(&chip_info)
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["AXISRAM", "SRAM4"]);
+        assert_eq!(
+            render_workspace_manifest(&chip_info, &crates).unwrap(),
+            "# Workspace of STM32H745ZIT6, one crate per core, generated by cargo-stm32bs\n\
+             [workspace]\nresolver = \"2\"\nmembers = [\"cm7\", \"cm4\"]\n"
+        );
+
+        let mut single_core = chip_info.clone();
+        single_core.core2 = None;
+        assert!(core_crates(&single_core).is_err());
+    }
+
+    #[test]
+    fn it_sets_the_variables_of_a_core() {
+        let chip_info = chip_info();
+        let crates = core_crates(&chip_info).unwrap();
+        let mut object = Object::new();
+        set_core_variables(&mut object, &chip_info, &crates[1], "dual").unwrap();
+        let get = |key: &str| object.get(key).unwrap().to_kstr().to_string();
+        assert_eq!(get("project-name"), "dual-cm4");
+        assert_eq!(get("core_tag"), "CM4");
+        assert_eq!(get("flash_origin"), "0x08100000");
+        assert_eq!(get("ram_origin"), "0x30000000");
+        assert_eq!(get("pac_feature"), "stm32h747cm4");
+        assert!(get("memory_x").contains("SECTIONS"));
+        assert_eq!(
+            core_pac_feature(&chip_info, ArmCore::CortexM7),
+            "stm32h747cm7"
+        );
+
+        let wl55 = ChipInfo {
+            core2: Some(ArmCore::CortexM0Plus),
+            pac_feature: "stm32wl5x_cm4".to_string(),
+            ..ChipInfo::test_part("STM32WL55JCI7", ArmCore::CortexM4)
+        };
+        assert_eq!(
+            core_pac_feature(&wl55, ArmCore::CortexM0Plus),
+            "stm32wl5x_cm0p"
+        );
+    }
+
+    #[test]
+    fn it_refuses_cores_sharing_their_memory() {
+        let chip_info = chip_info();
+        let crates = core_crates(&chip_info).unwrap();
+        check_core_memories(&chip_info, &crates).unwrap();
+
+        // both cores fall back to AXISRAM without their own RAM
+        let mut shared_ram = chip_info.clone();
+        shared_ram
+            .memory_regions
+            .retain(|r| !matches!(r.name.as_str(), "DTCM" | "SRAM1"));
+        let error = check_core_memories(&shared_ram, &crates).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "AXISRAM of Cortex-M7 and AXISRAM of Cortex-M4 overlap, pls update database!"
+        );
+
+        let mut overlapping_flash = chip_info.clone();
+        overlapping_flash.memory_regions[1].origin = 0x080f_0000;
+        assert!(check_core_memories(&overlapping_flash, &crates).is_err());
+    }
+
+    #[test]
+    fn it_sets_the_target_of_the_workspace() {
+        let crates = core_crates(&chip_info()).unwrap();
+        assert_eq!(workspace_target(&crates), Some("thumbv7em-none-eabihf"));
+
+        let wl55 = ChipInfo {
+            core2: Some(ArmCore::CortexM0Plus),
+            ..ChipInfo::test_part("STM32WL55JCI7", ArmCore::CortexM4)
+        };
+        assert_eq!(workspace_target(&core_crates(&wl55).unwrap()), None);
+    }
+}
@@ -41,9 +41,29 @@ impl ProjectConfig {
         };
         Ok(config)
     }
+
+    /// Get a string value of the `[project]` table
+    pub fn project_value(&self, key: &str) -> Option<&str> {
+        self.project
+            .as_ref()
+            .and_then(|s| s.get(key))
+            .and_then(|s| s.as_str())
+    }
 }
 
-pub fn check_config_file() -> Result<PathBuf> {
+/// Find `.stm32bs.toml` in `project_dir`, or without a directory search it
+/// from the current directory upwards
+pub fn check_config_file(project_dir: Option<&Path>) -> Result<PathBuf> {
+    if let Some(project_dir) = project_dir {
+        let config_file = project_dir.join(PROJECT_CONFIG_FILE_NAME);
+        if !config_file.exists() {
+            bail!(
+                "Project config file not found in {}!",
+                project_dir.display()
+            );
+        }
+        return Ok(config_file);
+    }
     let mut search_path = env::current_dir()?;
     loop {
         let config_file = search_path.join(PROJECT_CONFIG_FILE_NAME);
@@ -62,13 +82,25 @@ pub fn check_config_file() -> Result<PathBuf> {
 pub fn write_project_config_file(
     project_path: impl AsRef<Path>,
     project_type: ProjectType,
-    // BSP settings
+    chip_pn: &str,
 ) -> Result<()> {
-    let mut config = ProjectConfig::default();
-    config.project = Some(HashMap::from([(
-        "project_type".to_string(),
-        toml::Value::String(project_type.to_string()),
-    )]));
+    let mut config = ProjectConfig {
+        project: Some(HashMap::from([
+            (
+                "project_type".to_string(),
+                toml::Value::String(project_type.to_string()),
+            ),
+            ("chip".to_string(), toml::Value::String(chip_pn.to_string())),
+        ])),
+        ..Default::default()
+    };
+    if project_type == ProjectType::BSPProject {
+        // BSP settings, filled by the user and used to generate the `bsp` module
+        config.peripheral = Some(HashMap::new());
+        config.pinmap = Some(HashMap::new());
+        config.driver = Some(HashMap::new());
+        config.middleware = Some(HashMap::new());
+    }
     let config_file = project_path.as_ref().join(PROJECT_CONFIG_FILE_NAME);
     let toml_string = toml::to_string(&config)?;
     fs::write(config_file, toml_string)?;
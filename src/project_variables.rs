@@ -1,14 +1,15 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use console::style;
 use indexmap::IndexMap;
 use liquid_core::model::map::Entry;
 use liquid_core::Object;
 use liquid_core::{Value, ValueView};
-use log::info;
+use log::{info, warn};
 use regex::Regex;
 use thiserror::Error;
 
 use crate::interactive::LIST_SEP;
+use crate::stm32_device::chip_info::ChipInfo;
 use crate::template_config::{Config, TemplateSlotsTable};
 
 #[derive(Debug, Clone)]
@@ -16,6 +17,88 @@ pub struct TemplateSlots {
     pub(crate) var_name: String,
     pub(crate) var_info: VarInfo,
     pub(crate) prompt: String,
+    /// Kind of peripheral the choices are instances of, e.g. `USART`
+    pub(crate) peripheral: Option<String>,
+}
+
+impl TemplateSlots {
+    /// Restrict the choices of a placeholder with a `peripheral` kind to the instances the
+    /// part has. The instances are the choices if the placeholder lists none.
+    /// Databases without a peripheral inventory keep the choices of the placeholder, a
+    /// placeholder without choices becomes a free text with a warning.
+    pub(crate) fn restrict_to_peripherals(&self, chip_info: &ChipInfo) -> Result<TemplateSlots> {
+        let Some(kind) = &self.peripheral else {
+            return Ok(self.clone());
+        };
+        if chip_info.peripherals.is_empty() {
+            return Ok(self.unverified_peripheral(chip_info, kind));
+        }
+        let instances: Vec<String> = chip_info
+            .peripherals_of_kind(kind)
+            .iter()
+            .map(|p| p.instance.clone())
+            .collect();
+        let restrict = |choices: &Vec<String>| -> Result<Vec<String>> {
+            let restricted: Vec<String> = if choices.is_empty() {
+                instances.clone()
+            } else {
+                choices
+                    .iter()
+                    .filter(|c| instances.iter().any(|i| i.eq_ignore_ascii_case(c)))
+                    .cloned()
+                    .collect()
+            };
+            if restricted.is_empty() {
+                bail!(
+                    "{} has no {} for placeholder `{}`",
+                    chip_info.refname,
+                    kind,
+                    self.var_name
+                );
+            }
+            Ok(restricted)
+        };
+        let mut slot = self.clone();
+        match &mut slot.var_info {
+            VarInfo::Select { choices, default } => {
+                *choices = restrict(choices)?;
+                default.take_if(|d| !choices.contains(d));
+            }
+            VarInfo::MultiSelect { entry } => {
+                entry.choices = restrict(&entry.choices)?;
+                if let Some(default) = &mut entry.default {
+                    default.retain(|d| entry.choices.contains(d));
+                }
+            }
+            _ => {}
+        }
+        Ok(slot)
+    }
+
+    /// The placeholder for a part without peripheral inventory, its instances are unknown
+    fn unverified_peripheral(&self, chip_info: &ChipInfo, kind: &str) -> TemplateSlots {
+        let mut slot = self.clone();
+        let default = match &self.var_info {
+            VarInfo::Select { choices, default } if choices.is_empty() => default.clone(),
+            VarInfo::MultiSelect { entry } if entry.choices.is_empty() => {
+                entry.default.as_ref().map(|d| d.join(LIST_SEP))
+            }
+            _ => return slot,
+        };
+        warn!(
+            "{}",
+            style(format!(
+                "The database has no peripherals of {}, placeholder `{}` takes any {}",
+                chip_info.refname, self.var_name, kind
+            ))
+            .yellow()
+        );
+        slot.var_info = VarInfo::String {
+            regex: None,
+            default,
+        };
+        slot
+    }
 }
 
 /// Information needed to prompt for a typed value
@@ -34,12 +117,15 @@ pub enum VarInfo {
     },
     String {
         regex: Option<Regex>,
+        default: Option<String>,
     },
     Text {
         regex: Option<Regex>,
+        default: Option<String>,
     },
     Integer {
         range: Option<(i32, i32)>,
+        default: Option<i32>,
     },
 }
 
@@ -67,6 +153,8 @@ pub enum ConversionError {
     InvalidVariableType { var_name: String, value: String },
     #[error("{var_type} type does not support `choices` field")]
     UnsupportedChoices { var_type: String },
+    #[error("{var_type} type does not support `peripheral` field")]
+    UnsupportedPeripheral { var_type: String },
     #[error("missing `choices` field on `{var_name}`")]
     MissingChoices { var_name: String },
     #[error("bool type does not support `regex` field")]
@@ -132,7 +220,7 @@ pub fn show_project_variables_with_value(template_object: &Object, config: &Conf
 pub fn fill_project_variables(
     template_object: &mut Object,
     config: &Config,
-    value_provider: impl Fn(&TemplateSlots) -> Result<Value>,
+    mut value_provider: impl FnMut(&TemplateSlots) -> Result<Value>,
 ) -> Result<()> {
     let template_slots = config
         .placeholders
@@ -159,18 +247,14 @@ pub fn check_input_project_variables(
     slot: &TemplateSlots,
     provided_value: Option<String>,
 ) -> Option<Value> {
-    let value = match provided_value {
-        Some(value) => value,
-        None => return None,
-    };
+    let value = provided_value?;
     match slot.clone().var_info {
         VarInfo::Bool { .. } => {
-            let as_bool = value.parse::<bool>();
-            if as_bool.is_ok() {
-                return Some(Value::Scalar(as_bool.unwrap().into()));
+            if let Ok(as_bool) = value.parse::<bool>() {
+                return Some(Value::Scalar(as_bool.into()));
             }
         }
-        VarInfo::Integer { range } => {
+        VarInfo::Integer { range, .. } => {
             if let Ok(as_int) = value.trim().parse::<i32>() {
                 if let Some((min, max)) = range {
                     if as_int >= min && as_int <= max {
@@ -182,23 +266,13 @@ pub fn check_input_project_variables(
             }
         }
         VarInfo::String { regex, .. } => {
-            if regex.is_none() {
+            if regex.is_none_or(|regex| regex.is_match(&value)) {
                 return Some(Value::Scalar(value.into()));
-            } else {
-                let regex = regex.as_ref().unwrap();
-                if regex.is_match(&value) {
-                    return Some(Value::Scalar(value.into()));
-                }
             }
         }
         VarInfo::Text { regex, .. } => {
-            if regex.is_none() {
+            if regex.is_none_or(|regex| regex.is_match(&value)) {
                 return Some(Value::Scalar(value.into()));
-            } else {
-                let regex = regex.as_ref().unwrap();
-                if regex.is_match(&value) {
-                    return Some(Value::Scalar(value.into()));
-                }
             }
         }
         VarInfo::Select { choices, .. } => {
@@ -266,7 +340,8 @@ fn try_key_value_into_slot(
     let regex = extract_regex(key, var_type, table.get("regex"))?;
     let range = extract_range(key, var_type, table.get("range"))?;
     let prompt = extract_prompt(key, table.get("prompt"))?;
-    let choices = extract_choices(key, var_type, table.get("choices"))?;
+    let peripheral = extract_peripheral(key, var_type, table.get("peripheral"))?;
+    let choices = extract_choices(key, var_type, table.get("choices"), peripheral.is_some())?;
 
     let var_info = match var_type {
         SupportedVarType::Bool => VarInfo::Bool {
@@ -276,8 +351,22 @@ fn try_key_value_into_slot(
                 None
             },
         },
-        SupportedVarType::Integer => VarInfo::Integer { range },
-        SupportedVarType::String => VarInfo::String { regex },
+        SupportedVarType::Integer => VarInfo::Integer {
+            range,
+            default: if let Some(toml::Value::Integer(value)) = table.get("default") {
+                Some(*value as i32)
+            } else {
+                None
+            },
+        },
+        SupportedVarType::String => VarInfo::String {
+            regex,
+            default: if let Some(toml::Value::String(value)) = table.get("default") {
+                Some(value.to_string())
+            } else {
+                None
+            },
+        },
         SupportedVarType::Select => VarInfo::Select {
             choices: choices.unwrap_or_default(),
             default: if let Some(toml::Value::String(value)) = table.get("default") {
@@ -301,12 +390,20 @@ fn try_key_value_into_slot(
                 choices: choices.unwrap_or_default(),
             },
         },
-        SupportedVarType::Text => VarInfo::Text { regex },
+        SupportedVarType::Text => VarInfo::Text {
+            regex,
+            default: if let Some(toml::Value::String(value)) = table.get("default") {
+                Some(value.to_string())
+            } else {
+                None
+            },
+        },
     };
     Ok(TemplateSlots {
         var_name: key.to_string(),
         var_info,
         prompt: format!("🤷 {}", style(&prompt).bold()),
+        peripheral,
     })
 }
 
@@ -331,11 +428,11 @@ fn extract_range(
                     return Ok(Some((r1 as i32, r2 as i32)));
                 }
             }
-            return Err(ConversionError::WrongTypeParameter {
+            Err(ConversionError::WrongTypeParameter {
                 var_name: var_name.into(),
                 parameter: "range".to_string(),
                 correct_type: "Integer Array".to_string(),
-            });
+            })
         }
         (_, Some(_)) => Err(ConversionError::WrongTypeParameter {
             var_name: var_name.into(),
@@ -400,6 +497,30 @@ fn extract_type(
     }
 }
 
+fn extract_peripheral(
+    var_name: &str,
+    var_type: SupportedVarType,
+    table_entry: Option<&toml::Value>,
+) -> Result<Option<String>, ConversionError> {
+    match (var_type, table_entry) {
+        (
+            SupportedVarType::Select | SupportedVarType::MultiSelect,
+            Some(toml::Value::String(value)),
+        ) => Ok(Some(value.to_uppercase())),
+        (SupportedVarType::Select | SupportedVarType::MultiSelect, Some(_)) => {
+            Err(ConversionError::WrongTypeParameter {
+                var_name: var_name.into(),
+                parameter: "peripheral".into(),
+                correct_type: "String".into(),
+            })
+        }
+        (_, Some(_)) => Err(ConversionError::UnsupportedPeripheral {
+            var_type: format!("{var_type:?}"),
+        }),
+        (_, None) => Ok(None),
+    }
+}
+
 fn extract_prompt(
     var_name: &str,
     table_entry: Option<&toml::Value>,
@@ -417,10 +538,13 @@ fn extract_prompt(
     }
 }
 
+/// The choices of a select, they are optional if the placeholder has a `peripheral` kind,
+/// the instances of the part are offered then
 fn extract_choices(
     var_name: &str,
     var_type: SupportedVarType,
     table_entry: Option<&toml::Value>,
+    has_peripheral: bool,
 ) -> Result<Option<Vec<String>>, ConversionError> {
     match (table_entry, var_type) {
         (
@@ -456,6 +580,9 @@ fn extract_choices(
                 correct_type: "String Array".to_string(),
             })
         }
+        (None, SupportedVarType::Select | SupportedVarType::MultiSelect) if has_peripheral => {
+            Ok(None)
+        }
         (None, SupportedVarType::Select | SupportedVarType::MultiSelect) => {
             Err(ConversionError::MissingChoices {
                 var_name: var_name.into(),
@@ -1,26 +1,32 @@
 use anyhow::bail;
 use anyhow::Result;
 use core::fmt;
+use serde::{Serialize, Serializer};
 //use std::path::Path;
 
-///! This module contains the ChipInfo struct and its associated methods.
-///! It is used to parse the STM32 chip information from a CSV file.
-///! The ChipInfo struct contains various fields such as part number, family, description,
-///! status, package, core, frequency, FPU, co_type, co_freq, flash, RAM1, RAM2, RAM3,
-///! and target, and pac information.
+/// This module contains the ChipInfo struct and its associated methods.
+/// It is used to parse the STM32 chip information from a CSV file.
+/// The ChipInfo struct contains various fields such as part number, family, description,
+/// status, package, core, frequency, FPU, co_type, co_freq, flash, RAM1, RAM2, RAM3,
+/// and target, and pac information.
+#[derive(Debug, Clone, Serialize)]
 pub struct ChipInfo {
     pub cpn: String,
     pub refname: String,
     pub family: String,
-    //    pub description: String,
-    //    pub status: ChipStatus,
-    //    pub package: String,
+    pub description: String,
+    pub status: ChipStatus,
+    /// Package with its pin count, e.g. `LQFP48`
+    pub package: String,
     pub core: ArmCore,
     pub core2: Option<ArmCore>,
     pub freq: u32,
-    //    pub fpu: bool,
-    //    pub co_type: Option<String>,
-    //    pub co_freq: Option<u32>,
+    /// FPU of the main core, the second core of the STM32H7 has a single precision one
+    pub fpu: Fpu,
+    /// Coprocessor, e.g. the Cortex-M4 of a STM32H745
+    pub co_type: Option<String>,
+    /// Frequency of the coprocessor in MHz
+    pub co_freq: Option<u32>,
     pub flash: u32,
     pub ram: u32,
     pub ccmram: u32,
@@ -28,51 +34,361 @@ pub struct ChipInfo {
     pub pac_name: String,
     pub pac_ver: String,
     pub pac_feature: String,
+    /// Memory regions, the first flash and RAM regions are the ones a program is linked to
+    pub memory_regions: Vec<MemoryRegion>,
+    /// Oscillators of the family with their default frequency, e.g. HSI and MSI
+    pub oscillators: Vec<Oscillator>,
+    /// Peripheral instances, empty if the database has no peripheral inventory
+    pub peripherals: Vec<Peripheral>,
 }
 
-impl std::fmt::Display for ChipInfo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "CPN: {}\r\n\trefname: {}\r\n\tFamily: {}\r\n\tCore: {:?}\r\n\t\
-            Freq: {:?}\r\n\tFlash: {}\r\n\tRAM: {}\r\n\tCCMRAM: {}\r\n\t\
-            Target: {}\r\n\t\tPAC: {}\r\n\t\tver: {}\r\n\t\tfeature: {}",
-            self.cpn,
-            self.refname,
-            self.family,
-            self.core,
-            self.freq,
-            self.flash,
-            self.ram,
-            self.ccmram,
-            self.target,
-            self.pac_name,
-            self.pac_ver,
-            self.pac_feature,
-        )
+impl ChipInfo {
+    /// The first region of a kind, the one a program is linked to
+    pub fn main_region(&self, kind: MemoryKind) -> Option<&MemoryRegion> {
+        self.memory_regions.iter().find(|r| r.kind == kind)
+    }
+
+    /// The regions a core can use: the shared ones and its own, in the order of the database.
+    /// `core` is the tag of the regions, e.g. `CM4`
+    pub fn regions_of_core(&self, core: &str) -> Vec<&MemoryRegion> {
+        self.memory_regions
+            .iter()
+            .filter(|r| r.core.as_ref().is_none_or(|c| c.eq_ignore_ascii_case(core)))
+            .collect()
+    }
+
+    /// A region by its name, e.g. `CCMRAM`
+    pub fn region(&self, name: &str) -> Option<&MemoryRegion> {
+        self.memory_regions
+            .iter()
+            .find(|r| r.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The target is a hard-float one, `eabihf`
+    pub fn hard_float(&self) -> bool {
+        self.target.ends_with("eabihf")
+    }
+
+    /// Use the soft-float target even if the core has an FPU
+    pub fn force_soft_float(&mut self) {
+        self.target = self.core.target(false).to_string();
+    }
+
+    /// Rust target of a core of the part, the cores of a hard-float part with an FPU
+    /// get a hard-float target
+    pub fn target_of_core(&self, core: ArmCore) -> String {
+        if core.tag() == self.core.tag() {
+            self.target.clone()
+        } else {
+            core.target(self.hard_float() && core.has_fpu_option())
+                .to_string()
+        }
+    }
+
+    /// A peripheral by its instance name, e.g. `USART2`
+    pub fn peripheral(&self, instance: &str) -> Option<&Peripheral> {
+        self.peripherals
+            .iter()
+            .find(|p| p.instance.eq_ignore_ascii_case(instance))
+    }
+
+    /// The instances of a kind of peripheral, e.g. `USART1` and `USART2` for `USART`
+    pub fn peripherals_of_kind(&self, kind: &str) -> Vec<&Peripheral> {
+        self.peripherals
+            .iter()
+            .filter(|p| p.kind().eq_ignore_ascii_case(kind))
+            .collect()
+    }
+
+    /// Default frequency of an oscillator in Hz, e.g. `HSI`
+    pub fn oscillator(&self, name: &str) -> Result<u32> {
+        match self
+            .oscillators
+            .iter()
+            .find(|o| o.name.eq_ignore_ascii_case(name))
+        {
+            Some(oscillator) => Ok(oscillator.frequency),
+            None => bail!(
+                "The {} frequency of {} is absent, pls update the template!",
+                name.to_uppercase(),
+                self.family
+            ),
+        }
+    }
+
+    /// Frequency of SYSCLK after reset in Hz: the one of `RESET_CLOCK`, HSI otherwise
+    pub fn reset_clock(&self) -> Result<u32> {
+        match RESET_CLOCK.iter().find(|(f, _, _)| *f == self.family) {
+            Some((_, _, frequency)) => Ok(*frequency),
+            None => self.oscillator("HSI"),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ChipInfo {
+    /// A part for the unit tests, decoded from its part number, e.g. `STM32G071CBT6`.
+    /// It has the legacy memory regions and oscillators of its family and no peripheral,
+    /// the tests change the fields they need, e.g. `core2` or `memory_regions`.
+    pub fn test_part(cpn: &str, core: ArmCore) -> ChipInfo {
+        use crate::stm32_device::chip_pn::package_from_refname;
+        use crate::stm32_device::part_number::PartNumber;
+
+        let part = PartNumber::parse(cpn).unwrap();
+        let line = part.line.clone().unwrap();
+        let refname = format!(
+            "STM32{}{}{}{}x",
+            line,
+            part.pin_code.unwrap().0,
+            part.flash_code.unwrap().0,
+            part.package_code.unwrap().0
+        );
+        let flash = part.flash_code.unwrap().1;
+        let fpu = Fpu::of_part(&part.family, &refname);
+        ChipInfo {
+            cpn: cpn.to_string(),
+            package: package_from_refname(&refname).unwrap(),
+            family: part.family.clone(),
+            description: String::new(),
+            status: ChipStatus::Active,
+            core,
+            core2: None,
+            freq: 64,
+            fpu,
+            co_type: None,
+            co_freq: None,
+            flash,
+            ram: 128,
+            ccmram: 0,
+            target: core.target(fpu != Fpu::None).to_string(),
+            pac_name: part.family.to_lowercase(),
+            pac_ver: "0.15.1".to_string(),
+            pac_feature: format!("stm32{}", line.to_lowercase()),
+            memory_regions: MemoryRegion::legacy_regions(flash, 128, 0),
+            oscillators: Oscillator::legacy_oscillators(&part.family),
+            peripherals: Vec::new(),
+            refname,
+        }
+    }
+}
+
+/// An oscillator of a family, e.g. `HSI`, `MSI`, `LSI` or the usual `HSE` crystal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Oscillator {
+    pub name: String,
+    /// default frequency in Hz
+    pub frequency: u32,
+}
+
+impl Oscillator {
+    /// HSI of the built-in table, for databases without oscillators
+    pub fn legacy_oscillators(family: &str) -> Vec<Oscillator> {
+        HSI_DEFAULT
+            .iter()
+            .filter(|(f, _)| *f == family)
+            .map(|(_, frequency)| Oscillator {
+                name: "HSI".to_string(),
+                frequency: *frequency,
+            })
+            .collect()
+    }
+}
+
+/// Kind of a memory region, as written in the column `memory.kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryKind {
+    Flash,
+    Ram,
+    /// One-time programmable area
+    Otp,
+    OptionBytes,
+    /// A bank of a dual-bank flash, it lies inside a `Flash` region
+    FlashBank,
+}
+
+impl MemoryKind {
+    pub fn try_from_str<T: ToString>(data: T) -> Result<MemoryKind> {
+        match data.to_string().to_lowercase().as_str() {
+            "flash" => Ok(MemoryKind::Flash),
+            "ram" => Ok(MemoryKind::Ram),
+            "otp" => Ok(MemoryKind::Otp),
+            "option_bytes" | "option bytes" => Ok(MemoryKind::OptionBytes),
+            "flash_bank" | "flash bank" => Ok(MemoryKind::FlashBank),
+            s => bail!("`{}` is unknown memory kind!", s),
+        }
     }
+
+    /// Value of the column `memory.kind`
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemoryKind::Flash => "flash",
+            MemoryKind::Ram => "ram",
+            MemoryKind::Otp => "otp",
+            MemoryKind::OptionBytes => "option_bytes",
+            MemoryKind::FlashBank => "flash_bank",
+        }
+    }
+
+    /// Regions a program can be linked to. OTP and option bytes are not written by the
+    /// linker, a flash bank is already part of its flash region.
+    pub fn is_linked(&self) -> bool {
+        matches!(self, MemoryKind::Flash | MemoryKind::Ram)
+    }
+}
+
+/// A memory region of a part, as written in `memory.x`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemoryRegion {
+    pub name: String,
+    pub kind: MemoryKind,
+    pub origin: u32,
+    /// size in KB
+    pub size: u32,
+    /// Core which the region belongs to on multicore parts, `None` if it is shared
+    pub core: Option<String>,
+}
+
+impl MemoryRegion {
+    /// Origin as written in `memory.x`, e.g. `0x08000000`
+    pub fn origin_hex(&self) -> String {
+        format!("0x{:08X}", self.origin)
+    }
+
+    /// Regions of the classic STM32 layout, for databases without memory regions
+    pub fn legacy_regions(flash: u32, ram: u32, ccmram: u32) -> Vec<MemoryRegion> {
+        let region = |name: &str, kind, origin, size| MemoryRegion {
+            name: name.to_string(),
+            kind,
+            origin,
+            size,
+            core: None,
+        };
+        let mut regions = vec![
+            region("FLASH", MemoryKind::Flash, 0x0800_0000, flash),
+            region("RAM", MemoryKind::Ram, 0x2000_0000, ram),
+        ];
+        if ccmram > 0 {
+            regions.push(region("CCMRAM", MemoryKind::Ram, 0x1000_0000, ccmram));
+        }
+        regions
+    }
+}
+
+/// A peripheral instance of a part, e.g. `USART2`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Peripheral {
+    pub instance: String,
+    /// Name of the IP in the open pin data, e.g. `USART`
+    pub name: String,
+    /// Version of the IP in the open pin data, e.g. `sci3_v1_1_Cube`
+    pub version: String,
+    /// Bus the peripheral is connected to, e.g. `APB1`
+    pub bus: Option<String>,
+    /// RCC register enabling the clock of the peripheral, e.g. `APBENR1`
+    pub enable_register: Option<String>,
+    /// Bit of the peripheral in `enable_register`
+    pub enable_bit: Option<u8>,
+    pub irqs: Vec<PeripheralIrq>,
+    pub dma_requests: Vec<DmaRequest>,
+}
+
+/// An interrupt of a peripheral, e.g. `USART2_LPUART2` number 28
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeripheralIrq {
+    pub name: String,
+    pub number: u16,
+}
+
+/// A DMA request of a peripheral, e.g. `USART2_TX` on channel 3 of `DMA1`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DmaRequest {
+    pub signal: String,
+    pub controller: String,
+    /// Fixed channel or stream, `None` if any channel can be used through the DMAMUX
+    pub channel: Option<u8>,
+    /// Request number of the DMAMUX
+    pub request: Option<u16>,
+}
+
+impl Peripheral {
+    /// Kind of the peripheral, e.g. `USART` for `USART2`
+    pub fn kind(&self) -> &str {
+        peripheral_kind(&self.instance)
+    }
+}
+
+/// Kind of a peripheral instance, the instance without its number
+pub fn peripheral_kind(instance: &str) -> &str {
+    instance.trim_end_matches(|c: char| c.is_ascii_digit())
 }
 
-impl std::fmt::Debug for ChipInfo {
+impl std::fmt::Display for ChipInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Part number: {}", self.cpn)?;
+        writeln!(f, "  Refname:   {}", self.refname)?;
+        writeln!(f, "  Family:    {}", self.family)?;
+        if !self.description.is_empty() {
+            writeln!(f, "  Description: {}", self.description)?;
+        }
+        writeln!(f, "  Status:    {}", self.status)?;
+        writeln!(f, "  Package:   {}", self.package)?;
+        match &self.core2 {
+            Some(core2) => writeln!(f, "  Core:      {} + {}", self.core, core2)?,
+            None => writeln!(f, "  Core:      {}", self.core)?,
+        }
+        writeln!(f, "  Frequency: {} MHz", self.freq)?;
+        writeln!(f, "  FPU:       {}", self.fpu)?;
+        if let Some(co_type) = &self.co_type {
+            match self.co_freq {
+                Some(co_freq) => writeln!(f, "  Coprocessor: {} {} MHz", co_type, co_freq)?,
+                None => writeln!(f, "  Coprocessor: {}", co_type)?,
+            }
+        }
+        writeln!(f, "  Flash:     {} KB", self.flash)?;
+        writeln!(f, "  RAM:       {} KB", self.ram)?;
+        writeln!(f, "  CCMRAM:    {} KB", self.ccmram)?;
+        writeln!(f, "  Target:    {}", self.target)?;
         write!(
             f,
-            "CPN: {}\r\n\trefname: {}\r\n\tFamily: {}\r\n\tCore: {:?}\r\n\t\
-            Freq: {:?}\r\n\tFlash: {}\r\n\tRAM: {}\r\n\tCCMRAM: {}\r\n\t\
-            Target: {}\r\n\t\tPAC: {}\r\n\t\tver: {}\r\n\t\tfeature: {}",
-            self.cpn,
-            self.refname,
-            self.family,
-            self.core,
-            self.freq,
-            self.flash,
-            self.ram,
-            self.ccmram,
-            self.target,
-            self.pac_name,
-            self.pac_ver,
-            self.pac_feature,
-        )
+            "  PAC:       {} {} (feature `{}`)",
+            self.pac_name, self.pac_ver, self.pac_feature
+        )?;
+        for region in self.memory_regions.iter() {
+            write!(
+                f,
+                "\n  {:<10} {} {} KB",
+                format!("{}:", region.name),
+                region.origin_hex(),
+                region.size
+            )?;
+            if let Some(core) = &region.core {
+                write!(f, " ({core})")?;
+            }
+        }
+        if !self.oscillators.is_empty() {
+            write!(
+                f,
+                "\n  Oscillators: {}",
+                self.oscillators
+                    .iter()
+                    .map(|o| format!("{} {} Hz", o.name, o.frequency))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )?;
+        }
+        if !self.peripherals.is_empty() {
+            write!(
+                f,
+                "\n  Peripherals: {}",
+                self.peripherals
+                    .iter()
+                    .map(|p| p.instance.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -88,21 +404,61 @@ pub enum ArmCore {
     CortexM55,
 }
 
-impl ToString for ArmCore {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for ArmCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ArmCore::CortexM0 => "Cortex-M0".to_string(),
-            ArmCore::CortexM0Plus => "Cortex-M0+".to_string(),
-            ArmCore::CortexM3 => "Cortex-M3".to_string(),
-            ArmCore::CortexM4 => "Cortex-M4".to_string(),
-            ArmCore::CortexM7 => "Cortex-M7".to_string(),
-            ArmCore::CortexM33 => "Cortex-M33".to_string(),
-            ArmCore::CortexM55 => "Cortex-M55".to_string(),
+            ArmCore::CortexM0 => write!(f, "Cortex-M0"),
+            ArmCore::CortexM0Plus => write!(f, "Cortex-M0+"),
+            ArmCore::CortexM3 => write!(f, "Cortex-M3"),
+            ArmCore::CortexM4 => write!(f, "Cortex-M4"),
+            ArmCore::CortexM7 => write!(f, "Cortex-M7"),
+            ArmCore::CortexM33 => write!(f, "Cortex-M33"),
+            ArmCore::CortexM55 => write!(f, "Cortex-M55"),
         }
     }
 }
 
+/// Cores are written with their name, e.g. `Cortex-M0+`
+impl Serialize for ArmCore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl ArmCore {
+    /// Tag of the core in the memory regions of multicore parts, e.g. `CM4`
+    pub fn tag(&self) -> &'static str {
+        match self {
+            ArmCore::CortexM0 => "CM0",
+            ArmCore::CortexM0Plus => "CM0+",
+            ArmCore::CortexM3 => "CM3",
+            ArmCore::CortexM4 => "CM4",
+            ArmCore::CortexM7 => "CM7",
+            ArmCore::CortexM33 => "CM33",
+            ArmCore::CortexM55 => "CM55",
+        }
+    }
+
+    /// Rust target of the core, `hard_float` is ignored for the cores without FPU
+    pub fn target(&self, hard_float: bool) -> &'static str {
+        match (self, hard_float) {
+            (ArmCore::CortexM0 | ArmCore::CortexM0Plus, _) => "thumbv6m-none-eabi",
+            (ArmCore::CortexM3, _) => "thumbv7m-none-eabi",
+            (ArmCore::CortexM4 | ArmCore::CortexM7, false) => "thumbv7em-none-eabi",
+            (ArmCore::CortexM4 | ArmCore::CortexM7, true) => "thumbv7em-none-eabihf",
+            (ArmCore::CortexM33 | ArmCore::CortexM55, false) => "thumbv8m.main-none-eabi",
+            (ArmCore::CortexM33 | ArmCore::CortexM55, true) => "thumbv8m.main-none-eabihf",
+        }
+    }
+
+    /// The core can be built with an FPU
+    pub fn has_fpu_option(&self) -> bool {
+        !matches!(
+            self,
+            ArmCore::CortexM0 | ArmCore::CortexM0Plus | ArmCore::CortexM3
+        )
+    }
+
     pub fn try_from_short<T: ToString>(data: T) -> Result<ArmCore> {
         match data.to_string().as_str() {
             "0" => Ok(ArmCore::CortexM0),
@@ -117,7 +473,148 @@ impl ArmCore {
     }
 }
 
-pub const HSI_DEFAULT: [(&str, u32); 16] = [
+/// Marketing status of a part
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChipStatus {
+    Active,
+    /// Not recommended for new designs
+    Nrnd,
+    Obsolete,
+    /// Announced but not in production yet
+    Preview,
+    /// The database has no status of the part
+    Unknown,
+}
+
+impl std::fmt::Display for ChipStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChipStatus::Active => write!(f, "Active"),
+            ChipStatus::Nrnd => write!(f, "NRND"),
+            ChipStatus::Obsolete => write!(f, "Obsolete"),
+            ChipStatus::Preview => write!(f, "Preview"),
+            ChipStatus::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Status is written as in the database, e.g. `NRND`
+impl Serialize for ChipStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl ChipStatus {
+    /// Parts which should not be used for a new board without a confirmation
+    pub fn needs_confirmation(&self) -> bool {
+        matches!(
+            self,
+            ChipStatus::Nrnd | ChipStatus::Obsolete | ChipStatus::Preview
+        )
+    }
+
+    /// What the status means for a new board
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            ChipStatus::Active => "in production",
+            ChipStatus::Nrnd => "not recommended for new designs",
+            ChipStatus::Obsolete => "obsolete",
+            ChipStatus::Preview => "in preview and not in production yet",
+            ChipStatus::Unknown => "of unknown status",
+        }
+    }
+
+    /// Read the column `resource.status`, ST also writes `Proposal` and `Coming soon`
+    /// for the parts which are not in production
+    pub fn try_from_str<T: ToString>(data: T) -> Result<ChipStatus> {
+        match data.to_string().trim().to_lowercase().as_str() {
+            "active" => Ok(ChipStatus::Active),
+            "nrnd" => Ok(ChipStatus::Nrnd),
+            "obsolete" => Ok(ChipStatus::Obsolete),
+            "preview" | "proposal" | "coming soon" => Ok(ChipStatus::Preview),
+            "" | "unknown" => Ok(ChipStatus::Unknown),
+            s => bail!("`{}` is unknown part status!", s),
+        }
+    }
+}
+
+/// Floating point unit of a core
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fpu {
+    None,
+    Single,
+    Double,
+}
+
+impl std::fmt::Display for Fpu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fpu::None => write!(f, "none"),
+            Fpu::Single => write!(f, "single precision"),
+            Fpu::Double => write!(f, "double precision"),
+        }
+    }
+}
+
+impl Fpu {
+    /// Read the column `resource.fpu`: `none`, `sp` or `dp`
+    pub fn try_from_str<T: ToString>(data: T) -> Result<Fpu> {
+        match data.to_string().to_lowercase().as_str() {
+            "none" | "" => Ok(Fpu::None),
+            "sp" | "single" => Ok(Fpu::Single),
+            "dp" | "double" => Ok(Fpu::Double),
+            s => bail!("`{}` is unknown FPU type!", s),
+        }
+    }
+
+    /// Value of the column `resource.fpu`
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Fpu::None => "none",
+            Fpu::Single => "sp",
+            Fpu::Double => "dp",
+        }
+    }
+
+    /// FPU of a part from its family, for the databases without the column `resource.fpu`.
+    /// The STM32F76x and STM32F77x have the double precision FPU of the STM32F7.
+    pub fn of_part(family: &str, refname: &str) -> Fpu {
+        match family {
+            "STM32F3" | "STM32F4" | "STM32G4" | "STM32L4" | "STM32L4+" | "STM32WB" | "STM32L5"
+            | "STM32U5" | "STM32U3" | "STM32H5" | "STM32WBA" => Fpu::Single,
+            "STM32F7" if refname.starts_with("STM32F76") || refname.starts_with("STM32F77") => {
+                Fpu::Double
+            }
+            "STM32F7" => Fpu::Single,
+            "STM32H7" | "STM32N6" => Fpu::Double,
+            _ => Fpu::None,
+        }
+    }
+}
+
+/// SYSCLK after reset of the families which do not start from the undivided HSI: the MSI
+/// families, STM32C0 from HSI48 divided by 4, STM32H5 from HSI divided by 2 and the
+/// STM32WB0 and STM32WL3 from the 64 MHz RC divided by 4
+pub const RESET_CLOCK: [(&str, &str, u32); 13] = [
+    ("STM32C0", "HSISYS", 12_000_000),
+    ("STM32H5", "HSI", 32_000_000),
+    ("STM32L0", "MSI", 2_097_000),
+    ("STM32L1", "MSI", 2_097_000),
+    ("STM32L4", "MSI", 4_000_000),
+    ("STM32L4+", "MSI", 4_000_000),
+    ("STM32L5", "MSI", 4_000_000),
+    ("STM32U0", "MSI", 4_000_000),
+    ("STM32U5", "MSI", 4_000_000),
+    ("STM32WB", "MSI", 4_000_000),
+    ("STM32WL", "MSI", 4_000_000),
+    ("STM32WB0", "HSI", 16_000_000),
+    ("STM32WL3", "HSI", 16_000_000),
+];
+
+/// HSI frequency of the families, used with the databases which have no table `oscillator`
+pub const HSI_DEFAULT: [(&str, u32); 25] = [
     ("STM32C0", 48_000_000),
     ("STM32F0", 8_000_000),
     ("STM32F1", 8_000_000),
@@ -126,12 +623,21 @@ pub const HSI_DEFAULT: [(&str, u32); 16] = [
     ("STM32F4", 16_000_000),
     ("STM32F7", 16_000_000),
     ("STM32H7", 64_000_000),
-    ("STM32L0", 8_000_000),
+    ("STM32L0", 16_000_000),
     ("STM32L1", 16_000_000),
     ("STM32L4", 16_000_000),
     ("STM32L5", 16_000_000),
     ("STM32G0", 16_000_000),
     ("STM32G4", 16_000_000),
     ("STM32WB", 16_000_000),
-    ("STM32WL", 48_000_000),
+    ("STM32WL", 16_000_000),
+    ("STM32L4+", 16_000_000),
+    ("STM32U0", 16_000_000),
+    ("STM32U3", 16_000_000),
+    ("STM32U5", 16_000_000),
+    ("STM32H5", 64_000_000),
+    ("STM32WBA", 16_000_000),
+    ("STM32WB0", 64_000_000),
+    ("STM32WL3", 64_000_000),
+    ("STM32N6", 64_000_000),
 ];
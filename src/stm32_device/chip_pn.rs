@@ -1,42 +1,138 @@
-use anyhow::Ok;
+use anyhow::{bail, Ok};
 use console::style;
 use log::warn;
 
+use crate::database::cpn::CpnGroup;
+use crate::interactive::MissingValues;
 use crate::project_variables::{TemplateSlots, VarInfo};
+use crate::stm32_device::part_number::PartNumber;
 use crate::user_parsed_input::UserParsedInput;
 use crate::{database, interactive};
 
-pub fn get_chip_pn(user_parsed_input: &UserParsedInput) -> Result<String, anyhow::Error> {
-    let arg_pn = user_parsed_input.chip_pn();
+/// Get the part number from `--chip`, the provided value `chip_pn` or a prompt.
+/// In silent mode `None` is returned when the part can not be resolved without a prompt.
+/// A part which is NRND, obsolete or in preview has to be confirmed, see `check_status`.
+pub fn get_chip_pn(
+    user_parsed_input: &UserParsedInput,
+    missing: &mut MissingValues,
+) -> Result<Option<String>, anyhow::Error> {
+    match select_chip_pn(user_parsed_input, missing)? {
+        Some(pn) => check_status(pn, user_parsed_input, missing),
+        None => Ok(None),
+    }
+}
+
+/// Warn about a part which is NRND, obsolete or in preview, with the pin-compatible
+/// active parts of the database. The part is used with `--allow-nrnd` or when the prompt
+/// is confirmed, else one of the replacements can be chosen. In silent mode the part is
+/// reported as missing value without `--allow-nrnd`.
+fn check_status(
+    pn: String,
+    user_parsed_input: &UserParsedInput,
+    missing: &mut MissingValues,
+) -> Result<Option<String>, anyhow::Error> {
+    let status = database::cpn::get_status(&pn)?;
+    if !status.needs_confirmation() {
+        return Ok(Some(pn));
+    }
+    let replacements = database::cpn::pin_compatible_parts(&pn)?;
+    warn!(
+        "{} {} {}",
+        style("Attention,").bold().yellow(),
+        style(&pn).bold(),
+        style(format!("is {} ({})", status.explanation(), status))
+            .bold()
+            .yellow()
+    );
+    if !replacements.is_empty() {
+        warn!(
+            "{} {}",
+            style("Pin-compatible active parts:").bold(),
+            replacements.join(", ")
+        );
+    }
+    if user_parsed_input.allow_nrnd() {
+        return Ok(Some(pn));
+    }
+    if user_parsed_input.is_silent() {
+        let hint = if replacements.is_empty() {
+            String::new()
+        } else {
+            format!(", or use {}", replacements.join(", "))
+        };
+        missing.add(
+            "chip_pn",
+            format!(
+                "`{pn}` is {}, use --allow-nrnd to use it anyway{hint}",
+                status.explanation()
+            ),
+        );
+        return Ok(None);
+    }
+
+    let confirm = TemplateSlots {
+        prompt: format!("Use {pn} anyway?"),
+        var_name: "allow_nrnd".into(),
+        var_info: VarInfo::Bool {
+            default: Some(false),
+        },
+        peripheral: None,
+    };
+    if interactive::prompt_and_check_variable(&confirm)?.parse::<bool>()? {
+        return Ok(Some(pn));
+    }
+    if replacements.is_empty() {
+        bail!(
+            "{} is {}, pls choose another part!",
+            pn,
+            status.explanation()
+        );
+    }
+    let prompt_args = TemplateSlots {
+        prompt: "Which part should be used instead?".into(),
+        var_name: "pn".into(),
+        var_info: VarInfo::Select {
+            choices: replacements,
+            default: None,
+        },
+        peripheral: None,
+    };
+    interactive::prompt_and_check_variable(&prompt_args).map(Some)
+}
+
+/// Resolve the part number from the arguments or prompts, whatever its status
+fn select_chip_pn(
+    user_parsed_input: &UserParsedInput,
+    missing: &mut MissingValues,
+) -> Result<Option<String>, anyhow::Error> {
+    let arg_pn = user_parsed_input
+        .chip_pn()
+        .or_else(|| user_parsed_input.template_value_str("chip_pn"));
+    if user_parsed_input.is_silent() {
+        return get_chip_pn_silent(arg_pn, missing);
+    }
     let pn = match arg_pn {
-        Some(name) => name.to_string(),
-        None => interactive::chip_pn().unwrap(),
+        Some(name) => {
+            if let Err(err) = check_pn(name) {
+                bail!("`{}` is not a valid part number: {}", name, err);
+            }
+            name.to_string()
+        }
+        None => prompt_chip_pn()?,
     };
     let mut pn = pn.to_uppercase();
     loop {
-        let list = database::cpn::cpn_query(&pn)?;
-        match list.len() {
-            1 => {
-                return Ok(list.get(0).unwrap().to_string());
-            }
-            2..30 => {
-                let prompt_args = TemplateSlots {
-                    prompt: "Which part should be used?".into(),
-                    var_name: "pn".into(),
-                    var_info: VarInfo::Select {
-                        choices: list.into_iter().map(|p| p.to_string()).collect(),
-                        default: None,
-                    },
-                };
-                return interactive::prompt_and_check_variable(&prompt_args);
-            }
-            30.. => {
-                warn!(
-                    "Include \"{}\"'s P/N over 30, pls input more information",
-                    style(&pn).bold().yellow(),
-                );
-            }
-            _ => {
+        let groups = database::cpn::cpn_search(&pn)?;
+        let search = normalize_pn(&pn);
+        if let Some(exact) = groups
+            .iter()
+            .flat_map(|g| g.cpns.iter())
+            .find(|cpn| **cpn == search)
+        {
+            return Ok(Some(exact.clone()));
+        }
+        match groups.as_slice() {
+            [] => {
                 warn!(
                     "{} \"{}\" {}",
                     style("Sorry,").bold().red(),
@@ -44,34 +140,333 @@ pub fn get_chip_pn(user_parsed_input: &UserParsedInput) -> Result<String, anyhow
                     style("is not a valid value for chip_pn").bold().red()
                 );
             }
+            [group] if group.cpns.len() == 1 => {
+                return Ok(Some(group.cpns[0].clone()));
+            }
+            _ => {
+                if let Some(cpn) = pick_chip_pn(&groups)? {
+                    return Ok(Some(cpn));
+                }
+            }
+        }
+        pn = prompt_chip_pn()?.to_uppercase();
+    }
+}
+
+/// Prompt a part number until its codes are valid
+fn prompt_chip_pn() -> Result<String, anyhow::Error> {
+    loop {
+        let pn = interactive::chip_pn()?;
+        let Err(err) = check_pn(&pn) else {
+            return Ok(pn);
+        };
+        warn!(
+            "{} \"{}\" {}",
+            style("Sorry,").bold().red(),
+            style(&pn).bold().yellow(),
+            style(format!("is not a valid part number: {err}"))
+                .bold()
+                .red()
+        );
+    }
+}
+
+/// Check the codes of a part number before it is searched. A search which starts with a
+/// digit, e.g. `071CB`, is the middle of a part number and is not checked. A code unknown
+/// to the decoder only gives a warning when the database has the part.
+fn check_pn(pn: &str) -> Result<(), anyhow::Error> {
+    if pn.trim().starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(());
+    }
+    let Err(err) = PartNumber::parse(pn) else {
+        return Ok(());
+    };
+    if database::cpn::cpn_query(pn.trim().to_uppercase())?.is_empty() {
+        return Err(err);
+    }
+    warn!(
+        "{}",
+        style(format!(
+            "{} is in the database, but it can not be decoded: {}",
+            pn.trim(),
+            err
+        ))
+        .yellow()
+    );
+    Ok(())
+}
+
+/// Page through the matching parts, one line per reference name, the list can be filtered.
+/// `None` is returned when another part number should be searched.
+fn pick_chip_pn(groups: &[CpnGroup]) -> Result<Option<String>, anyhow::Error> {
+    let mut filter = String::new();
+    loop {
+        let shown: Vec<&CpnGroup> = groups
+            .iter()
+            .filter(|g| g.refname.contains(&filter) || g.cpns.iter().any(|c| c.contains(&filter)))
+            .collect();
+        let mut items: Vec<String> = shown
+            .iter()
+            .map(|g| format!("{} ({})", g.refname, g.cpns.join(", ")))
+            .collect();
+        items.push("🔍 Filter the list".into());
+        items.push("✏️  Search another part number".into());
+        let prompt = if filter.is_empty() {
+            format!("{} parts match, which one should be used?", shown.len())
+        } else {
+            format!(
+                "{} parts match and contain {}, which one should be used?",
+                shown.len(),
+                filter
+            )
+        };
+        let chosen = interactive::select_page(&items, &prompt)?;
+        if chosen == shown.len() {
+            filter = interactive::filter()?.trim().to_uppercase();
+            continue;
+        }
+        if chosen > shown.len() {
+            return Ok(None);
+        }
+        let group = shown[chosen];
+        if let [cpn] = group.cpns.as_slice() {
+            return Ok(Some(cpn.clone()));
+        }
+        let prompt_args = TemplateSlots {
+            prompt: "Which part should be used?".into(),
+            var_name: "pn".into(),
+            var_info: VarInfo::Select {
+                choices: group.cpns.clone(),
+                default: None,
+            },
+            peripheral: None,
+        };
+        return interactive::prompt_and_check_variable(&prompt_args).map(Some);
+    }
+}
+
+/// Resolve the part number without any prompt, it must match exactly one part
+fn get_chip_pn_silent(
+    pn: Option<&str>,
+    missing: &mut MissingValues,
+) -> Result<Option<String>, anyhow::Error> {
+    let Some(pn) = pn else {
+        missing.add("chip_pn", "no part number is given, use --chip");
+        return Ok(None);
+    };
+    if let Err(err) = check_pn(pn) {
+        missing.add(
+            "chip_pn",
+            format!("`{pn}` is not a valid part number: {err}"),
+        );
+        return Ok(None);
+    }
+    let pn = pn.to_uppercase();
+    let list = database::cpn::cpn_query(&pn)?;
+    if list.len() == 1 {
+        return Ok(list.into_iter().next());
+    }
+    if list.contains(&pn) {
+        return Ok(Some(pn));
+    }
+    if list.is_empty() {
+        missing.add("chip_pn", format!("`{pn}` is not a valid part number"));
+    } else {
+        missing.add(
+            "chip_pn",
+            format!(
+                "{} parts match `{pn}`, pls give the full part number",
+                list.len()
+            ),
+        );
+    }
+    Ok(None)
+}
+
+/// Part number as typed, e.g. ` g071-cb`, in the form of the database: `STM32G071CB`
+pub fn normalize_pn(input: &str) -> String {
+    let pn = input
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+    if pn.starts_with("STM") {
+        pn
+    } else {
+        format!("STM32{pn}")
+    }
+}
+
+/// Rank of a part number for a search normalized by `normalize_pn`, lower is better and
+/// `None` if it does not match.
+///
+/// - below 100 the part number starts with the search, an `X` of the search matches any
+///   character as in the reference names. The shorter the rest, the better, so a packing
+///   suffix such as `TR` comes after the plain part number.
+/// - below 200 the part number contains the search without `STM32`, e.g. `071CB`.
+/// - from 200 the start of the part number is the search with a typo, one error is
+///   allowed, two for the searches longer than 12 characters.
+pub fn match_score(search: &str, cpn: &str) -> Option<u32> {
+    let cpn = cpn.to_uppercase();
+    let rest = cpn.len().saturating_sub(search.len()) as u32;
+    if cpn.len() >= search.len()
+        && search
+            .chars()
+            .zip(cpn.chars())
+            .all(|(s, c)| s == c || s == 'X')
+    {
+        return Some(rest);
+    }
+    let line = search.strip_prefix("STM32").unwrap_or(search);
+    if line.len() < 3 {
+        return None;
+    }
+    if cpn.contains(line) {
+        return Some(100 + rest);
+    }
+    let allowed = if search.len() > 12 { 2 } else { 1 };
+    let errors = (search.len().saturating_sub(1)..=search.len() + 1)
+        .filter_map(|len| cpn.get(..len))
+        .map(|start| strsim::osa_distance(search, start))
+        .min()?;
+    (errors <= allowed).then(|| 200 + errors as u32 * 20 + rest)
+}
+
+/// Pin count encoded by the letter after the line of the part number, e.g. `C` of `STM32G071CBT6`
+pub(crate) const PIN_COUNT_CODES: [(char, u32); 23] = [
+    ('A', 169),
+    ('B', 208),
+    ('C', 48),
+    ('D', 14),
+    ('E', 25),
+    ('F', 20),
+    ('G', 28),
+    ('H', 40),
+    ('I', 176),
+    ('J', 8),
+    ('K', 32),
+    ('L', 225),
+    ('M', 80),
+    ('N', 216),
+    ('O', 90),
+    ('Q', 132),
+    ('R', 64),
+    ('S', 44),
+    ('T', 36),
+    ('U', 63),
+    ('V', 100),
+    ('X', 256),
+    ('Z', 144),
+];
+
+/// Package type encoded by the letter after the flash size of the part number, e.g. `T` of `STM32G071CBT6`
+pub(crate) const PACKAGE_CODES: [(char, &str); 11] = [
+    ('H', "TFBGA"),
+    ('I', "UFBGA"),
+    ('J', "UFBGA"),
+    ('K', "UFBGA"),
+    ('M', "SO"),
+    ('P', "TSSOP"),
+    ('Q', "UFBGA"),
+    ('T', "LQFP"),
+    ('U', "UFQFPN"),
+    ('V', "VFQFPN"),
+    ('Y', "WLCSP"),
+];
+
+/// Package of a part, e.g. `LQFP48`, from its reference name such as `STM32G071CBTx`.
+/// The pin count, flash and package codes are the three letters before the `x`.
+pub fn package_from_refname(refname: &str) -> Option<String> {
+    let refname = refname.to_uppercase();
+    let codes: Vec<char> = refname.get(..refname.rfind('X')?)?.chars().collect();
+    let [pin_code, _flash_code, package_code] = codes.get(codes.len().checked_sub(3)?..)? else {
+        return None;
+    };
+    let package = PACKAGE_CODES
+        .iter()
+        .find(|(code, _)| code == package_code)?
+        .1;
+    match PIN_COUNT_CODES.iter().find(|(code, _)| code == pin_code) {
+        Some((_, pins)) => Some(format!("{package}{pins}")),
+        None => Some(package.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db_init;
+    use crate::database::tests::{test_db, DB_TEST_LOCK};
+
+    #[test]
+    fn it_accepts_the_parts_of_the_database() {
+        let _lock = DB_TEST_LOCK.lock().unwrap();
+        let db = test_db("test_check_pn.db");
+        db.execute(
+            "
+            CREATE TABLE IF NOT EXISTS cpn (cpn TEXT, refname TEXT);
+            INSERT INTO cpn VALUES ('STM32G071CBW6', 'STM32G071CBWx');
+            ",
+        )
+        .unwrap();
+        db_init("test_check_pn.db").unwrap();
+
+        for pn in ["STM32WB55RGV6", "STM32WB07CCV6", "STM32H7B3LIH6Q", "071CB"] {
+            assert!(check_pn(pn).is_ok(), "{pn}");
         }
-        // if list.len() == 1 {
-        //     return Ok(list.get(0).unwrap().to_string());
-        // } else if list.len() > 1 && list.len() < 30 {
-        //     let prompt_args = TemplateSlots {
-        //         prompt: "Which part should be used?".into(),
-        //         var_name: "pn".into(),
-        //         var_info: VarInfo::Select {
-        //             choices: list.into_iter().map(|p| p.to_string()).collect(),
-        //             default: None,
-        //         },
-        //     };
-        //     return interactive::prompt_and_check_variable(&prompt_args);
-        // } else if list.len() >= 30 {
-        //     warn!(
-        //         "Include \"{}\"'s P/N over 30, pls input more information",
-        //         style(&pn).bold().yellow(),
-        //     );
-        // } else {
-        //     warn!(
-        //         "{} \"{}\" {}",
-        //         style("Sorry,").bold().red(),
-        //         style(&pn).bold().yellow(),
-        //         style("is not a valid value for chip_pn").bold().red()
-        //     );
-        // }
-
-        pn = interactive::chip_pn().unwrap().to_uppercase();
-        continue;
+        // `W` is no package code, the part is accepted because the database has it
+        assert!(check_pn("STM32G071CBW6").is_ok());
+        assert_eq!(
+            check_pn("STM32G071CBW7").unwrap_err().to_string(),
+            "package code 'W' does not exist"
+        );
+        std::fs::remove_file("test_check_pn.db").unwrap();
+    }
+
+    #[test]
+    fn it_decodes_the_package_of_a_refname() {
+        assert_eq!(
+            package_from_refname("STM32G071CBTx").as_deref(),
+            Some("LQFP48")
+        );
+        assert_eq!(
+            package_from_refname("STM32U575ZITxQ").as_deref(),
+            Some("LQFP144")
+        );
+        assert_eq!(
+            package_from_refname("STM32G030F6Px").as_deref(),
+            Some("TSSOP20")
+        );
+        assert_eq!(package_from_refname("STM32"), None);
+        assert_eq!(package_from_refname("x"), None);
+    }
+
+    #[test]
+    fn it_ranks_the_part_numbers_of_a_search() {
+        assert_eq!(normalize_pn(" g071-cb"), "STM32G071CB");
+        assert_eq!(normalize_pn("stm32g071cbt6"), "STM32G071CBT6");
+
+        let search = normalize_pn("g071cb");
+        assert_eq!(match_score(&search, "STM32G071CBT6"), Some(2));
+        assert_eq!(match_score(&search, "STM32G071CBT6TR"), Some(4));
+        assert_eq!(match_score(&search, "STM32G071RBT6"), Some(222));
+        assert_eq!(match_score(&search, "STM32F407VGT6"), None);
+        // refnames have an `x` for the temperature range
+        assert_eq!(
+            match_score(&normalize_pn("STM32G071CBTx"), "STM32G071CBT7"),
+            Some(0)
+        );
+        assert_eq!(
+            match_score(&normalize_pn("071cb"), "STM32G071CBT6"),
+            Some(103)
+        );
+        // typos: a wrong, a missing and a swapped character
+        assert_eq!(
+            match_score(&normalize_pn("g017cb"), "STM32G071CBT6"),
+            Some(222)
+        );
+        assert!(match_score(&normalize_pn("g71cb"), "STM32G071CBT6").is_some());
+        assert!(match_score(&normalize_pn("g0771cb"), "STM32G071CBT6").is_some());
+        assert_eq!(match_score(&normalize_pn("g0"), "STM32F407VGT6"), None);
     }
 }
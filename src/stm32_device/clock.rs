@@ -0,0 +1,722 @@
+use anyhow::{bail, Result};
+use console::style;
+use core::fmt;
+use log::warn;
+use serde::Serialize;
+use std::ops::RangeInclusive;
+
+use crate::args::ClockArgs;
+use crate::stm32_device::chip_info::ChipInfo;
+
+const MHZ: u32 = 1_000_000;
+
+/// What the application needs from the clock tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClockRequest {
+    /// Frequency of the HSE crystal or oscillator in Hz, HSI is used without
+    pub hse: Option<u32>,
+    /// Wanted SYSCLK in Hz, the maximum frequency of the part by default
+    pub sysclk: Option<u32>,
+    /// Wanted frequency of the PLL Q output in Hz, e.g. 48 MHz for USB, SDIO and RNG
+    pub pll_q: Option<u32>,
+}
+
+impl ClockRequest {
+    pub fn is_empty(&self) -> bool {
+        *self == ClockRequest::default()
+    }
+}
+
+impl From<&ClockArgs> for ClockRequest {
+    fn from(args: &ClockArgs) -> Self {
+        Self {
+            hse: args.hse,
+            sysclk: args.sysclk,
+            pll_q: args.pll_q,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ClockSource {
+    Hsi,
+    Hse,
+    Pll,
+}
+
+impl fmt::Display for ClockSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClockSource::Hsi => write!(f, "HSI"),
+            ClockSource::Hse => write!(f, "HSE"),
+            ClockSource::Pll => write!(f, "PLL"),
+        }
+    }
+}
+
+/// Dividers of the main PLL, the outputs which are not used are `None`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PllConfig {
+    pub m: u32,
+    pub n: u32,
+    pub p: Option<u32>,
+    pub q: Option<u32>,
+    pub r: Option<u32>,
+    pub vco_in_hz: u32,
+    pub vco_out_hz: u32,
+    pub q_hz: Option<u32>,
+}
+
+/// Settings of the RCC, the flash and the power controller for a clock request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClockConfig {
+    /// Oscillator which drives the PLL, or SYSCLK without the PLL
+    pub source: ClockSource,
+    pub source_hz: u32,
+    pub sysclk_source: ClockSource,
+    pub sysclk_hz: u32,
+    /// HPRE, HCLK is SYSCLK divided by it
+    pub ahb_prescaler: u32,
+    pub hclk_hz: u32,
+    pub apb1_prescaler: u32,
+    pub pclk1_hz: u32,
+    /// `None` on the families with a single APB, such as STM32G0
+    pub apb2_prescaler: Option<u32>,
+    pub pclk2_hz: Option<u32>,
+    pub pll: Option<PllConfig>,
+    pub flash_latency: u32,
+    pub voltage_scale: u32,
+    /// Boost mode of STM32G4 and STM32L4+, over-drive of STM32F4 and STM32F7
+    pub voltage_boost: bool,
+}
+
+impl fmt::Display for ClockConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mhz = |hz: u32| format!("{} MHz", hz as f64 / MHZ as f64);
+        writeln!(f, "Source:        {} {}", self.source, mhz(self.source_hz))?;
+        writeln!(
+            f,
+            "SYSCLK:        {} from {}",
+            mhz(self.sysclk_hz),
+            self.sysclk_source
+        )?;
+        if let Some(pll) = &self.pll {
+            let divider = |name: &str, value: Option<u32>| {
+                value.map_or(String::new(), |v| format!(" {name}={v}"))
+            };
+            writeln!(
+                f,
+                "PLL:           M={} N={}{}{}{} (VCO {} -> {})",
+                pll.m,
+                pll.n,
+                divider("P", pll.p),
+                divider("Q", pll.q),
+                divider("R", pll.r),
+                mhz(pll.vco_in_hz),
+                mhz(pll.vco_out_hz)
+            )?;
+            if let Some(q_hz) = pll.q_hz {
+                writeln!(f, "PLL Q:         {}", mhz(q_hz))?;
+            }
+        }
+        writeln!(
+            f,
+            "HCLK:          {} (AHB /{})",
+            mhz(self.hclk_hz),
+            self.ahb_prescaler
+        )?;
+        writeln!(
+            f,
+            "PCLK1:         {} (APB1 /{})",
+            mhz(self.pclk1_hz),
+            self.apb1_prescaler
+        )?;
+        if let (Some(pclk2), Some(prescaler)) = (self.pclk2_hz, self.apb2_prescaler) {
+            writeln!(f, "PCLK2:         {} (APB2 /{})", mhz(pclk2), prescaler)?;
+        }
+        writeln!(f, "Flash latency: {} wait states", self.flash_latency)?;
+        write!(
+            f,
+            "Voltage scale: {}{}",
+            self.voltage_scale,
+            if self.voltage_boost { " boost" } else { "" }
+        )
+    }
+}
+
+/// Output of the PLL which drives SYSCLK
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SysclkOutput {
+    P,
+    R,
+}
+
+/// Voltage scale with the maximum HCLK of each number of flash wait states
+struct VoltageScale {
+    scale: u32,
+    boost: bool,
+    wait_states: &'static [u32],
+}
+
+impl VoltageScale {
+    fn max_hclk(&self) -> u32 {
+        *self.wait_states.last().unwrap() * MHZ
+    }
+}
+
+/// Clock tree limits of a family, from the reference manuals.
+/// Frequencies are in MHz, wait states are given for the 2.7 V to 3.6 V range.
+struct FamilyClock {
+    families: &'static [&'static str],
+    hse: RangeInclusive<u32>,
+    m: RangeInclusive<u32>,
+    n: RangeInclusive<u32>,
+    vco_in: RangeInclusive<f64>,
+    vco_out: RangeInclusive<u32>,
+    p: &'static [u32],
+    q: &'static [u32],
+    r: &'static [u32],
+    sysclk_output: SysclkOutput,
+    /// SYSCLK runs at most at the maximum HCLK multiplied by this
+    ahb_ratio: u32,
+    /// APB1 and APB2 run at most at the maximum HCLK divided by these
+    apb1_ratio: u32,
+    apb2_ratio: Option<u32>,
+    /// from the lowest to the highest power consumption
+    scales: &'static [VoltageScale],
+}
+
+const DIV_2_TO_8: &[u32] = &[2, 3, 4, 5, 6, 7, 8];
+const DIV_2_TO_15: &[u32] = &[2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+const DIV_EVEN_2_TO_8: &[u32] = &[2, 4, 6, 8];
+const AHB_PRESCALERS: &[u32] = &[1, 2, 4, 8, 16, 64, 128, 256, 512];
+
+const FAMILY_CLOCKS: &[FamilyClock] = &[
+    FamilyClock {
+        families: &["STM32F4"],
+        hse: 4..=26,
+        m: 2..=63,
+        n: 50..=432,
+        vco_in: 1.0..=2.0,
+        vco_out: 100..=432,
+        p: DIV_EVEN_2_TO_8,
+        q: DIV_2_TO_15,
+        r: &[],
+        sysclk_output: SysclkOutput::P,
+        ahb_ratio: 1,
+        apb1_ratio: 4,
+        apb2_ratio: Some(2),
+        scales: &[
+            VoltageScale {
+                scale: 2,
+                boost: false,
+                wait_states: &[30, 60, 90, 120, 144],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: false,
+                wait_states: &[30, 60, 90, 120, 150, 168],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: true,
+                wait_states: &[30, 60, 90, 120, 150, 180],
+            },
+        ],
+    },
+    FamilyClock {
+        families: &["STM32F7"],
+        hse: 4..=26,
+        m: 2..=63,
+        n: 50..=432,
+        vco_in: 1.0..=2.0,
+        vco_out: 100..=432,
+        p: DIV_EVEN_2_TO_8,
+        q: DIV_2_TO_15,
+        r: &[],
+        sysclk_output: SysclkOutput::P,
+        ahb_ratio: 1,
+        apb1_ratio: 4,
+        apb2_ratio: Some(2),
+        scales: &[
+            VoltageScale {
+                scale: 3,
+                boost: false,
+                wait_states: &[30, 60, 90, 120, 144],
+            },
+            VoltageScale {
+                scale: 2,
+                boost: false,
+                wait_states: &[30, 60, 90, 120, 150, 168],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: false,
+                wait_states: &[30, 60, 90, 120, 150, 180],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: true,
+                wait_states: &[30, 60, 90, 120, 150, 180, 210, 216],
+            },
+        ],
+    },
+    FamilyClock {
+        families: &["STM32G0"],
+        hse: 4..=48,
+        m: 1..=8,
+        n: 8..=86,
+        vco_in: 2.66..=16.0,
+        vco_out: 64..=344,
+        p: &[],
+        q: DIV_2_TO_8,
+        r: DIV_2_TO_8,
+        sysclk_output: SysclkOutput::R,
+        ahb_ratio: 1,
+        apb1_ratio: 1,
+        apb2_ratio: None,
+        scales: &[
+            VoltageScale {
+                scale: 2,
+                boost: false,
+                wait_states: &[8, 16],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: false,
+                wait_states: &[24, 48, 64],
+            },
+        ],
+    },
+    FamilyClock {
+        families: &["STM32G4"],
+        hse: 4..=48,
+        m: 1..=16,
+        n: 8..=127,
+        vco_in: 2.66..=16.0,
+        vco_out: 96..=344,
+        p: &[],
+        q: DIV_EVEN_2_TO_8,
+        r: DIV_EVEN_2_TO_8,
+        sysclk_output: SysclkOutput::R,
+        ahb_ratio: 1,
+        apb1_ratio: 1,
+        apb2_ratio: Some(1),
+        scales: &[
+            VoltageScale {
+                scale: 2,
+                boost: false,
+                wait_states: &[12, 24, 26],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: false,
+                wait_states: &[30, 60, 90, 120, 150],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: true,
+                wait_states: &[34, 68, 102, 136, 170],
+            },
+        ],
+    },
+    FamilyClock {
+        families: &["STM32L4"],
+        hse: 4..=48,
+        m: 1..=8,
+        n: 8..=86,
+        vco_in: 4.0..=16.0,
+        vco_out: 64..=344,
+        p: &[],
+        q: DIV_EVEN_2_TO_8,
+        r: DIV_EVEN_2_TO_8,
+        sysclk_output: SysclkOutput::R,
+        ahb_ratio: 1,
+        apb1_ratio: 1,
+        apb2_ratio: Some(1),
+        scales: &[
+            VoltageScale {
+                scale: 2,
+                boost: false,
+                wait_states: &[6, 12, 18, 26],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: false,
+                wait_states: &[16, 32, 48, 64, 80],
+            },
+        ],
+    },
+    FamilyClock {
+        families: &["STM32L4+"],
+        hse: 4..=48,
+        m: 1..=16,
+        n: 8..=127,
+        vco_in: 2.66..=16.0,
+        vco_out: 64..=344,
+        p: &[],
+        q: DIV_EVEN_2_TO_8,
+        r: DIV_EVEN_2_TO_8,
+        sysclk_output: SysclkOutput::R,
+        ahb_ratio: 1,
+        apb1_ratio: 1,
+        apb2_ratio: Some(1),
+        scales: &[
+            VoltageScale {
+                scale: 2,
+                boost: false,
+                wait_states: &[8, 16, 26],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: false,
+                wait_states: &[20, 40, 60, 80],
+            },
+            VoltageScale {
+                scale: 1,
+                boost: true,
+                wait_states: &[20, 40, 60, 80, 100, 120],
+            },
+        ],
+    },
+];
+
+fn family_clock(family: &str) -> Option<&'static FamilyClock> {
+    FAMILY_CLOCKS.iter().find(|c| c.families.contains(&family))
+}
+
+/// Solve the clock tree of a project. Without a request the part runs at its maximum
+/// frequency from HSI, families unknown to the solver give `None` in that case only.
+pub fn solve_for_project(
+    chip_info: &ChipInfo,
+    request: &ClockRequest,
+) -> Result<Option<ClockConfig>> {
+    if request.is_empty() && family_clock(&chip_info.family).is_none() {
+        warn!(
+            "{}",
+            style(format!(
+                "The clock solver does not support family {} yet, the clock tree is not set",
+                chip_info.family
+            ))
+            .yellow()
+        );
+        return Ok(None);
+    }
+    solve(chip_info, request).map(Some)
+}
+
+/// Compute the PLL dividers, the APB prescalers, the flash wait states and the voltage
+/// scale which give the requested SYSCLK, or the closest one below the maximum of the part
+/// with a warning. A requested PLL Q frequency must be met exactly.
+pub fn solve(chip_info: &ChipInfo, request: &ClockRequest) -> Result<ClockConfig> {
+    let Some(tree) = family_clock(&chip_info.family) else {
+        bail!(
+            "The clock solver does not support family {} yet!",
+            chip_info.family
+        );
+    };
+    let (source, source_hz) = match request.hse {
+        Some(hse) => {
+            if hse < tree.hse.start() * MHZ || hse > tree.hse.end() * MHZ {
+                bail!(
+                    "HSE of {} Hz is out of the range of {}, {} to {} MHz",
+                    hse,
+                    chip_info.family,
+                    tree.hse.start(),
+                    tree.hse.end()
+                );
+            }
+            (ClockSource::Hse, hse)
+        }
+        None => (ClockSource::Hsi, chip_info.oscillator("HSI")?),
+    };
+    let max_hclk = tree.scales.last().unwrap().max_hclk();
+    let max_sysclk = (chip_info.freq * MHZ).min(max_hclk * tree.ahb_ratio);
+    let target = request.sysclk.unwrap_or(max_sysclk);
+    if target > max_sysclk {
+        bail!(
+            "SYSCLK of {} Hz is above the maximum of {}, {} Hz",
+            target,
+            chip_info.cpn,
+            max_sysclk
+        );
+    }
+
+    let pll = if target == source_hz && request.pll_q.is_none() {
+        None
+    } else {
+        Some(solve_pll(
+            tree,
+            source_hz,
+            target,
+            max_sysclk,
+            request.pll_q,
+        )?)
+    };
+    let sysclk_hz = match &pll {
+        Some(pll) => {
+            let divider = match tree.sysclk_output {
+                SysclkOutput::P => pll.p,
+                SysclkOutput::R => pll.r,
+            };
+            pll.vco_out_hz / divider.unwrap()
+        }
+        None => source_hz,
+    };
+    if request.sysclk.is_some() && sysclk_hz != target {
+        warn!(
+            "{}",
+            style(format!(
+                "SYSCLK of {} Hz can not be reached, the closest is {} Hz",
+                target, sysclk_hz
+            ))
+            .yellow()
+        );
+    }
+
+    let ahb_prescaler = ahb_prescaler(sysclk_hz, max_hclk.min(chip_info.freq * MHZ));
+    let hclk_hz = sysclk_hz / ahb_prescaler;
+    let prescaler = |ratio: u32| -> u32 {
+        [1, 2, 4, 8, 16]
+            .into_iter()
+            .find(|p| hclk_hz / p <= max_hclk / ratio)
+            .unwrap_or(16)
+    };
+    let apb1_prescaler = prescaler(tree.apb1_ratio);
+    let apb2_prescaler = tree.apb2_ratio.map(prescaler);
+    let Some(scale) = tree.scales.iter().find(|s| s.max_hclk() >= hclk_hz) else {
+        bail!(
+            "No voltage scale of {} allows {} Hz",
+            chip_info.family,
+            hclk_hz
+        );
+    };
+    let flash_latency = scale
+        .wait_states
+        .iter()
+        .position(|max| max * MHZ >= hclk_hz)
+        .unwrap() as u32;
+
+    Ok(ClockConfig {
+        source,
+        source_hz,
+        sysclk_source: if pll.is_some() {
+            ClockSource::Pll
+        } else {
+            source
+        },
+        sysclk_hz,
+        ahb_prescaler,
+        hclk_hz,
+        apb1_prescaler,
+        pclk1_hz: hclk_hz / apb1_prescaler,
+        apb2_prescaler,
+        pclk2_hz: apb2_prescaler.map(|p| hclk_hz / p),
+        pll,
+        flash_latency,
+        voltage_scale: scale.scale,
+        voltage_boost: scale.boost,
+    })
+}
+
+/// The lowest HPRE which keeps HCLK at most at `max_hclk`
+fn ahb_prescaler(sysclk_hz: u32, max_hclk: u32) -> u32 {
+    AHB_PRESCALERS
+        .iter()
+        .copied()
+        .find(|p| sysclk_hz / p <= max_hclk)
+        .unwrap_or(512)
+}
+
+/// Search every divider combination of the family. The best one meets the Q frequency,
+/// then is the closest to the SYSCLK target, then has the highest VCO input, which
+/// lowers the jitter, and the lowest VCO output, which lowers the consumption.
+fn solve_pll(
+    tree: &FamilyClock,
+    source_hz: u32,
+    target: u32,
+    max_sysclk: u32,
+    pll_q: Option<u32>,
+) -> Result<PllConfig> {
+    let sysclk_dividers = match tree.sysclk_output {
+        SysclkOutput::P => tree.p,
+        SysclkOutput::R => tree.r,
+    };
+    let mut best: Option<((u64, u64, u32, u64), PllConfig)> = None;
+    for m in tree.m.clone() {
+        let vco_in = source_hz as f64 / m as f64 / MHZ as f64;
+        if !tree.vco_in.contains(&vco_in) {
+            continue;
+        }
+        for n in tree.n.clone() {
+            let vco_out = source_hz as u64 * n as u64 / m as u64;
+            if vco_out < *tree.vco_out.start() as u64 * MHZ as u64
+                || vco_out > *tree.vco_out.end() as u64 * MHZ as u64
+            {
+                continue;
+            }
+            let q = pll_q.and_then(|wanted| {
+                tree.q
+                    .iter()
+                    .min_by_key(|q| (vco_out / **q as u64).abs_diff(wanted as u64))
+                    .copied()
+            });
+            let q_error = match (q, pll_q) {
+                (Some(q), Some(wanted)) => (vco_out / q as u64).abs_diff(wanted as u64),
+                _ => 0,
+            };
+            for divider in sysclk_dividers.iter() {
+                let sysclk = vco_out / *divider as u64;
+                if sysclk > max_sysclk as u64 {
+                    continue;
+                }
+                let score = (q_error, sysclk.abs_diff(target as u64), m, vco_out);
+                if best.as_ref().is_some_and(|(best, _)| *best <= score) {
+                    continue;
+                }
+                let (p, r) = match tree.sysclk_output {
+                    SysclkOutput::P => (Some(*divider), None),
+                    SysclkOutput::R => (None, Some(*divider)),
+                };
+                best = Some((
+                    score,
+                    PllConfig {
+                        m,
+                        n,
+                        p,
+                        q,
+                        r,
+                        vco_in_hz: source_hz / m,
+                        vco_out_hz: vco_out as u32,
+                        q_hz: q.map(|q| (vco_out / q as u64) as u32),
+                    },
+                ));
+            }
+        }
+    }
+    match best {
+        Some(((0, _, _, _), pll)) => Ok(pll),
+        Some((_, pll)) => bail!(
+            "No PLL setting gives {} Hz on PLL Q, the closest is {} Hz",
+            pll_q.unwrap_or_default(),
+            pll.q_hz.unwrap_or_default()
+        ),
+        None => bail!(
+            "No PLL setting is possible with a source of {} Hz",
+            source_hz
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stm32_device::chip_info::ArmCore;
+
+    fn chip_info(cpn: &str, core: ArmCore, freq: u32) -> ChipInfo {
+        ChipInfo {
+            freq,
+            ..ChipInfo::test_part(cpn, core)
+        }
+    }
+
+    #[test]
+    fn it_solves_the_f4_clock_tree() {
+        let request = ClockRequest {
+            hse: Some(8 * MHZ),
+            sysclk: Some(168 * MHZ),
+            pll_q: Some(48 * MHZ),
+        };
+        let config = solve(
+            &chip_info("STM32F407VGT6", ArmCore::CortexM4, 168),
+            &request,
+        )
+        .unwrap();
+        assert_eq!(config.sysclk_source, ClockSource::Pll);
+        assert_eq!(config.sysclk_hz, 168 * MHZ);
+        let pll = config.pll.unwrap();
+        assert_eq!((pll.m, pll.n, pll.p, pll.q), (4, 168, Some(2), Some(7)));
+        assert_eq!(pll.q_hz, Some(48 * MHZ));
+        assert_eq!(config.apb1_prescaler, 4);
+        assert_eq!(config.apb2_prescaler, Some(2));
+        assert_eq!(config.flash_latency, 5);
+        assert_eq!((config.voltage_scale, config.voltage_boost), (1, false));
+    }
+
+    #[test]
+    fn it_solves_the_g0_clock_tree() {
+        let config = solve(
+            &chip_info("STM32G071CBT6", ArmCore::CortexM0Plus, 64),
+            &ClockRequest::default(),
+        )
+        .unwrap();
+        assert_eq!(config.source, ClockSource::Hsi);
+        assert_eq!(config.sysclk_hz, 64 * MHZ);
+        let pll = config.pll.unwrap();
+        assert_eq!(pll.vco_out_hz / pll.r.unwrap(), 64 * MHZ);
+        assert_eq!(pll.p, None);
+        assert_eq!(config.apb2_prescaler, None);
+        assert_eq!(config.flash_latency, 2);
+        assert_eq!(config.voltage_scale, 1);
+
+        // SYSCLK from HSI without the PLL, in the low power range
+        let request = ClockRequest {
+            sysclk: Some(16 * MHZ),
+            ..Default::default()
+        };
+        let config = solve(
+            &chip_info("STM32G071CBT6", ArmCore::CortexM0Plus, 64),
+            &request,
+        )
+        .unwrap();
+        assert_eq!(config.sysclk_source, ClockSource::Hsi);
+        assert_eq!(config.pll, None);
+        assert_eq!((config.flash_latency, config.voltage_scale), (1, 2));
+    }
+
+    #[test]
+    fn it_divides_sysclk_down_to_the_maximum_hclk() {
+        assert_eq!(ahb_prescaler(170 * MHZ, 170 * MHZ), 1);
+        assert_eq!(ahb_prescaler(480 * MHZ, 240 * MHZ), 2);
+        assert_eq!(ahb_prescaler(480 * MHZ, 100 * MHZ), 8);
+        let config = solve(
+            &chip_info("STM32F407VGT6", ArmCore::CortexM4, 168),
+            &ClockRequest::default(),
+        )
+        .unwrap();
+        assert_eq!(config.ahb_prescaler, 1);
+        assert_eq!(config.hclk_hz, config.sysclk_hz);
+    }
+
+    #[test]
+    fn it_selects_the_boost_mode() {
+        let request = ClockRequest {
+            hse: Some(24 * MHZ),
+            ..Default::default()
+        };
+        let config = solve(
+            &chip_info("STM32G474RET6", ArmCore::CortexM4, 170),
+            &request,
+        )
+        .unwrap();
+        assert_eq!(config.sysclk_hz, 170 * MHZ);
+        assert!(config.voltage_boost);
+        assert_eq!(config.flash_latency, 4);
+    }
+
+    #[test]
+    fn it_rejects_impossible_requests() {
+        let chip_info = chip_info("STM32F407VGT6", ArmCore::CortexM4, 168);
+        let request = |hse, sysclk, pll_q| ClockRequest { hse, sysclk, pll_q };
+        assert!(solve(&chip_info, &request(None, Some(200 * MHZ), None)).is_err());
+        assert!(solve(&chip_info, &request(Some(50 * MHZ), None, None)).is_err());
+        assert!(solve(&chip_info, &request(Some(8 * MHZ), None, Some(47_999_000))).is_err());
+        let h7 = self::chip_info("STM32H743ZIT6", ArmCore::CortexM7, 480);
+        assert!(solve(&h7, &ClockRequest::default()).is_err());
+        assert_eq!(
+            solve_for_project(&h7, &ClockRequest::default()).unwrap(),
+            None
+        );
+    }
+}
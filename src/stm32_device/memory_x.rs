@@ -0,0 +1,228 @@
+use anyhow::{bail, Result};
+use std::fmt::Write;
+
+use crate::stm32_device::chip_info::{ArmCore, ChipInfo, MemoryKind, MemoryRegion};
+
+/// Render `memory.x` with every region a core can use.
+///
+/// The first flash and RAM regions are named `FLASH` and `RAM` as required by `cortex-m-rt`,
+/// every other RAM region gets a `NOLOAD` section named after it, e.g. `.sram4`, so buffers
+/// can be placed with `#[link_section = ".sram4"]`. OTP, option bytes and flash banks are
+/// not linked, they are only listed in a comment with their origin.
+pub fn render_memory_x(chip_info: &ChipInfo, core: ArmCore) -> Result<String> {
+    render(chip_info, core, false)
+}
+
+/// Render the `memory.x` of one crate of a multicore workspace.
+///
+/// The cores must not share their `RAM`, so the first RAM region of the core itself is
+/// preferred to the shared ones. The shared regions stay available as `NOLOAD` sections
+/// for the communication between the cores.
+pub fn render_core_memory_x(chip_info: &ChipInfo, core: ArmCore) -> Result<String> {
+    render(chip_info, core, true)
+}
+
+fn render(chip_info: &ChipInfo, core: ArmCore, own_ram: bool) -> Result<String> {
+    let (regions, unlinked): (Vec<&MemoryRegion>, Vec<&MemoryRegion>) = chip_info
+        .regions_of_core(core.tag())
+        .into_iter()
+        .partition(|r| r.kind.is_linked());
+    let Some(flash) = regions.iter().find(|r| r.kind == MemoryKind::Flash) else {
+        bail!(
+            "{} has no flash region, pls update database!",
+            chip_info.refname
+        );
+    };
+    let own_region = regions
+        .iter()
+        .find(|r| own_ram && r.kind == MemoryKind::Ram && r.core.is_some());
+    let Some(ram) = own_region.or_else(|| regions.iter().find(|r| r.kind == MemoryKind::Ram))
+    else {
+        bail!(
+            "{} has no RAM region, pls update database!",
+            chip_info.refname
+        );
+    };
+    let linker_name = |region: &MemoryRegion| -> String {
+        if std::ptr::eq(region, *flash) {
+            "FLASH".into()
+        } else if std::ptr::eq(region, *ram) {
+            "RAM".into()
+        } else {
+            region.name.to_uppercase()
+        }
+    };
+
+    let mut script = String::new();
+    writeln!(
+        script,
+        "/* Memory layout of {} ({}), generated by cargo-stm32bs */",
+        chip_info.refname, core
+    )?;
+    writeln!(script, "MEMORY\n{{")?;
+    let width = regions
+        .iter()
+        .map(|r| linker_name(r).len())
+        .max()
+        .unwrap_or(0);
+    for region in regions.iter() {
+        let name = linker_name(region);
+        write!(
+            script,
+            "  {:<width$} : ORIGIN = {}, LENGTH = {}K",
+            name,
+            region.origin_hex(),
+            region.size
+        )?;
+        if name != region.name.to_uppercase() {
+            write!(script, " /* {} */", region.name)?;
+        }
+        writeln!(script)?;
+    }
+    writeln!(script, "}}")?;
+    for region in unlinked {
+        writeln!(
+            script,
+            "/* {} ({}) at {}, not linked */",
+            region.name,
+            region.kind.name(),
+            region.origin_hex()
+        )?;
+    }
+
+    let sections: Vec<&&MemoryRegion> = regions
+        .iter()
+        .filter(|r| r.kind == MemoryKind::Ram && !std::ptr::eq(**r, *ram))
+        .collect();
+    if !sections.is_empty() {
+        writeln!(script, "\nSECTIONS\n{{")?;
+        for region in sections {
+            let section = region.name.to_lowercase();
+            writeln!(script, "  .{section} (NOLOAD) : ALIGN(4)\n  {{")?;
+            writeln!(script, "    *(.{section} .{section}.*);")?;
+            writeln!(script, "    . = ALIGN(4);")?;
+            writeln!(script, "  }} > {}", linker_name(region))?;
+        }
+        writeln!(script, "}} INSERT AFTER .bss;")?;
+    }
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(
+        name: &str,
+        kind: MemoryKind,
+        origin: u32,
+        size: u32,
+        core: Option<&str>,
+    ) -> MemoryRegion {
+        MemoryRegion {
+            name: name.to_string(),
+            kind,
+            origin,
+            size,
+            core: core.map(str::to_string),
+        }
+    }
+
+    fn chip_info(memory_regions: Vec<MemoryRegion>) -> ChipInfo {
+        ChipInfo {
+            core2: Some(ArmCore::CortexM4),
+            memory_regions,
+            ..ChipInfo::test_part("STM32H745ZIT6", ArmCore::CortexM7)
+        }
+    }
+
+    #[test]
+    fn it_renders_every_region_of_a_core() {
+        let chip_info = chip_info(vec![
+            region("FLASH", MemoryKind::Flash, 0x0800_0000, 1024, Some("CM7")),
+            region(
+                "FLASH_CM4",
+                MemoryKind::Flash,
+                0x0810_0000,
+                1024,
+                Some("CM4"),
+            ),
+            region("AXISRAM", MemoryKind::Ram, 0x2400_0000, 512, None),
+            region("DTCM", MemoryKind::Ram, 0x2000_0000, 128, Some("CM7")),
+            region("SRAM1", MemoryKind::Ram, 0x3000_0000, 128, Some("CM4")),
+        ]);
+
+        assert_eq!(
+            render_memory_x(&chip_info, ArmCore::CortexM7).unwrap(),
+            "/* Memory layout of STM32H745ZITx (Cortex-M7), generated by cargo-stm32bs */\n\
+             MEMORY\n\
+             {\n\
+             \x20 FLASH : ORIGIN = 0x08000000, LENGTH = 1024K\n\
+             \x20 RAM   : ORIGIN = 0x24000000, LENGTH = 512K /* AXISRAM */\n\
+             \x20 DTCM  : ORIGIN = 0x20000000, LENGTH = 128K\n\
+             }\n\
+             \n\
+             SECTIONS\n\
+             {\n\
+             \x20 .dtcm (NOLOAD) : ALIGN(4)\n\
+             \x20 {\n\
+             \x20   *(.dtcm .dtcm.*);\n\
+             \x20   . = ALIGN(4);\n\
+             \x20 } > DTCM\n\
+             } INSERT AFTER .bss;\n"
+        );
+
+        let memory_x = render_memory_x(&chip_info, ArmCore::CortexM4).unwrap();
+        assert!(memory_x.contains("FLASH : ORIGIN = 0x08100000, LENGTH = 1024K /* FLASH_CM4 */"));
+        assert!(memory_x.contains("SRAM1 : ORIGIN = 0x30000000"));
+        assert!(!memory_x.contains("DTCM"));
+
+        // the crates of a workspace link to the RAM of their core
+        let memory_x = render_core_memory_x(&chip_info, ArmCore::CortexM7).unwrap();
+        assert!(memory_x.contains("RAM     : ORIGIN = 0x20000000, LENGTH = 128K /* DTCM */"));
+        assert!(memory_x.contains("  } > AXISRAM\n"));
+        let memory_x = render_core_memory_x(&chip_info, ArmCore::CortexM4).unwrap();
+        assert!(memory_x.contains("RAM     : ORIGIN = 0x30000000, LENGTH = 128K /* SRAM1 */"));
+    }
+
+    #[test]
+    fn it_renders_a_single_ram_without_sections() {
+        let chip_info = chip_info(MemoryRegion::legacy_regions(128, 36, 0));
+        let memory_x = render_memory_x(&chip_info, ArmCore::CortexM7).unwrap();
+        assert!(memory_x.contains("RAM   : ORIGIN = 0x20000000, LENGTH = 36K\n"));
+        assert!(!memory_x.contains("SECTIONS"));
+
+        let chip_info =
+            self::chip_info(vec![region("RAM", MemoryKind::Ram, 0x2000_0000, 36, None)]);
+        assert!(render_memory_x(&chip_info, ArmCore::CortexM7).is_err());
+    }
+
+    #[test]
+    fn it_lists_the_regions_which_are_not_linked() {
+        let chip_info = chip_info(vec![
+            region("FLASH", MemoryKind::Flash, 0x0800_0000, 512, None),
+            region("BANK2", MemoryKind::FlashBank, 0x0804_0000, 256, None),
+            region("RAM", MemoryKind::Ram, 0x2000_0000, 96, None),
+            region("OTP", MemoryKind::Otp, 0x1FFF_7000, 1, None),
+            region(
+                "OPTION_BYTES",
+                MemoryKind::OptionBytes,
+                0x1FFF_7800,
+                0,
+                None,
+            ),
+        ]);
+        assert_eq!(
+            render_memory_x(&chip_info, ArmCore::CortexM4).unwrap(),
+            "/* Memory layout of STM32H745ZITx (Cortex-M4), generated by cargo-stm32bs */\n\
+             MEMORY\n\
+             {\n\
+             \x20 FLASH : ORIGIN = 0x08000000, LENGTH = 512K\n\
+             \x20 RAM   : ORIGIN = 0x20000000, LENGTH = 96K\n\
+             }\n\
+             /* BANK2 (flash_bank) at 0x08040000, not linked */\n\
+             /* OTP (otp) at 0x1FFF7000, not linked */\n\
+             /* OPTION_BYTES (option_bytes) at 0x1FFF7800, not linked */\n"
+        );
+    }
+}
@@ -1,6 +1,14 @@
 /// This module is used to parse the STM32 chip part number
 pub mod chip_pn;
 
+/// This module is used to split the STM32 part number into its fields
+pub mod part_number;
 
 /// This module is used to parse the STM32 chip information
 pub mod chip_info;
+
+/// This module is used to generate the linker script `memory.x`
+pub mod memory_x;
+
+/// This module is used to solve the clock tree of a part
+pub mod clock;
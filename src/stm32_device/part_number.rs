@@ -0,0 +1,248 @@
+use anyhow::{bail, Result};
+
+use crate::stm32_device::chip_pn::{normalize_pn, PACKAGE_CODES, PIN_COUNT_CODES};
+
+/// Fields of a STM32 part number, e.g. `STM32G071CBT6TR`.
+///
+/// A part number can be incomplete, as typed in a search, the missing fields are `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PartNumber {
+    /// e.g. `STM32G0`
+    pub family: String,
+    /// Product line, e.g. `G071`
+    pub line: Option<String>,
+    /// Pin count code and pin count, e.g. `C` is 48 pins
+    pub pin_code: Option<(char, u32)>,
+    /// Flash size code and size in KB, e.g. `B` is 128 KB
+    pub flash_code: Option<(char, u32)>,
+    /// Package code and package type, e.g. `T` is LQFP
+    pub package_code: Option<(char, &'static str)>,
+    /// Temperature code and range in °C, e.g. `6` is -40 to 85 °C. `None` as well for
+    /// the `x` of the reference names.
+    pub temperature: Option<(char, i32, i32)>,
+    /// Packing and options after the temperature code, e.g. `TR` for tape and reel
+    pub packing: Option<String>,
+}
+
+/// Families by the start of their product lines, with the length of the product line.
+/// The longer starts come first, e.g. `WBA` before `WB`.
+const FAMILY_LINES: [(&str, &str, usize); 28] = [
+    ("WBA", "STM32WBA", 5),
+    ("WB0", "STM32WB0", 4),
+    ("WB", "STM32WB", 4),
+    ("WL3", "STM32WL3", 4),
+    ("WL", "STM32WL", 4),
+    ("L4R", "STM32L4+", 4),
+    ("L4S", "STM32L4+", 4),
+    ("L4P", "STM32L4+", 4),
+    ("L4Q", "STM32L4+", 4),
+    ("C0", "STM32C0", 4),
+    ("F0", "STM32F0", 4),
+    ("F1", "STM32F1", 4),
+    ("F2", "STM32F2", 4),
+    ("F3", "STM32F3", 4),
+    ("F4", "STM32F4", 4),
+    ("F7", "STM32F7", 4),
+    ("G0", "STM32G0", 4),
+    ("G4", "STM32G4", 4),
+    ("H5", "STM32H5", 4),
+    ("H7", "STM32H7", 4),
+    ("L0", "STM32L0", 4),
+    ("L1", "STM32L1", 4),
+    ("L4", "STM32L4", 4),
+    ("L5", "STM32L5", 4),
+    ("N6", "STM32N6", 4),
+    ("U0", "STM32U0", 4),
+    ("U3", "STM32U3", 4),
+    ("U5", "STM32U5", 4),
+];
+
+/// Flash size in KB of the flash codes
+const FLASH_CODES: [(char, u32); 17] = [
+    ('0', 0),
+    ('3', 8),
+    ('4', 16),
+    ('6', 32),
+    ('8', 64),
+    ('B', 128),
+    ('Z', 192),
+    ('C', 256),
+    ('D', 384),
+    ('E', 512),
+    ('Y', 640),
+    ('F', 768),
+    ('G', 1024),
+    ('H', 1536),
+    ('I', 2048),
+    ('J', 4096),
+    ('K', 3072),
+];
+
+/// Flash codes used by ST in each family
+const FAMILY_FLASH_CODES: [(&str, &str); 25] = [
+    ("STM32C0", "468BC"),
+    ("STM32F0", "468BC"),
+    ("STM32F1", "468BCDEFG"),
+    ("STM32F2", "BCEFG"),
+    ("STM32F3", "468BCDE"),
+    ("STM32F4", "8BCDEGHI"),
+    ("STM32F7", "8CEGI"),
+    ("STM32G0", "468BCE"),
+    ("STM32G4", "68BCE"),
+    ("STM32H5", "BCEGI"),
+    ("STM32H7", "8BEGI"),
+    ("STM32L0", "3468BZ"),
+    ("STM32L1", "68BCDE"),
+    ("STM32L4", "8BCEG"),
+    ("STM32L4+", "EGI"),
+    ("STM32L5", "CE"),
+    ("STM32U0", "468BC"),
+    ("STM32U3", "BCEG"),
+    ("STM32U5", "BCEGIJ"),
+    ("STM32WB", "CEGY"),
+    ("STM32WBA", "CEGI"),
+    ("STM32WB0", "8BZCE"),
+    ("STM32WL", "8BC"),
+    ("STM32WL3", "8BC"),
+    ("STM32N6", "0"),
+];
+
+/// Temperature range in °C of the temperature codes
+const TEMPERATURE_CODES: [(char, i32, i32); 3] = [('6', -40, 85), ('7', -40, 105), ('3', -40, 125)];
+
+impl PartNumber {
+    /// Split a part number, complete or not. The input is normalized as a search, so
+    /// ` g071cb` is `STM32G071CB`. A code which does not exist is an error, e.g. the flash
+    /// code `Z` of `STM32G071CZT6`.
+    pub fn parse(input: &str) -> Result<PartNumber> {
+        let pn = normalize_pn(input);
+        let Some(rest) = pn.strip_prefix("STM32") else {
+            bail!("`{}` is not a STM32 part number", input.trim());
+        };
+        let Some((start, family, line_len)) = FAMILY_LINES
+            .iter()
+            .find(|(start, _, _)| rest.starts_with(start))
+        else {
+            bail!("`{}` is not a known STM32 family", input.trim());
+        };
+        let mut part = PartNumber {
+            family: family.to_string(),
+            ..Default::default()
+        };
+        if rest.len() < *line_len {
+            // only the family, e.g. `G0`
+            if rest.len() > start.len() {
+                part.line = Some(rest.to_string());
+            }
+            return Ok(part);
+        }
+        part.line = Some(rest[..*line_len].to_string());
+
+        let mut codes = rest[*line_len..].chars();
+        if let Some(code) = codes.next() {
+            let Some(pins) = PIN_COUNT_CODES.iter().find(|(c, _)| *c == code) else {
+                bail!("pin count code '{}' does not exist", code);
+            };
+            part.pin_code = Some(*pins);
+        }
+        if let Some(code) = codes.next() {
+            let used = FAMILY_FLASH_CODES
+                .iter()
+                .find(|(f, _)| f == family)
+                .map_or("", |(_, codes)| *codes);
+            let flash = FLASH_CODES.iter().find(|(c, _)| *c == code);
+            match flash {
+                Some(flash) if used.contains(code) => part.flash_code = Some(*flash),
+                _ => bail!("flash code '{}' does not exist for {}", code, family),
+            }
+        }
+        if let Some(code) = codes.next() {
+            let Some(package) = PACKAGE_CODES.iter().find(|(c, _)| *c == code) else {
+                bail!("package code '{}' does not exist", code);
+            };
+            part.package_code = Some(*package);
+        }
+        if let Some(code) = codes.next() {
+            if code != 'X' {
+                let Some(temperature) = TEMPERATURE_CODES.iter().find(|(c, _, _)| *c == code)
+                else {
+                    bail!("temperature code '{}' does not exist", code);
+                };
+                part.temperature = Some(*temperature);
+            }
+        }
+        let packing: String = codes.collect();
+        if !packing.is_empty() {
+            part.packing = Some(packing);
+        }
+        Ok(part)
+    }
+
+    pub fn pin_count(&self) -> Option<u32> {
+        self.pin_code.map(|(_, pins)| pins)
+    }
+
+    /// Temperature range, e.g. `-40°C to 85°C`
+    pub fn temp_range(&self) -> Option<String> {
+        self.temperature
+            .map(|(_, min, max)| format!("{min}°C to {max}°C"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_a_part_number() {
+        let part = PartNumber::parse("STM32G071CBT6TR").unwrap();
+        assert_eq!(part.family, "STM32G0");
+        assert_eq!(part.line.as_deref(), Some("G071"));
+        assert_eq!(part.pin_code, Some(('C', 48)));
+        assert_eq!(part.flash_code, Some(('B', 128)));
+        assert_eq!(part.package_code, Some(('T', "LQFP")));
+        assert_eq!(part.temp_range().as_deref(), Some("-40°C to 85°C"));
+        assert_eq!(part.packing.as_deref(), Some("TR"));
+
+        let part = PartNumber::parse("STM32WBA52CGU6").unwrap();
+        assert_eq!(part.family, "STM32WBA");
+        assert_eq!(part.line.as_deref(), Some("WBA52"));
+        assert_eq!(part.pin_count(), Some(48));
+        assert_eq!(
+            PartNumber::parse("stm32l4r5zit6").unwrap().family,
+            "STM32L4+"
+        );
+        // the `x` of a reference name is no temperature range
+        let part = PartNumber::parse("STM32U575ZITxQ").unwrap();
+        assert_eq!(part.temperature, None);
+        assert_eq!(part.packing.as_deref(), Some("Q"));
+    }
+
+    #[test]
+    fn it_accepts_an_incomplete_part_number() {
+        let part = PartNumber::parse("g071cb").unwrap();
+        assert_eq!(part.flash_code, Some(('B', 128)));
+        assert_eq!(part.package_code, None);
+        let part = PartNumber::parse("G0").unwrap();
+        assert_eq!((part.family.as_str(), part.line), ("STM32G0", None));
+    }
+
+    #[test]
+    fn it_reports_the_wrong_code() {
+        let error = |pn: &str| PartNumber::parse(pn).unwrap_err().to_string();
+        assert_eq!(
+            error("STM32G071CZT6"),
+            "flash code 'Z' does not exist for STM32G0"
+        );
+        assert_eq!(error("STM32G071WBT6"), "pin count code 'W' does not exist");
+        assert_eq!(error("STM32G071CBW6"), "package code 'W' does not exist");
+        assert_eq!(
+            error("STM32G071CBT9"),
+            "temperature code '9' does not exist"
+        );
+        assert_eq!(
+            error("STM32X071CBT6"),
+            "`STM32X071CBT6` is not a known STM32 family"
+        );
+    }
+}
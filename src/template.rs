@@ -3,8 +3,7 @@ use console::style;
 use indicatif::ProgressBar;
 use liquid::model::KString;
 use liquid::{Parser, ParserBuilder};
-use liquid_core::{Object, Value};
-use std::collections::HashMap;
+use liquid_core::{Object, Value, ValueView};
 use std::env;
 use std::{
     fs,
@@ -12,11 +11,15 @@ use std::{
 };
 use tempfile::TempDir;
 
-use crate::interactive::prompt_and_check_variable;
+use crate::interactive::{expected_value, prompt_and_check_variable, MissingValues};
 use crate::progressbar;
 use crate::progressbar::spinner;
-use crate::project_variables::{TemplateSlots, VarInfo};
-use crate::stm32_device::chip_info::{ChipInfo, HSI_DEFAULT};
+use crate::project_variables::{check_input_project_variables, TemplateSlots, VarInfo};
+use crate::stm32_device::chip_info::{ChipInfo, MemoryKind};
+use crate::stm32_device::clock::{self, ClockRequest};
+use crate::stm32_device::memory_x::render_memory_x;
+use crate::stm32_device::part_number::PartNumber;
+use crate::template_cache;
 use crate::template_config::locate_template_configs;
 use crate::template_filters::*;
 use crate::template_variables::project_name::ProjectType;
@@ -64,6 +67,7 @@ pub fn set_project_variables(
     chipinfo: &ChipInfo,
     project_name: &String,
     project_type: &ProjectType,
+    clock_request: &ClockRequest,
 ) -> Result<()> {
     liquid_object.insert(
         "project-name".into(),
@@ -85,36 +89,29 @@ pub fn set_project_variables(
         "pac_feature".into(),
         Value::Scalar(chipinfo.pac_feature.to_owned().into()),
     );
-    liquid_object.insert("flash_origin".into(), Value::Scalar("0x08000000".into()));
-    liquid_object.insert(
-        "flash_size".into(),
-        Value::Scalar(chipinfo.flash.to_owned().into()),
-    );
-    liquid_object.insert("ram_origin".into(), Value::Scalar("0x20000000".into()));
-    liquid_object.insert(
-        "ram_size".into(),
-        Value::Scalar(chipinfo.ram.to_owned().into()),
-    );
-    liquid_object.insert("ccmram_origin".into(), Value::Scalar("0x10000000".into()));
-    liquid_object.insert(
-        "ccmram_size".into(),
-        Value::Scalar(chipinfo.ccmram.to_owned().into()),
-    );
+    set_part_variables(liquid_object, chipinfo)?;
+    set_memory_variables(liquid_object, chipinfo)?;
+    set_clock_variables(liquid_object, chipinfo, clock_request)?;
+    set_oscillator_variables(liquid_object, chipinfo);
+    set_peripheral_variables(liquid_object, chipinfo)?;
     liquid_object.insert("pn".into(), Value::Scalar(chipinfo.cpn.to_owned().into()));
     if let Some(core2) = chipinfo.core2 {
         liquid_object.insert("core2".into(), Value::Scalar(core2.to_string().into()));
     }
 
-    let freq =  chipinfo.freq;
+    let freq = chipinfo.freq;
     match project_type {
         ProjectType::BSPProject => {
             liquid_object.insert("frequency".into(), Value::Scalar(freq.into()));
+            liquid_object.insert("bsp".into(), Value::Scalar(true.into()));
         }
-        ProjectType::DemoProject(_) => {
-            let hsi_freq = HashMap::from(HSI_DEFAULT);
-            let family = chipinfo.family.clone();
-            let hsi_freq = hsi_freq.get(family.to_string().as_str()).unwrap();
-            liquid_object.insert("HSI_freq".into(), Value::Scalar((*hsi_freq).into()));
+        // demos run from HSI, its frequency must be known
+        ProjectType::DemoProject(demo) if chipinfo.oscillator("HSI").is_err() => {
+            bail!(
+                "Demo `{}` runs from HSI, but the HSI frequency of {} is absent, pls update the template!",
+                demo,
+                chipinfo.family
+            );
         }
         _ => {}
     }
@@ -122,10 +119,208 @@ pub fn set_project_variables(
     Ok(())
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn walk_dir(
-    include_list: &Vec<String>,
+/// Insert the details of the part: `chip_description`, `chip_status`, `chip_package` and
+/// `fpu` (`none`, `single` or `double`), `co_type` and `co_freq` for the parts with a
+/// coprocessor.
+/// `line`, `pin_count` and `temp_range` are decoded from the part number.
+fn set_part_variables(liquid_object: &mut Object, chipinfo: &ChipInfo) -> Result<()> {
+    liquid_object.insert(
+        "chip_description".into(),
+        Value::Scalar(chipinfo.description.to_owned().into()),
+    );
+    liquid_object.insert(
+        "chip_status".into(),
+        Value::Scalar(chipinfo.status.to_string().into()),
+    );
+    liquid_object.insert(
+        "chip_package".into(),
+        Value::Scalar(chipinfo.package.to_owned().into()),
+    );
+    liquid_object.insert("fpu".into(), liquid_core::model::to_value(&chipinfo.fpu)?);
+    if let Some(co_type) = &chipinfo.co_type {
+        liquid_object.insert("co_type".into(), Value::Scalar(co_type.to_owned().into()));
+    }
+    if let Some(co_freq) = chipinfo.co_freq {
+        liquid_object.insert("co_freq".into(), Value::Scalar(co_freq.into()));
+    }
+    // the part number has been checked when it was chosen
+    if let Ok(part) = PartNumber::parse(&chipinfo.cpn) {
+        if let Some(line) = part.line.clone() {
+            liquid_object.insert("line".into(), Value::Scalar(line.into()));
+        }
+        if let Some(pin_count) = part.pin_count() {
+            liquid_object.insert("pin_count".into(), Value::Scalar(pin_count.into()));
+        }
+        if let Some(temp_range) = part.temp_range() {
+            liquid_object.insert("temp_range".into(), Value::Scalar(temp_range.into()));
+        }
+    }
+    Ok(())
+}
+
+/// Insert the memory regions of the part, `flash_*` and `ram_*` describe the regions
+/// a program is linked to, `memory_regions` lists every region of the part and
+/// `memory_x` is a complete `memory.x` for the main core
+fn set_memory_variables(liquid_object: &mut Object, chipinfo: &ChipInfo) -> Result<()> {
+    let Some(flash) = chipinfo.main_region(MemoryKind::Flash) else {
+        bail!(
+            "{} has no flash region, pls update database!",
+            chipinfo.refname
+        );
+    };
+    let Some(ram) = chipinfo.main_region(MemoryKind::Ram) else {
+        bail!(
+            "{} has no RAM region, pls update database!",
+            chipinfo.refname
+        );
+    };
+    liquid_object.insert(
+        "flash_origin".into(),
+        Value::Scalar(flash.origin_hex().into()),
+    );
+    liquid_object.insert("flash_size".into(), Value::Scalar(flash.size.into()));
+    liquid_object.insert("ram_origin".into(), Value::Scalar(ram.origin_hex().into()));
+    liquid_object.insert("ram_size".into(), Value::Scalar(ram.size.into()));
+    // kept for the templates written before the memory regions, the size is 0 without CCMRAM
+    let ccmram = chipinfo.region("CCMRAM");
+    liquid_object.insert(
+        "ccmram_origin".into(),
+        Value::Scalar(
+            ccmram
+                .map_or("0x00000000".into(), |r| r.origin_hex())
+                .into(),
+        ),
+    );
+    liquid_object.insert(
+        "ccmram_size".into(),
+        Value::Scalar(ccmram.map_or(0, |r| r.size).into()),
+    );
+
+    let regions = chipinfo
+        .memory_regions
+        .iter()
+        .map(|region| {
+            let mut object = Object::new();
+            object.insert("name".into(), Value::Scalar(region.name.clone().into()));
+            object.insert("kind".into(), Value::Scalar(region.kind.name().into()));
+            object.insert("origin".into(), Value::Scalar(region.origin_hex().into()));
+            object.insert("size".into(), Value::Scalar(region.size.into()));
+            // nil for the shared regions, so `region.core` can be tested by the templates
+            let core = match &region.core {
+                Some(core) => Value::Scalar(core.clone().into()),
+                None => Value::Nil,
+            };
+            object.insert("core".into(), core);
+            Value::Object(object)
+        })
+        .collect();
+    liquid_object.insert("memory_regions".into(), Value::Array(regions));
+    liquid_object.insert(
+        "memory_x".into(),
+        Value::Scalar(render_memory_x(chipinfo, chipinfo.core)?.into()),
+    );
+    Ok(())
+}
+
+/// Insert the default frequency of every oscillator of the family as `<NAME>_freq`,
+/// e.g. `HSI_freq` or `MSI_freq`, and all of them in the `oscillators` object
+fn set_oscillator_variables(liquid_object: &mut Object, chipinfo: &ChipInfo) {
+    let mut oscillators = Object::new();
+    for oscillator in chipinfo.oscillators.iter() {
+        liquid_object.insert(
+            format!("{}_freq", oscillator.name).into(),
+            Value::Scalar(oscillator.frequency.into()),
+        );
+        oscillators.insert(
+            oscillator.name.clone().into(),
+            Value::Scalar(oscillator.frequency.into()),
+        );
+    }
+    liquid_object.insert("oscillators".into(), Value::Object(oscillators));
+}
+
+/// Insert the peripheral instances of the part: `peripheral_instances` lists their names,
+/// e.g. `{% if peripheral_instances contains "USART3" %}`, and `peripherals` groups them
+/// by kind, e.g. `peripherals.USART[0].irqs[0].number`. Both are empty if the database
+/// has no peripheral inventory.
+fn set_peripheral_variables(liquid_object: &mut Object, chipinfo: &ChipInfo) -> Result<()> {
+    let mut peripherals = Object::new();
+    for peripheral in chipinfo.peripherals.iter() {
+        let value = liquid_core::model::to_value(peripheral)?;
+        match peripherals.get_mut(peripheral.kind()) {
+            Some(Value::Array(instances)) => instances.push(value),
+            _ => {
+                peripherals.insert(
+                    peripheral.kind().to_string().into(),
+                    Value::Array(vec![value]),
+                );
+            }
+        }
+    }
+    let instances = chipinfo
+        .peripherals
+        .iter()
+        .map(|p| Value::Scalar(p.instance.clone().into()))
+        .collect();
+    liquid_object.insert("peripheral_instances".into(), Value::Array(instances));
+    liquid_object.insert("peripherals".into(), Value::Object(peripherals));
+    Ok(())
+}
+
+/// Insert the solved clock tree as `clock`, e.g. `clock.sysclk_hz` or `clock.pll.n`.
+/// It is nil for the families the solver does not know, unless clock options were given.
+fn set_clock_variables(
+    liquid_object: &mut Object,
+    chipinfo: &ChipInfo,
+    clock_request: &ClockRequest,
+) -> Result<()> {
+    let clock = match clock::solve_for_project(chipinfo, clock_request)? {
+        Some(config) => Value::Object(liquid::to_object(&config)?),
+        None => Value::Nil,
+    };
+    liquid_object.insert("clock".into(), clock);
+    Ok(())
+}
+
+/// A file of the project and the template file it is rendered from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateFile {
+    /// Path in the project, e.g. `src/main.rs`
+    pub target: String,
+    pub source: PathBuf,
+}
+
+/// The files of `include_list`, each one rendered from the same path of the template
+/// unless one of `overrides` has the same target, e.g. the `src/main.rs` of a demo.
+/// Every target is listed once.
+pub fn template_files(
     template_dir: &Path,
+    include_list: &[String],
+    overrides: &[TemplateFile],
+) -> Vec<TemplateFile> {
+    let mut files: Vec<TemplateFile> = Vec::with_capacity(include_list.len());
+    for filename in include_list {
+        if !files.iter().any(|f| &f.target == filename) {
+            files.push(TemplateFile {
+                target: filename.clone(),
+                source: template_dir.join(filename),
+            });
+        }
+    }
+    for file in overrides {
+        match files.iter_mut().find(|f| f.target == file.target) {
+            Some(existing) => existing.source = file.source.clone(),
+            None => files.push(file.clone()),
+        }
+    }
+    files
+}
+
+/// Render the template files into `destination`. The template is only read, so the same
+/// template can be rendered several times.
+pub fn render_files(
+    files: &[TemplateFile],
+    destination: &Path,
     liquid_object: &mut Object,
 ) -> Result<()> {
     let liquid_engine = create_liquid_engine();
@@ -133,11 +328,12 @@ pub fn walk_dir(
     let mp = progressbar::new();
     let spinner_style = spinner();
 
-    if include_list.is_empty() {
+    if files.is_empty() {
         bail!("No files to include in the template.");
     }
-    let total = include_list.len().to_string();
-    for (progress, filename) in include_list.iter().enumerate() {
+    let total = files.len().to_string();
+    for (progress, file) in files.iter().enumerate() {
+        let filename = &file.target;
         let pb = mp.add(ProgressBar::new(50));
         pb.set_style(spinner_style.clone());
         pb.set_prefix(format!(
@@ -146,19 +342,19 @@ pub fn walk_dir(
             total,
             width = total.len()
         ));
-        let filepath = PathBuf::from(template_dir).join(filename);
         pb.set_message(format!("Processing: {filename:?}"));
-        if !filepath.exists() {
+        if !file.source.exists() {
             bail!(
                 "File `{}` does not exist in the template directory.",
-                filepath.display()
+                file.source.display()
             );
         }
-        match template_process_file(liquid_object, &liquid_engine, &filepath) {
+        match template_process_file(liquid_object, &liquid_engine, &file.source) {
             Ok(new_contents) => {
                 pb.inc(25);
-                fs::create_dir_all(filepath.parent().unwrap()).unwrap();
-                fs::write(filepath, new_contents).with_context(|| {
+                let dst_path = destination.join(filename);
+                fs::create_dir_all(dst_path.parent().unwrap())?;
+                fs::write(dst_path, new_contents).with_context(|| {
                     format!(
                         "⛔ {} `{}`",
                         style("Error writing rendered file.").bold().red(),
@@ -171,7 +367,7 @@ pub fn walk_dir(
             Err(e) => {
                 bail!(
                     "⛔ Error processing file `{}`: {}",
-                    filepath.display(),
+                    file.source.display(),
                     e.to_string()
                 );
             }
@@ -244,21 +440,7 @@ pub fn render_string_gracefully(
 /// TemplateLocation: Local path or git path
 pub fn get_source_template_into_temp(template_location: &TemplateLocation) -> Result<TempDir> {
     match template_location {
-        TemplateLocation::Git(git) => {
-            let result = utils::clone_git_template_into_temp(
-                git.url(),
-                git.branch(),
-                git.tag(),
-                git.revision(),
-                git.identity(),
-                git.gitconfig(),
-                git.skip_submodules,
-            );
-            if let Result::Ok(ref temp_dir) = result {
-                utils::remove_history(temp_dir.path())?;
-            };
-            result
-        }
+        TemplateLocation::Git(git) => template_cache::get_git_template_into_temp(git),
         TemplateLocation::Path(path) => {
             let temp_dir = tmp_dir()?;
             let mut file_list = fs::read_dir(path)?
@@ -304,9 +486,26 @@ pub fn get_source_template_into_temp(template_location: &TemplateLocation) -> Re
 }
 
 /// resolve the template location for the actual template to expand
-pub fn resolve_template_dir(template_base_dir: &TempDir) -> Result<PathBuf> {
+pub fn resolve_template_dir(
+    template_base_dir: &TempDir,
+    user_parsed_input: &UserParsedInput,
+) -> Result<PathBuf> {
     let template_dir = template_base_dir.path().to_path_buf();
-    auto_locate_template_dir(template_dir, &mut |slots| prompt_and_check_variable(slots))
+    auto_locate_template_dir(template_dir, &mut |slots| {
+        if !user_parsed_input.is_silent() {
+            return prompt_and_check_variable(slots);
+        }
+        let provided_value = user_parsed_input
+            .template_value_str(&slots.var_name)
+            .map(|s| s.to_string());
+        match check_input_project_variables(slots, provided_value) {
+            Some(value) => Ok(value.to_kstr().to_string()),
+            None => Err(MissingValues::single(
+                &slots.var_name,
+                expected_value(slots),
+            )),
+        }
+    })
 }
 
 /// look through the template folder structure and attempt to find a suitable template.
@@ -338,6 +537,7 @@ fn auto_locate_template_dir(
                         .collect(),
                     default: None,
                 },
+                peripheral: None,
             };
             let path = prompt(&prompt_args)?;
 
@@ -348,3 +548,80 @@ fn auto_locate_template_dir(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stm32_device::chip_info::ArmCore;
+
+    #[test]
+    fn it_needs_the_hsi_frequency_for_a_demo() {
+        let chip_info = ChipInfo {
+            oscillators: Vec::new(),
+            ..ChipInfo::test_part("STM32H743ZIT6", ArmCore::CortexM7)
+        };
+        let name = "blinky".to_string();
+        let request = ClockRequest::default();
+        let demo = ProjectType::DemoProject("blink".to_string());
+        let error = set_project_variables(&mut Object::new(), &chip_info, &name, &demo, &request)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("the HSI frequency of STM32H7 is absent"));
+        set_project_variables(
+            &mut Object::new(),
+            &chip_info,
+            &name,
+            &ProjectType::BSPProject,
+            &request,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn it_renders_without_modifying_the_template() {
+        let template = tmp_dir().unwrap();
+        let destination = tmp_dir().unwrap();
+        fs::create_dir_all(template.path().join("src")).unwrap();
+        fs::create_dir_all(template.path().join("demo")).unwrap();
+        fs::write(template.path().join("Cargo.toml"), "name = \"{{name}}\"").unwrap();
+        fs::write(template.path().join("src/main.rs"), "// main").unwrap();
+        fs::write(template.path().join("demo/blink.rs"), "// blink {{name}}").unwrap();
+
+        let include_list = ["Cargo.toml".to_string(), "src/main.rs".to_string()];
+        let demo = TemplateFile {
+            target: "src/main.rs".to_string(),
+            source: template.path().join("demo/blink.rs"),
+        };
+        let files = template_files(template.path(), &include_list, &[demo]);
+        assert_eq!(files.len(), 2);
+
+        let mut object = Object::new();
+        object.insert("name".into(), Value::Scalar("blinky".into()));
+        render_files(&files, destination.path(), &mut object).unwrap();
+        // a second project from the same template
+        render_files(&files, &destination.path().join("again"), &mut object).unwrap();
+
+        for dir in [
+            destination.path().to_path_buf(),
+            destination.path().join("again"),
+        ] {
+            assert_eq!(
+                fs::read_to_string(dir.join("Cargo.toml")).unwrap(),
+                "name = \"blinky\""
+            );
+            assert_eq!(
+                fs::read_to_string(dir.join("src/main.rs")).unwrap(),
+                "// blink blinky"
+            );
+        }
+        assert_eq!(
+            fs::read_to_string(template.path().join("Cargo.toml")).unwrap(),
+            "name = \"{{name}}\""
+        );
+        assert_eq!(
+            fs::read_to_string(template.path().join("src/main.rs")).unwrap(),
+            "// main"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use log::{info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+use crate::user_parsed_input::GitUserInput;
+use crate::utils;
+
+/// Directory of the cached templates, in the home directory
+const CACHE_DIR: &str = ".stm32bs/templates";
+
+/// How the cached templates are used, set by `--offline` and `--refresh`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheMode {
+    /// Fetch the template and update the cache, the cached template is only used when
+    /// the fetch fails, e.g. without network access
+    #[default]
+    Default,
+    /// Only use the cached template, without network access
+    Offline,
+    /// Fetch the template and replace the cached one, without falling back to the cache
+    Refresh,
+}
+
+impl CacheMode {
+    pub fn from_flags(offline: bool, refresh: bool) -> Self {
+        match (offline, refresh) {
+            (true, _) => CacheMode::Offline,
+            (false, true) => CacheMode::Refresh,
+            (false, false) => CacheMode::Default,
+        }
+    }
+}
+
+/// Get the template of a git repository into a temporary directory, through the cache.
+/// Local repositories are not cached, they are available without network anyway.
+pub fn get_git_template_into_temp(git: &GitUserInput) -> Result<TempDir> {
+    if !is_remote(git.url()) {
+        return clone_template(git);
+    }
+    let cache_dir = cache_dir(git.url(), git.git_ref())?;
+    match git.cache_mode() {
+        CacheMode::Offline if !cache_dir.exists() => bail!(
+            "{} {} {}",
+            style("The template").bold().red(),
+            style(git.url()).bold().yellow(),
+            style("is not cached yet, run once without --offline!")
+                .bold()
+                .red()
+        ),
+        CacheMode::Offline => use_cached(&cache_dir),
+        CacheMode::Default => match clone_template(git) {
+            Ok(temp_dir) => {
+                store(temp_dir.path(), &cache_dir)?;
+                Ok(temp_dir)
+            }
+            Err(error) if cache_dir.exists() => {
+                warn!(
+                    "{} {:#}",
+                    style("Cannot fetch the template, the cached one is used:")
+                        .bold()
+                        .yellow(),
+                    error
+                );
+                use_cached(&cache_dir)
+            }
+            Err(error) => Err(error),
+        },
+        CacheMode::Refresh => {
+            let temp_dir = clone_template(git)?;
+            store(temp_dir.path(), &cache_dir)?;
+            Ok(temp_dir)
+        }
+    }
+}
+
+/// Copy the cached template into a temporary directory
+fn use_cached(cache_dir: &Path) -> Result<TempDir> {
+    info!(
+        "🔧 {} {}",
+        style("Using the cached template").bold(),
+        style(cache_dir.display()).underlined()
+    );
+    let temp_dir = utils::tmp_dir()?;
+    utils::copy_dir_all(cache_dir, temp_dir.path())?;
+    Ok(temp_dir)
+}
+
+fn clone_template(git: &GitUserInput) -> Result<TempDir> {
+    let temp_dir = utils::clone_git_template_into_temp(
+        git.url(),
+        git.branch(),
+        git.tag(),
+        git.revision(),
+        git.identity(),
+        git.gitconfig(),
+        git.skip_submodules,
+    )?;
+    utils::remove_history(temp_dir.path())?;
+    Ok(temp_dir)
+}
+
+/// Replace the cached template, it is first copied next to the cache so an
+/// interrupted copy never leaves a partial template behind
+fn store(template_dir: &Path, cache_dir: &Path) -> Result<()> {
+    let partial_dir = cache_dir.with_extension("partial");
+    if partial_dir.exists() {
+        fs::remove_dir_all(&partial_dir)?;
+    }
+    utils::copy_dir_all(template_dir, &partial_dir)
+        .with_context(|| format!("Cannot cache the template in {}", cache_dir.display()))?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(cache_dir)?;
+    }
+    fs::rename(&partial_dir, cache_dir)?;
+    Ok(())
+}
+
+fn is_remote(url: &str) -> bool {
+    url.contains("://") || url.starts_with("git@")
+}
+
+fn cache_dir(url: &str, git_ref: &str) -> Result<PathBuf> {
+    Ok(utils::home()?.join(CACHE_DIR).join(cache_key(url, git_ref)))
+}
+
+/// `https://github.com/AtlasHW/stm32bs-template-default` at `HEAD` is cached in
+/// `github.com_AtlasHW_stm32bs-template-default@HEAD`
+fn cache_key(url: &str, git_ref: &str) -> String {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+    let sanitize = |s: &str| -> String {
+        s.chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' => c,
+                _ => '_',
+            })
+            .collect()
+    };
+    format!("{}@{}", sanitize(url), sanitize(git_ref))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keys_the_cache_by_url_and_ref() {
+        assert_eq!(
+            cache_key(
+                "https://github.com/AtlasHW/stm32bs-template-default",
+                "HEAD"
+            ),
+            "github.com_AtlasHW_stm32bs-template-default@HEAD"
+        );
+        assert_eq!(
+            cache_key(
+                "https://github.com/AtlasHW/stm32bs-template-default.git/",
+                "v0.1.0"
+            ),
+            "github.com_AtlasHW_stm32bs-template-default@v0.1.0"
+        );
+        assert_eq!(
+            cache_key("git@github.com:AtlasHW/template.git", "feature/x"),
+            "git_github.com_AtlasHW_template@feature_x"
+        );
+    }
+
+    #[test]
+    fn it_tells_remote_repositories_apart() {
+        assert!(is_remote("https://github.com/AtlasHW/stm32bs"));
+        assert!(is_remote("file:///tmp/template"));
+        assert!(is_remote("git@github.com:AtlasHW/stm32bs.git"));
+        assert!(!is_remote("/tmp/template"));
+        assert!(!is_remote("../template"));
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use clap::ValueEnum;
 use indexmap::IndexMap;
 use semver::VersionReq;
 use serde::Deserialize;
@@ -14,13 +15,16 @@ pub struct Config {
     pub template: Option<TemplateConfig>,
     pub placeholders: Option<TemplateSlotsTable>,
     pub conditional: Option<HashMap<String, ConditionalConfig>>,
-    pub demo: Option<HashMap<String, IndexMap<String, toml::Value>>>,
+    pub demo: Option<HashMap<String, DemoConfig>>,
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Default, Clone)]
 pub struct TemplateConfig {
     pub cargo_generate_version: Option<VersionReq>,
     pub include: Option<Vec<String>>,
+    /// Write `memory.x` from the memory regions of the part instead of rendering the one of
+    /// the template
+    pub generate_memory_x: Option<bool>,
 }
 
 #[derive(Deserialize, Debug, PartialEq, Clone)]
@@ -29,6 +33,90 @@ pub struct ConditionalConfig {
     pub placeholders: Option<TemplateSlotsTable>,
 }
 
+/// A `[demo.<name>]` table, every key besides `description`, `tags`, `difficulty`,
+/// `hardware`, `requires`, `include` and `dependencies` is a placeholder of the demo
+#[derive(Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct DemoConfig {
+    /// One line shown in the demo chooser and by `cargo stm32bs demos`
+    pub description: Option<String>,
+    /// e.g. `["gpio", "interrupt"]`
+    pub tags: Option<Vec<String>>,
+    pub difficulty: Option<Difficulty>,
+    /// Hardware needed besides the part, e.g. `["LED on PA5", "USB cable"]`
+    pub hardware: Option<Vec<String>>,
+    pub requires: Option<DemoRequirements>,
+    /// Files of the template added to the project with the demo, as `[template] include`
+    pub include: Option<Vec<String>>,
+    /// Crates added to `Cargo.toml`, as in cargo, e.g. `heapless = "0.8"`
+    pub dependencies: Option<HashMap<String, toml::Value>>,
+    #[serde(flatten)]
+    pub placeholders: IndexMap<String, toml::Value>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Beginner => write!(f, "beginner"),
+            Difficulty::Intermediate => write!(f, "intermediate"),
+            Difficulty::Advanced => write!(f, "advanced"),
+        }
+    }
+}
+
+impl DemoConfig {
+    /// The demo has the tag, the case is ignored
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags
+            .iter()
+            .flatten()
+            .any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// One line with the description, the difficulty, the tags and the hardware,
+    /// e.g. `Blink the LED · beginner · gpio · needs LED on PA5`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(description) = &self.description {
+            parts.push(description.clone());
+        }
+        if let Some(difficulty) = self.difficulty {
+            parts.push(difficulty.to_string());
+        }
+        if let Some(tags) = self.tags.as_ref().filter(|t| !t.is_empty()) {
+            parts.push(tags.join(", "));
+        }
+        if let Some(hardware) = self.hardware.as_ref().filter(|h| !h.is_empty()) {
+            parts.push(format!("needs {}", hardware.join(", ")));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// What a part needs to run a demo, e.g. `requires = { peripherals = ["USB"] }`.
+/// A part matches a list if it matches one of its entries.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DemoRequirements {
+    /// e.g. `STM32G0`
+    pub families: Option<Vec<String>>,
+    /// e.g. `CM4` or `Cortex-M4`, the second core of a multicore part counts as well
+    pub cores: Option<Vec<String>>,
+    /// instances or kinds of peripherals which must all be present, e.g. `USART2` or `USB`
+    pub peripherals: Option<Vec<String>>,
+    /// in KB
+    pub min_flash: Option<u32>,
+    /// in KB
+    pub min_ram: Option<u32>,
+}
+
 #[derive(Deserialize, Debug, PartialEq, Clone, Default)]
 pub struct TemplateSlotsTable(pub IndexMap<String, toml::Value>);
 
@@ -57,6 +145,11 @@ impl Config {
         Ok(config)
     }
 
+    /// The `[demo.<name>]` table of a demo
+    pub fn get_demo(&self, name: &str) -> Option<&DemoConfig> {
+        self.demo.as_ref().and_then(|demos| demos.get(name))
+    }
+
     pub fn get_demo_list(&self) -> Vec<String> {
         self.demo
             .as_ref()
@@ -143,10 +236,8 @@ pub fn replenish_include_file<P: AsRef<Path>>(
                 );
             }
         }
-        return Ok(());
-    } else {
-        return Ok(());
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -184,6 +275,7 @@ mod tests {
             Some(TemplateConfig {
                 cargo_generate_version: Some(VersionReq::from_str(">=0.8.0").unwrap()),
                 include: Some(vec!["Cargo.toml".into()]),
+                generate_memory_x: None,
             })
         );
         assert!(config.placeholders.is_some());
@@ -219,6 +311,54 @@ mod tests {
         assert!(result.is_err(), "Config should not have parsed");
     }
 
+    #[test]
+    fn config_try_from_handles_demo_tables() {
+        let config = Config::try_from(
+            r#"
+            [demo.usb]
+            requires = { families = ["STM32F4"], peripherals = ["USB_OTG_FS"], min_ram = 64 }
+            vid = { type = "string", prompt = "USB vendor id" }
+            "#
+            .to_string(),
+        )
+        .unwrap();
+        let demo = &config.demo.unwrap()["usb"];
+        let requires = demo.requires.as_ref().unwrap();
+        assert_eq!(requires.families, Some(vec!["STM32F4".to_string()]));
+        assert_eq!(requires.min_ram, Some(64));
+        assert_eq!(requires.min_flash, None);
+        // `requires` is no placeholder
+        assert_eq!(demo.placeholders.keys().collect::<Vec<_>>(), vec!["vid"]);
+
+        let config = Config::try_from(
+            r#"
+            [demo.blink]
+            description = "Blink the LED"
+            difficulty = "beginner"
+            tags = ["gpio", "timer"]
+            hardware = ["LED on PA5"]
+            "#
+            .to_string(),
+        )
+        .unwrap();
+        let demo = config.get_demo("blink").unwrap();
+        assert!(demo.placeholders.is_empty());
+        assert!(demo.has_tag("GPIO"));
+        assert_eq!(
+            demo.summary(),
+            "Blink the LED · beginner · gpio, timer · needs LED on PA5"
+        );
+
+        let result = Config::try_from(
+            r#"
+            [demo.usb]
+            requires = { family = ["STM32F4"] }
+            "#
+            .to_string(),
+        );
+        assert!(result.is_err(), "unknown requirements should be refused");
+    }
+
     #[test]
     fn config_try_from_handles_placeholders() {
         let result = Config::try_from(
@@ -2,8 +2,12 @@ mod authors;
 mod project_dir;
 pub mod project_name;
 
+use anyhow::{Context, Result};
 use indexmap::IndexMap;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 
 pub use authors::{get_authors, Authors};
 
@@ -13,3 +17,101 @@ pub use project_dir::ProjectDir;
 struct TemplateValuesToml {
     pub(crate) values: IndexMap<String, toml::Value>,
 }
+
+/// Read the template values from a file given by `--values-file`.
+///
+/// The file is either a TOML file with a `[values]` table, or a list of
+/// `key=value` lines. Values of the lines are read as TOML values when possible,
+/// so `count=3` gives an integer and `list=["a", "b"]` an array, anything else is a string.
+pub fn load_template_values_file(path: &Path) -> Result<HashMap<String, toml::Value>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read the values file {}", path.display()))?;
+    if let Ok(values) = toml::from_str::<TemplateValuesToml>(&content) {
+        return Ok(values.values.into_iter().collect());
+    }
+
+    let mut values = HashMap::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            anyhow::bail!(
+                "Line {} of the values file {} is not in the format `key=value`",
+                number + 1,
+                path.display()
+            );
+        };
+        values.insert(key.trim().to_string(), parse_value(value.trim()));
+    }
+    Ok(values)
+}
+
+/// Keep the type of a value written in TOML syntax, fallback to a plain string
+fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tmp_dir;
+
+    #[test]
+    fn it_loads_a_values_table() {
+        let dir = tmp_dir().unwrap();
+        let path = dir.path().join("values.toml");
+        fs::write(
+            &path,
+            r#"
+            [values]
+            port = "B"
+            pin = 5
+            led = true
+            list = ["a", "b"]
+            "#,
+        )
+        .unwrap();
+
+        let values = load_template_values_file(&path).unwrap();
+        assert_eq!(values.get("port"), Some(&toml::Value::String("B".into())));
+        assert_eq!(values.get("pin"), Some(&toml::Value::Integer(5)));
+        assert_eq!(values.get("led"), Some(&toml::Value::Boolean(true)));
+        assert_eq!(
+            values.get("list"),
+            Some(&toml::Value::Array(vec!["a".into(), "b".into()]))
+        );
+    }
+
+    #[test]
+    fn it_loads_key_value_lines() {
+        let dir = tmp_dir().unwrap();
+        let path = dir.path().join("values.txt");
+        fs::write(
+            &path,
+            "# comment\nport=B\npin = 5\nled=true\nname=\"quoted\"\nlist=[\"a\", \"b\"]\n\n",
+        )
+        .unwrap();
+
+        let values = load_template_values_file(&path).unwrap();
+        assert_eq!(values.len(), 5);
+        assert_eq!(values.get("port"), Some(&toml::Value::String("B".into())));
+        assert_eq!(values.get("pin"), Some(&toml::Value::Integer(5)));
+        assert_eq!(values.get("led"), Some(&toml::Value::Boolean(true)));
+        assert_eq!(
+            values.get("name"),
+            Some(&toml::Value::String("quoted".into()))
+        );
+        assert_eq!(
+            values.get("list"),
+            Some(&toml::Value::Array(vec!["a".into(), "b".into()]))
+        );
+
+        fs::write(&path, "port=B\nnot a pair\n").unwrap();
+        assert!(load_template_values_file(&path).is_err());
+    }
+}
@@ -5,10 +5,16 @@ use heck::ToKebabCase;
 use log::info;
 use log::warn;
 
+use crate::demos;
+use crate::interactive::MissingValues;
+use crate::stm32_device::chip_info::ChipInfo;
 use crate::template_config::Config;
 use crate::{interactive, user_parsed_input::UserParsedInput};
 
-pub fn get_project_name(user_parsed_input: &UserParsedInput) -> String {
+pub fn get_project_name(
+    user_parsed_input: &UserParsedInput,
+    missing: &mut MissingValues,
+) -> String {
     let name = user_parsed_input.name();
     match name {
         Some(name) => {
@@ -24,10 +30,15 @@ pub fn get_project_name(user_parsed_input: &UserParsedInput) -> String {
             }
             name.to_kebab_case()
         }
+        None if user_parsed_input.is_silent() => {
+            missing.add("project-name", "no project name is given, use --name");
+            String::new()
+        }
         None => interactive::name().unwrap(),
     }
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum ProjectType {
     BSPProject,
@@ -45,20 +56,33 @@ impl std::fmt::Display for ProjectType {
     }
 }
 
+/// Get the project type from `--type`, `--demo` or a prompt.
+/// In silent mode `None` is returned when the type can not be resolved without a prompt.
+///
+/// Only the demos the part can run are offered, a demo given by name which the part can
+/// not run is an error.
 pub fn get_project_type(
     user_parsed_input: &UserParsedInput,
     template_config: &mut Config,
-) -> Result<ProjectType> {
+    chip_info: Option<&ChipInfo>,
+    missing: &mut MissingValues,
+) -> Result<Option<ProjectType>> {
+    if user_parsed_input.is_silent() {
+        return get_project_type_silent(user_parsed_input, template_config, chip_info, missing);
+    }
     let project_type = user_parsed_input.project_type();
     let demo_name = user_parsed_input.demo_name();
     if let Some(demo_name) = demo_name {
-        return Ok(ProjectType::DemoProject(demo_name.to_string()));
+        if let Some(chip_info) = chip_info {
+            demos::check_demo(template_config, demo_name, chip_info)?;
+        }
+        return Ok(Some(ProjectType::DemoProject(demo_name.to_string())));
     }
     let mut is_demo = false;
     if let Some(project_type_str) = project_type {
         match project_type_str {
-            "bsp" => return Ok(ProjectType::BSPProject),
-            "empty" => return Ok(ProjectType::EmptyProject),
+            "bsp" => return Ok(Some(ProjectType::BSPProject)),
+            "empty" => return Ok(Some(ProjectType::EmptyProject)),
             "demo" => is_demo = true,
             _ => {
                 bail!("Invalid project type: {}", project_type_str);
@@ -78,8 +102,6 @@ pub fn get_project_type(
     let project_type = match project_type_str.as_str() {
         "Project with BSP" => {
             info!("Create a STM32 Project with BSP...");
-            bail!("The function is not implemented yet!");
-            #[allow(unreachable_code)]
             ProjectType::BSPProject
         }
         "Empty Project" => {
@@ -88,15 +110,96 @@ pub fn get_project_type(
         }
         "Demo" => {
             info!("Create a STM32 Demo project...");
-            let demo_list = template_config.get_demo_list();
-            let demo_list = demo_list.iter().map(|s| s.as_str()).collect();
+            let demo_list = available_demos(template_config, chip_info)?;
+            let width = demo_list.iter().map(|d| d.len()).max().unwrap_or(0);
+            let labels: Vec<String> = demo_list
+                .iter()
+                .map(|d| demos::demo_label(template_config, d, width))
+                .collect();
             // chooce a demo for the project
-            let demo_name = interactive::select(&demo_list, "🤷 Choose a demo", None)?;
-            ProjectType::DemoProject((&demo_name).clone())
+            let index = interactive::select_page(&labels, "🤷 Choose a demo")?;
+            ProjectType::DemoProject(demo_list[index].clone())
         }
         _ => {
             bail!("Invalid project type selected!");
         }
     };
-    Ok(project_type)
+    Ok(Some(project_type))
+}
+
+/// The demos the part can run, the other ones are listed with the reasons why not.
+/// Without a part every demo is available.
+fn available_demos(template_config: &Config, chip_info: Option<&ChipInfo>) -> Result<Vec<String>> {
+    let Some(chip_info) = chip_info else {
+        let mut demos = template_config.get_demo_list();
+        demos.sort();
+        return Ok(demos);
+    };
+    let (available, unavailable) = demos::demos_for_part(template_config, chip_info);
+    for (demo, reasons) in unavailable.iter() {
+        info!(
+            "{}",
+            style(format!(
+                "Demo `{}` is not available for {}: {}",
+                demo,
+                chip_info.cpn,
+                reasons.join(", ")
+            ))
+            .dim()
+        );
+    }
+    if available.is_empty() {
+        bail!(
+            "No demo of the template is available for {}!",
+            chip_info.cpn
+        );
+    }
+    for demo in available.iter() {
+        demos::warn_unverified_requirements(template_config, demo, chip_info);
+    }
+    Ok(available)
+}
+
+/// Resolve the project type from the arguments and the provided values
+/// `project_type` and `demo`, without any prompt
+fn get_project_type_silent(
+    user_parsed_input: &UserParsedInput,
+    template_config: &Config,
+    chip_info: Option<&ChipInfo>,
+    missing: &mut MissingValues,
+) -> Result<Option<ProjectType>> {
+    let project_type = user_parsed_input
+        .project_type()
+        .or_else(|| user_parsed_input.template_value_str("project_type"));
+    let demo_name = user_parsed_input
+        .demo_name()
+        .or_else(|| user_parsed_input.template_value_str("demo"));
+    match (project_type, demo_name) {
+        (Some("bsp"), _) => Ok(Some(ProjectType::BSPProject)),
+        (Some("empty"), _) => Ok(Some(ProjectType::EmptyProject)),
+        (None | Some("demo"), Some(demo_name)) => {
+            if let Some(chip_info) = chip_info {
+                demos::check_demo(template_config, demo_name, chip_info)?;
+            }
+            Ok(Some(ProjectType::DemoProject(demo_name.to_string())))
+        }
+        (Some("demo"), None) => {
+            missing.add(
+                "demo",
+                format!(
+                    "no demo is given, use --demo with one of {}",
+                    available_demos(template_config, chip_info)?.join(", ")
+                ),
+            );
+            Ok(None)
+        }
+        (Some(s), _) => bail!("Invalid project type: {}", s),
+        (None, None) => {
+            missing.add(
+                "project_type",
+                "no project type is given, use --type with empty, bsp or demo",
+            );
+            Ok(None)
+        }
+    }
 }
@@ -7,8 +7,12 @@ use std::{
 };
 
 use crate::absolute_path::AbsolutePathExt;
+use crate::template_cache::CacheMode;
+use crate::template_variables::load_template_values_file;
+use anyhow::Result;
 use console::style;
 
+use crate::stm32_device::clock::ClockRequest;
 use crate::AppArgs;
 use log::warn;
 
@@ -26,14 +30,19 @@ pub struct UserParsedInput {
     project_type: Option<String>,
     demo_name: Option<String>,
 
+    clock: ClockRequest,
+    multicore: bool,
+    soft_float: bool,
+    allow_nrnd: bool,
+
     // all values that user defined through:
-    // 1. environment variables
-    // 2. configuration file
-    // 3. cli arguments --define
+    // 1. values file --values-file
+    // 2. cli arguments --define
     template_values: HashMap<String, toml::Value>,
 
     overwrite: bool,
     verbose: bool,
+    silent: bool,
     //TODO:
     // 1. This structure should be used instead of args
     // 2. This struct can contains internally args and app_config to not confuse
@@ -46,7 +55,7 @@ impl UserParsedInput {
     /// # Panics
     /// This function assume that Args and AppConfig are verified earlier and are logically correct
     /// For example if both `--git` and `--path` are set this function will panic
-    pub fn try_from_args(args: &AppArgs) -> Self {
+    pub fn try_from_args(args: &AppArgs) -> Result<Self> {
         let destination = args
             .destination
             .as_ref()
@@ -61,7 +70,11 @@ impl UserParsedInput {
 
         let demo_name = args.demo_name.clone();
 
-        let mut default_values = HashMap::new();
+        // values of `--define` take precedence over the ones of `--values-file`
+        let mut default_values = match &args.template_values_file {
+            Some(path) => load_template_values_file(Path::new(path))?,
+            None => HashMap::new(),
+        };
         for item in args.define.iter() {
             if let Some((k, v)) = item.split_once('=') {
                 default_values.insert(k.to_string(), toml::Value::String(v.to_string()));
@@ -80,33 +93,44 @@ impl UserParsedInput {
                 ssh_identity,
                 args.gitconfig.clone(),
                 args.skip_submodules,
+                CacheMode::from_flags(args.offline, args.refresh),
             );
-            return Self {
+            return Ok(Self {
                 name: args.name.clone(),
                 chip_pn: args.chip_pn.clone(),
                 template_location: git_user_in.into(),
                 template_values: default_values,
                 overwrite: args.overwrite,
                 verbose: args.verbose,
+                silent: args.silent,
                 destination,
                 project_type,
                 demo_name,
-            };
+                clock: ClockRequest::from(&args.clock),
+                multicore: args.multicore,
+                soft_float: args.soft_float,
+                allow_nrnd: args.allow_nrnd,
+            });
         }
 
         // --path
         if let Some(path) = args.template_path.path() {
-            return Self {
+            return Ok(Self {
                 name: args.name.clone(),
                 chip_pn: args.chip_pn.clone(),
                 template_location: path.as_ref().into(),
                 template_values: default_values,
                 overwrite: args.overwrite,
                 verbose: args.verbose,
+                silent: args.silent,
                 destination,
                 project_type,
                 demo_name,
-            };
+                clock: ClockRequest::from(&args.clock),
+                multicore: args.multicore,
+                soft_float: args.soft_float,
+                allow_nrnd: args.allow_nrnd,
+            });
         }
 
         // If auto path is inputed, to check git short, local path and git full path
@@ -134,6 +158,7 @@ impl UserParsedInput {
                 ssh_identity,
                 args.gitconfig.clone(),
                 args.skip_submodules,
+                CacheMode::from_flags(args.offline, args.refresh),
             );
             TemplateLocation::from(git_user_in)
         });
@@ -153,17 +178,22 @@ impl UserParsedInput {
             location_msg
         );
 
-        Self {
+        Ok(Self {
             name: args.name.clone(),
             chip_pn: args.chip_pn.clone(),
             template_location: temp_location,
             template_values: default_values,
             overwrite: args.overwrite,
             verbose: args.verbose,
+            silent: args.silent,
             destination,
             project_type,
             demo_name,
-        }
+            clock: ClockRequest::from(&args.clock),
+            multicore: args.multicore,
+            soft_float: args.soft_float,
+            allow_nrnd: args.allow_nrnd,
+        })
     }
 
     pub fn name(&self) -> Option<&str> {
@@ -190,6 +220,16 @@ impl UserParsedInput {
         self.verbose
     }
 
+    /// In silent mode nothing is prompted, missing values are reported as an error
+    pub const fn is_silent(&self) -> bool {
+        self.silent
+    }
+
+    /// Get a provided string value, for values which are not template placeholders
+    pub fn template_value_str(&self, key: &str) -> Option<&str> {
+        self.template_values.get(key).and_then(|v| v.as_str())
+    }
+
     pub fn destination(&self) -> &Path {
         self.destination.as_path()
     }
@@ -200,6 +240,23 @@ impl UserParsedInput {
     pub fn demo_name(&self) -> Option<&str> {
         self.demo_name.as_deref()
     }
+
+    pub const fn clock(&self) -> &ClockRequest {
+        &self.clock
+    }
+
+    pub const fn multicore(&self) -> bool {
+        self.multicore
+    }
+
+    pub const fn soft_float(&self) -> bool {
+        self.soft_float
+    }
+
+    /// Parts which are NRND, obsolete or in preview are used without a confirmation
+    pub const fn allow_nrnd(&self) -> bool {
+        self.allow_nrnd
+    }
 }
 
 /// favorite can be in form with abbreviation what means that input is git repository
@@ -234,6 +291,7 @@ pub struct GitUserInput {
     identity: Option<PathBuf>,
     gitconfig: Option<PathBuf>,
     pub skip_submodules: bool,
+    cache_mode: CacheMode,
 }
 
 impl GitUserInput {
@@ -246,6 +304,7 @@ impl GitUserInput {
         identity: Option<PathBuf>,
         gitconfig: Option<PathBuf>,
         skip_submodules: bool,
+        cache_mode: CacheMode,
     ) -> Self {
         Self {
             url: url.as_ref().to_owned(),
@@ -255,6 +314,7 @@ impl GitUserInput {
             identity,
             gitconfig,
             skip_submodules,
+            cache_mode,
         }
     }
 
@@ -268,6 +328,7 @@ impl GitUserInput {
             args.ssh_identity.clone(),
             args.gitconfig.clone(),
             args.skip_submodules,
+            CacheMode::from_flags(args.offline, args.refresh),
         )
     }
 
@@ -294,6 +355,19 @@ impl GitUserInput {
     pub fn gitconfig(&self) -> Option<&Path> {
         self.gitconfig.as_deref()
     }
+
+    pub fn cache_mode(&self) -> CacheMode {
+        self.cache_mode
+    }
+
+    /// Name of the checked out ref, to tell apart the cached checkouts of a repository
+    pub fn git_ref(&self) -> &str {
+        self.revision
+            .as_deref()
+            .or(self.tag.as_deref())
+            .or(self.branch.as_deref())
+            .unwrap_or("HEAD")
+    }
 }
 
 // Distinguish between plain copy and clone
@@ -96,15 +96,15 @@ pub fn clone_git_template_into_temp(
         // Match git config file item
         // [url "https://github.com/"]
         // insteadOf = gh:
+        let re = Regex::new("url.(.+).insteadof").unwrap();
         while let Some(entry) = entries.next() {
             let entry = entry.unwrap();
-            let re = Regex::new("url.(.+).insteadof").unwrap();
             let cap = re.captures(entry.name().unwrap());
             if let Some(item) = cap {
                 let insteadof_value = entry.value().unwrap();
                 let insteadof_url = item.get(1).unwrap().as_str();
-                if url.starts_with(&insteadof_value) {
-                    url = insteadof_url.to_owned() + url.strip_prefix(&insteadof_value).unwrap();
+                if url.starts_with(insteadof_value) {
+                    url = insteadof_url.to_owned() + url.strip_prefix(insteadof_value).unwrap();
                     info!("🔧 gitconfig 'insteadOf' lead to this url: {}", url);
                 }
             }
@@ -152,7 +152,7 @@ pub fn clone_git_template_into_temp(
     builder.fetch_options(fetch_options);
 
     let repository = builder
-        .clone(&url, &git_clone_dir.path())
+        .clone(&url, git_clone_dir.path())
         .context("Please check if the Git user / repository exists.")?;
 
     if let Some(tag_or_revision) = tag_or_revision {
@@ -189,6 +189,21 @@ pub fn clone_git_template_into_temp(
     Ok(git_clone_dir)
 }
 
+/// Copy a directory with all of its content, `dst` is created if needed
+pub fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let dst_path = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dst_path)?;
+        } else {
+            fs::copy(entry.path(), dst_path)?;
+        }
+    }
+    Ok(())
+}
+
 /// remove context of repository by removing `.git` from filesystem
 pub fn remove_history(project_dir: &Path) -> Result<()> {
     let git_dir = project_dir.join(".git");
@@ -23,16 +23,16 @@ fn it_can_use_a_plain_folder() {
                 .from_utf8(),
         );
 
-    assert_eq!(dir.exists("foobar-project"), true);
-    assert_eq!(dir.exists("foobar-project/.git"), false);
-    assert_eq!(dir.exists("foobar-project/Cargo.toml"), true);
-    assert_eq!(dir.exists("foobar-project/memory.x"), true);
-    assert_eq!(dir.exists("foobar-project/build.rs"), true);
-    assert_eq!(dir.exists("foobar-project/src/main.rs"), true);
-    assert_eq!(dir.exists("foobar-project/build.rs"), true);
-    assert_eq!(dir.exists("foobar-project/.cargo/config.toml"), true);
-    assert_eq!(dir.exists("foobar-project/stm32bs.toml"), false);
-    assert_eq!(dir.exists("foobar-project/.stm32bs.toml"), true);
+    assert!(dir.exists("foobar-project"));
+    assert!(!dir.exists("foobar-project/.git"));
+    assert!(dir.exists("foobar-project/Cargo.toml"));
+    assert!(dir.exists("foobar-project/memory.x"));
+    assert!(dir.exists("foobar-project/build.rs"));
+    assert!(dir.exists("foobar-project/src/main.rs"));
+    assert!(dir.exists("foobar-project/build.rs"));
+    assert!(dir.exists("foobar-project/.cargo/config.toml"));
+    assert!(!dir.exists("foobar-project/stm32bs.toml"));
+    assert!(dir.exists("foobar-project/.stm32bs.toml"));
 }
 
 #[test]
@@ -51,16 +51,16 @@ fn it_can_use_a_specified_path() {
         .success()
         .stdout(predicates::str::contains("Done!").from_utf8());
 
-    assert_eq!(dir.exists("foobar-project"), true);
-    assert_eq!(dir.exists("foobar-project/.git"), false);
-    assert_eq!(dir.exists("foobar-project/Cargo.toml"), true);
-    assert_eq!(dir.exists("foobar-project/memory.x"), true);
-    assert_eq!(dir.exists("foobar-project/build.rs"), true);
-    assert_eq!(dir.exists("foobar-project/src/main.rs"), true);
-    assert_eq!(dir.exists("foobar-project/build.rs"), true);
-    assert_eq!(dir.exists("foobar-project/.cargo/config.toml"), true);
-    assert_eq!(dir.exists("foobar-project/stm32bs.toml"), false);
-    assert_eq!(dir.exists("foobar-project/.stm32bs.toml"), true);
+    assert!(dir.exists("foobar-project"));
+    assert!(!dir.exists("foobar-project/.git"));
+    assert!(dir.exists("foobar-project/Cargo.toml"));
+    assert!(dir.exists("foobar-project/memory.x"));
+    assert!(dir.exists("foobar-project/build.rs"));
+    assert!(dir.exists("foobar-project/src/main.rs"));
+    assert!(dir.exists("foobar-project/build.rs"));
+    assert!(dir.exists("foobar-project/.cargo/config.toml"));
+    assert!(!dir.exists("foobar-project/stm32bs.toml"));
+    assert!(dir.exists("foobar-project/.stm32bs.toml"));
 }
 
 #[test]
@@ -191,6 +191,315 @@ extern crate {{pac_name}};
     assert!(file.contains("stm32g0"));
 }
 
+#[test]
+fn it_renders_the_memory_regions() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "memory.x",
+            indoc! {r#"
+                MEMORY
+                {
+                  FLASH : ORIGIN = {{flash_origin}}, LENGTH = {{flash_size}}K
+                  RAM : ORIGIN = {{ram_origin}}, LENGTH = {{ram_size}}K
+                {%- for region in memory_regions %}
+                  /* {{region.name}} {{region.kind}} {{region.origin}} {{region.size}}K {{region.core}} */
+                {%- endfor %}
+                }
+            "#},
+        )
+        .init_git()
+        .build();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32H745ZIT6")
+        .arg_type("empty")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    let file = dir.read("foobar-project/memory.x");
+    assert!(file.contains("FLASH : ORIGIN = 0x08000000, LENGTH = 1024K"));
+    assert!(file.contains("RAM : ORIGIN = 0x24000000, LENGTH = 512K"));
+    assert!(file.contains("/* DTCM ram 0x20000000 128K CM7 */"));
+    assert!(file.contains("/* SRAM4 ram 0x38000000 64K  */"));
+}
+
+#[test]
+fn it_renders_the_clock_tree() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "src/main.rs",
+            indoc! {r#"
+                // {{clock.source}} {{clock.source_hz}} -> {{clock.sysclk_hz}}
+                // M={{clock.pll.m}} N={{clock.pll.n}} P={{clock.pll.p}} Q={{clock.pll.q}}
+                // latency={{clock.flash_latency}} scale={{clock.voltage_scale}}
+            "#},
+        )
+        .init_git()
+        .build();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32F407VGT6")
+        .arg_type("empty")
+        .arg("--hse")
+        .arg("8MHz")
+        .arg("--pll-q")
+        .arg("48MHz")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    let file = dir.read("foobar-project/src/main.rs");
+    assert!(file.contains("// HSE 8000000 -> 168000000"));
+    assert!(file.contains("// M=4 N=168 P=2 Q=7"));
+    assert!(file.contains("// latency=5 scale=1"));
+}
+
+#[test]
+fn it_generates_memory_x_when_the_template_opts_in() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                generate_memory_x = true
+            "#},
+        )
+        .init_git()
+        .build();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32F407VGT6")
+        .arg_type("empty")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    let file = dir.read("foobar-project/memory.x");
+    assert!(file.contains("FLASH  : ORIGIN = 0x08000000, LENGTH = 1024K"));
+    assert!(file.contains("RAM    : ORIGIN = 0x20000000, LENGTH = 128K"));
+    assert!(file.contains("CCMRAM : ORIGIN = 0x10000000, LENGTH = 64K"));
+    assert!(file.contains("} > CCMRAM"));
+}
+
+#[test]
+fn it_generates_a_crate_per_core_with_multicore() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "src/main.rs",
+            indoc! {r#"
+                // {{core_tag}} {{target}} multicore={{multicore}} {{pac_feature}}
+                {%- for region in ipc_regions %}
+                // {{region.name}} {{region.section}} {{region.origin}}
+                {%- endfor %}
+            "#},
+        )
+        .init_git()
+        .build();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32H745ZIT6")
+        .arg_type("empty")
+        .arg("--multicore")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    let manifest = dir.read("foobar-project/Cargo.toml");
+    assert!(manifest.contains("[workspace]"));
+    assert!(manifest.contains(r#"members = ["cm7", "cm4"]"#));
+    assert!(dir
+        .read("foobar-project/cm4/Cargo.toml")
+        .contains(r#"name = "foobar-project-cm4""#));
+    let main_rs = dir.read("foobar-project/cm4/src/main.rs");
+    assert!(main_rs.contains("// CM4 thumbv7em-none-eabihf multicore=true stm32h745cm4"));
+    assert!(dir
+        .read("foobar-project/cm7/src/main.rs")
+        .contains("// CM7 thumbv7em-none-eabihf multicore=true stm32h745cm7"));
+    // the cores share the target, the workspace builds from its root too
+    assert_eq!(
+        dir.read("foobar-project/.cargo/config.toml"),
+        "[build]\ntarget = \"thumbv7em-none-eabihf\"\n"
+    );
+    assert!(main_rs.contains("// SRAM4 .sram4 0x38000000"));
+    assert!(dir
+        .read("foobar-project/cm7/memory.x")
+        .contains("/* DTCM */"));
+    assert!(dir
+        .read("foobar-project/cm4/memory.x")
+        .contains("/* SRAM1 */"));
+}
+
+#[test]
+fn it_refuses_multicore_for_a_single_core_part() {
+    let template = tempdir().init_default_template().build();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32F407VGT6")
+        .arg_type("empty")
+        .arg("--multicore")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("has a single core").from_utf8());
+}
+
+#[test]
+fn it_uses_the_soft_float_target_on_request() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file("src/main.rs", "// target={{target}}")
+        .init_git()
+        .build();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("hard-float")
+        .arg_chip("STM32F407VGT6")
+        .arg_type("empty")
+        .current_dir(dir.path())
+        .assert()
+        .success();
+    binary()
+        .arg_git(template.path())
+        .arg_name("soft-float")
+        .arg_chip("STM32F407VGT6")
+        .arg_type("empty")
+        .arg("--soft-float")
+        .current_dir(dir.path())
+        .assert()
+        .success();
+
+    assert!(dir
+        .read("hard-float/src/main.rs")
+        .contains("// target=thumbv7em-none-eabihf"));
+    assert_eq!(
+        dir.read("soft-float/src/main.rs").trim_end(),
+        "// target=thumbv7em-none-eabi"
+    );
+}
+
+#[test]
+fn it_renders_the_details_of_the_part() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "src/main.rs",
+            "{{chip_description}}|{{chip_status}}|{{chip_package}}|{{fpu}}|{{co_type}}|{{co_freq}}",
+        )
+        .init_git()
+        .build();
+    let db = sqlite::open(template.path().join("stm32bs.db")).unwrap();
+    db.execute(
+        "
+        ALTER TABLE resource ADD COLUMN description TEXT;
+        ALTER TABLE resource ADD COLUMN status TEXT;
+        ALTER TABLE resource ADD COLUMN co_type TEXT;
+        ALTER TABLE resource ADD COLUMN co_freq INTEGER;
+        UPDATE resource SET description = 'Dual core MCU', status = 'NRND',
+            co_type = 'Cortex-M4', co_freq = 240 WHERE refname = 'STM32H745ZITx';
+        ",
+    )
+    .unwrap();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_path(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32H745ZIT6")
+        .arg_type("empty")
+        .arg("--verbose")
+        .arg("--allow-nrnd")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("Status:    NRND")
+                .and(predicates::str::contains("Package:   LQFP144"))
+                .and(predicates::str::contains("Coprocessor: Cortex-M4 240 MHz"))
+                .from_utf8(),
+        );
+
+    assert_eq!(
+        dir.read("foobar-project/src/main.rs").as_str(),
+        "Dual core MCU|NRND|LQFP144|double|Cortex-M4|240"
+    );
+}
+
+#[test]
+fn it_decodes_the_part_number_for_the_template() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file("src/main.rs", "{{line}} {{pin_count}} {{temp_range}}")
+        .init_git()
+        .build();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    assert_eq!(
+        dir.read("foobar-project/src/main.rs"),
+        "G071 48 -40°C to 85°C"
+    );
+}
+
+#[test]
+fn it_refuses_a_part_number_with_a_wrong_code() {
+    let template = tempdir().init_default_template().build();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CZT6")
+        .arg_type("empty")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("flash code 'Z' does not exist for STM32G0").from_utf8());
+}
+
 #[test]
 fn short_commands_work() {
     let template = tempdir().init_default_template().build();
@@ -0,0 +1,274 @@
+use crate::helpers::prelude::*;
+use crate::helpers::project::Project;
+
+#[test]
+fn it_can_build_a_bsp_project() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "src/main.rs",
+            indoc! {r#"
+                {% if bsp %}mod bsp;{% endif %}
+                fn main() {}
+            "#},
+        )
+        .init_git()
+        .build();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("bsp")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    assert!(dir.read("foobar-project/src/main.rs").contains("mod bsp;"));
+    assert!(dir
+        .read("foobar-project/src/bsp/mod.rs")
+        .contains("pub use stm32g0 as pac;"));
+    assert!(dir.exists("foobar-project/src/bsp/pins.rs"));
+
+    let project_config = dir.read("foobar-project/.stm32bs.toml");
+    assert!(project_config.contains(r#"project_type = "Project with BSP""#));
+    assert!(project_config.contains(r#"chip = "STM32G071CBT6TR""#));
+    assert!(project_config.contains("[pinmap]"));
+}
+
+#[test]
+fn empty_project_has_no_bsp() {
+    let template = tempdir().with_default_manifest().init_git().build();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .current_dir(dir.path())
+        .assert()
+        .success();
+
+    assert!(!dir.exists("foobar-project/src/bsp"));
+    assert!(dir
+        .read("foobar-project/.stm32bs.toml")
+        .contains(r#"chip = "STM32G071CBT6TR""#));
+}
+
+fn generate_project(template: &Project, dir: &Project, project_type: &str) {
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type(project_type)
+        .current_dir(dir.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn it_updates_the_bsp_of_a_project() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let dir = tempdir().build();
+    generate_project(&template, &dir, "bsp");
+
+    let config_file = dir.path().join("foobar-project/.stm32bs.toml");
+    let config = std::fs::read_to_string(&config_file).unwrap();
+    std::fs::write(
+        &config_file,
+        config.replace("[pinmap]", "[pinmap]\nLED = \"PA5\""),
+    )
+    .unwrap();
+
+    binary()
+        .arg("update")
+        .arg_path(template.path())
+        .current_dir(dir.path().join("foobar-project/src"))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("BSP updated").from_utf8());
+
+    assert!(dir.read("foobar-project/src/bsp/pins.rs").contains("LED"));
+}
+
+#[test]
+fn it_adds_a_bsp_to_an_empty_project() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let dir = tempdir().build();
+    generate_project(&template, &dir, "empty");
+
+    binary()
+        .arg("update")
+        .arg("--project-dir")
+        .arg(dir.path().join("foobar-project"))
+        .arg_path(template.path())
+        .current_dir(dir.path())
+        .assert()
+        .failure();
+
+    binary()
+        .arg("bsp")
+        .arg("--project-dir")
+        .arg(dir.path().join("foobar-project"))
+        .arg_path(template.path())
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("BSP updated").from_utf8());
+
+    assert!(dir.exists("foobar-project/src/bsp/mod.rs"));
+    assert!(dir
+        .read("foobar-project/.stm32bs.toml")
+        .contains(r#"project_type = "Project with BSP""#));
+}
+
+#[test]
+fn it_checks_the_project_config() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let dir = tempdir().build();
+    generate_project(&template, &dir, "bsp");
+
+    binary()
+        .arg("check")
+        .arg_path(template.path())
+        .current_dir(dir.path().join("foobar-project"))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("is valid").from_utf8());
+
+    let config_file = dir.path().join("foobar-project/.stm32bs.toml");
+    let config = std::fs::read_to_string(&config_file).unwrap();
+    std::fs::write(
+        &config_file,
+        config.replace("[pinmap]", "[pinmap]\nLED = \"PZ5\""),
+    )
+    .unwrap();
+
+    binary()
+        .arg("check")
+        .arg_path(template.path())
+        .current_dir(dir.path().join("foobar-project"))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("PZ5").from_utf8());
+}
+
+#[test]
+fn a_bare_invocation_generates_inside_a_project() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let dir = tempdir().build();
+    generate_project(&template, &dir, "bsp");
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("nested-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .current_dir(dir.path().join("foobar-project"))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    assert!(dir.exists("foobar-project/nested-project/Cargo.toml"));
+}
+
+#[test]
+fn it_checks_the_pinmap_against_the_package() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let db = sqlite::open(template.path().join("stm32bs.db")).unwrap();
+    db.execute(
+        "
+        CREATE TABLE pin (refname TEXT, pin TEXT, position TEXT);
+        CREATE TABLE pin_signal (refname TEXT, pin TEXT, signal TEXT, af INTEGER);
+        INSERT INTO pin VALUES ('STM32G071CBTx', 'PA2', '12'), ('STM32G071CBTx', 'PA5', '15');
+        INSERT INTO pin_signal VALUES ('STM32G071CBTx', 'PA2', 'USART2_TX', 1);
+        ",
+    )
+    .unwrap();
+    let dir = tempdir().build();
+    generate_project(&template, &dir, "bsp");
+
+    let config_file = dir.path().join("foobar-project/.stm32bs.toml");
+    let config = std::fs::read_to_string(&config_file).unwrap();
+    std::fs::write(
+        &config_file,
+        config.replace(
+            "[pinmap]",
+            "[pinmap]\nLED = \"PA5\"\nTX = { pin = \"PA2\", signal = \"USART2_TX\" }",
+        ),
+    )
+    .unwrap();
+    binary()
+        .arg("update")
+        .arg_path(template.path())
+        .current_dir(dir.path().join("foobar-project"))
+        .assert()
+        .success();
+    // the AF number is taken from the database
+    assert!(dir
+        .read("foobar-project/src/bsp/pins.rs")
+        .contains("dp.GPIOA.afrl()"));
+
+    std::fs::write(
+        &config_file,
+        config.replace(
+            "[pinmap]",
+            "[pinmap]\nLED = \"PB9\"\nTX = { pin = \"PA5\", signal = \"USART2_TX\" }",
+        ),
+    )
+    .unwrap();
+    binary()
+        .arg("check")
+        .arg_path(template.path())
+        .current_dir(dir.path().join("foobar-project"))
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains("PB9 is not available in the package")
+                .and(predicates::str::contains(
+                    "signal USART2_TX is not available on PA5, possible pins: PA2",
+                ))
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_checks_the_peripherals_against_the_part() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let db = sqlite::open(template.path().join("stm32bs.db")).unwrap();
+    db.execute(
+        "
+        CREATE TABLE peripheral (refname TEXT, instance TEXT, name TEXT, version TEXT);
+        INSERT INTO peripheral VALUES ('STM32G071CBTx', 'USART1', 'USART', 'sci3_v1_1_Cube'),
+            ('STM32G071CBTx', 'USART2', 'USART', 'sci3_v1_1_Cube');
+        ",
+    )
+    .unwrap();
+    let dir = tempdir().build();
+    generate_project(&template, &dir, "bsp");
+
+    let config_file = dir.path().join("foobar-project/.stm32bs.toml");
+    let config = std::fs::read_to_string(&config_file).unwrap();
+    std::fs::write(
+        &config_file,
+        config.replace("[peripheral]", "[peripheral]\nUSART3 = true"),
+    )
+    .unwrap();
+    binary()
+        .arg("check")
+        .arg_path(template.path())
+        .current_dir(dir.path().join("foobar-project"))
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains(
+                "STM32G071CBTx has no USART3, available instances: USART1, USART2",
+            )
+            .from_utf8(),
+        );
+}
@@ -0,0 +1,223 @@
+use crate::helpers::prelude::*;
+
+fn template() -> crate::helpers::project::Project {
+    tempdir().with_default_manifest().init_git().build()
+}
+
+#[test]
+fn it_lists_the_parts_matching_the_filters() {
+    let template = template();
+
+    binary()
+        .arg("chips")
+        .arg("G071")
+        .arg("--family")
+        .arg("g0")
+        .arg("--min-flash")
+        .arg("128")
+        .arg("--package")
+        .arg("LQFP48")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("STM32G071CBT6TR")
+                .and(predicates::str::contains("STM32G071CBTx"))
+                .and(predicates::str::contains("STM32G071RBT6").not())
+                .and(predicates::str::contains("STM32F407VGT6").not())
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_lists_the_parts_as_json() {
+    let template = template();
+
+    let output = binary()
+        .arg("chips")
+        .arg("--core")
+        .arg("M4")
+        .arg("--json")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let json_start = stdout.find('[').unwrap();
+    let list: Vec<serde_json::Value> = serde_json::from_str(&stdout[json_start..]).unwrap();
+    let cpns: Vec<&str> = list.iter().map(|e| e["cpn"].as_str().unwrap()).collect();
+    assert_eq!(cpns, vec!["STM32F407VGT6", "STM32H745ZIT6"]);
+    assert_eq!(list[0]["refname"], "STM32F407VGTx");
+}
+
+#[test]
+fn it_shows_the_information_of_a_part() {
+    let template = template();
+
+    binary()
+        .arg("info")
+        .arg("stm32h745zit6")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("Part number: STM32H745ZIT6")
+                .and(predicates::str::contains("Cortex-M7 + Cortex-M4"))
+                .and(predicates::str::contains("\r").not())
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_shows_the_information_of_a_part_as_toml_and_json() {
+    let template = template();
+
+    binary()
+        .arg("info")
+        .arg("STM32G071CBT6TR")
+        .arg("--format")
+        .arg("toml")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains(r#"refname = "STM32G071CBTx""#)
+                .and(predicates::str::contains(r#"core = "Cortex-M0+""#))
+                .and(predicates::str::contains(
+                    r#"target = "thumbv6m-none-eabi""#,
+                ))
+                .from_utf8(),
+        );
+
+    let output = binary()
+        .arg("info")
+        .arg("STM32F407VGT6")
+        .arg("--format")
+        .arg("json")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let info: serde_json::Value =
+        serde_json::from_str(&stdout[stdout.find('{').unwrap()..]).unwrap();
+    assert_eq!(info["family"], "STM32F4");
+    assert_eq!(info["flash"], 1024);
+    assert_eq!(info["core2"], serde_json::Value::Null);
+    assert_eq!(info["fpu"], "single");
+    assert_eq!(info["target"], "thumbv7em-none-eabihf");
+}
+
+#[test]
+fn it_fails_on_an_ambiguous_part() {
+    let template = template();
+
+    binary()
+        .arg("info")
+        .arg("G071")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains("3 parts match")
+                .and(predicates::str::contains("STM32G071RBT6"))
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_shows_the_memory_regions_of_a_part() {
+    let template = template();
+
+    binary()
+        .arg("info")
+        .arg("STM32H745ZIT6")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("AXISRAM:   0x24000000 512 KB")
+                .and(predicates::str::contains(
+                    "FLASH_CM4: 0x08100000 1024 KB (CM4)",
+                ))
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_rejects_a_database_newer_than_the_binary() {
+    let template = template();
+    let db = sqlite::open(template.path().join("stm32bs.db")).unwrap();
+    db.execute("INSERT OR REPLACE INTO meta VALUES ('schema_version', '9999');")
+        .unwrap();
+
+    binary()
+        .arg("info")
+        .arg("STM32G071CBT6TR")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains("schema version 9999")
+                .and(predicates::str::contains("pls update cargo-stm32bs"))
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_solves_the_clock_tree_of_a_part() {
+    let template = template();
+
+    binary()
+        .arg("clock")
+        .arg("STM32G071CBT6")
+        .arg("--sysclk")
+        .arg("16MHz")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("SYSCLK:        16 MHz from HSI")
+                .and(predicates::str::contains("Flash latency: 1 wait states"))
+                .from_utf8(),
+        );
+
+    binary()
+        .arg("clock")
+        .arg("STM32G071CBT6")
+        .arg("--sysclk")
+        .arg("100MHz")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("above the maximum").from_utf8());
+
+    // the PLL of STM32G0 gives at least 8 MHz
+    binary()
+        .arg("clock")
+        .arg("STM32G071CBT6")
+        .arg("--sysclk")
+        .arg("1MHz")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains(
+                "SYSCLK of 1000000 Hz can not be reached, the closest is 8000000 Hz",
+            )
+            .and(predicates::str::contains("HCLK:          8 MHz (AHB /1)"))
+            .from_utf8(),
+        );
+}
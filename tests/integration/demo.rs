@@ -102,3 +102,421 @@ fn demo_project_can_fill_placeholder() {
         "hello demo include var port = B"
     );
 }
+
+#[test]
+fn it_lists_the_demos() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                [demo.'hello']
+                [demo.'blink']
+            "#},
+        )
+        .init_git()
+        .build();
+
+    binary()
+        .arg("demos")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("blink\nhello\n").from_utf8());
+}
+
+#[test]
+fn demo_project_gets_the_oscillators_of_the_family() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                 [demo.'hello']
+            "#},
+        )
+        .file("demo/hello.rs", "HSI {{HSI_freq}} MSI {{MSI_freq}}")
+        .init_git()
+        .build();
+    let db = sqlite::open(template.path().join("stm32bs.db")).unwrap();
+    db.execute(
+        "
+        CREATE TABLE oscillator (family TEXT, name TEXT, frequency INTEGER);
+        INSERT INTO oscillator VALUES ('STM32U5', 'HSI', 16000000), ('STM32U5', 'MSI', 4000000);
+        ",
+    )
+    .unwrap();
+
+    let dir = tempdir().build();
+
+    binary()
+        .arg_path(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32U575ZIT6Q")
+        .arg_demo("hello")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    assert_eq!(
+        dir.read("foobar-project/src/main.rs").as_str(),
+        "HSI 16000000 MSI 4000000"
+    );
+}
+
+#[test]
+fn demo_project_offers_the_peripherals_of_the_part() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                [demo.'console']
+                uart = { type = "select", prompt = "UART of the console?", peripheral = "USART", default = "USART2" }
+                [demo.'modbus']
+                uart = { type = "select", prompt = "UART of the bus?", peripheral = "USART", choices = ["USART3", "USART4"] }
+            "#},
+        )
+        .file(
+            "demo/console.rs",
+            "{{uart}} of {{peripherals.USART.size}} IRQ {{peripherals.USART[1].irqs[0].number}}{% if peripheral_instances contains \"SPI1\" %} SPI1{% endif %}",
+        )
+        .file("demo/modbus.rs", "{{uart}}")
+        .init_git()
+        .build();
+    let db = sqlite::open(template.path().join("stm32bs.db")).unwrap();
+    db.execute(
+        "
+        CREATE TABLE peripheral (refname TEXT, instance TEXT, name TEXT, version TEXT,
+            bus TEXT, enable_register TEXT, enable_bit INTEGER);
+        CREATE TABLE peripheral_irq (refname TEXT, instance TEXT, irq TEXT, number INTEGER);
+        INSERT INTO peripheral VALUES
+            ('STM32G071CBTx', 'USART1', 'USART', 'sci3_v1_1_Cube', 'APB2', 'APBENR2', 14),
+            ('STM32G071CBTx', 'USART2', 'USART', 'sci3_v1_1_Cube', 'APB1', 'APBENR1', 17),
+            ('STM32G071CBTx', 'SPI1', 'SPI', 'spi2s1_v1_1_Cube', 'APB2', 'APBENR2', 12);
+        INSERT INTO peripheral_irq VALUES ('STM32G071CBTx', 'USART2', 'USART2_LPUART2', 28);
+        ",
+    )
+    .unwrap();
+
+    let dir = tempdir().build();
+    binary()
+        .arg_path(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_demo("console")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+    assert_eq!(
+        dir.read("foobar-project/src/main.rs").as_str(),
+        "USART2 of 2 IRQ 28 SPI1"
+    );
+
+    binary()
+        .arg_path(template.path())
+        .arg_name("modbus-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_demo("modbus")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains("STM32G071CBTx has no USART for placeholder `uart`")
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn demo_project_takes_any_peripheral_without_inventory() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                [demo.'console']
+                uart = { type = "select", prompt = "UART of the console?", peripheral = "USART" }
+            "#},
+        )
+        .file("demo/console.rs", "{{uart}}")
+        .init_git()
+        .build();
+
+    // the database of the template has no peripheral of the part
+    let dir = tempdir().build();
+    binary()
+        .arg_path(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_demo("console")
+        .arg_define("uart=USART3")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains(
+                "The database has no peripherals of STM32G071CBTx, placeholder `uart` takes any USART",
+            )
+            .and(predicates::str::contains("Done!"))
+            .from_utf8(),
+        );
+    assert_eq!(dir.read("foobar-project/src/main.rs").as_str(), "USART3");
+}
+
+fn template_with_requirements() -> crate::helpers::project::Project {
+    tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                [demo.'blink']
+                [demo.'dsp']
+                requires = { cores = ["CM4", "Cortex-M7"], min_flash = 512 }
+                [demo.'g0_only']
+                requires = { families = ["G0"] }
+            "#},
+        )
+        .file("demo/blink.rs", "blink")
+        .file("demo/dsp.rs", "dsp")
+        .file("demo/g0_only.rs", "g0")
+        .init_git()
+        .build()
+}
+
+#[test]
+fn it_refuses_a_demo_the_part_can_not_run() {
+    let template = template_with_requirements();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_demo("dsp")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains(
+                "Demo `dsp` is not available for STM32G071CBT6TR: the core is Cortex-M0+, \
+                 not CM4 or Cortex-M7, it has 128 KB of flash, 512 KB are needed",
+            )
+            .from_utf8(),
+        );
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32F407VGT6")
+        .arg_demo("dsp")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+    assert_eq!(dir.read("foobar-project/src/main.rs").as_str(), "dsp");
+}
+
+#[test]
+fn it_offers_only_the_demos_of_the_part_in_silent_mode() {
+    let template = template_with_requirements();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32F407VGT6")
+        .arg_type("demo")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stdout(
+            predicates::str::contains(
+                "Demo `g0_only` is not available for STM32F407VGT6: the family is STM32F4, not G0",
+            )
+            .from_utf8(),
+        )
+        .stderr(
+            predicates::str::contains("no demo is given, use --demo with one of blink, dsp\n")
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_lists_the_demos_with_their_description_and_filters() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                [demo.'blink']
+                description = "Blink the LED of the board"
+                difficulty = "beginner"
+                tags = ["gpio"]
+                hardware = ["LED on PA5"]
+                [demo.'uart_dma']
+                description = "Echo the characters of the UART with DMA"
+                difficulty = "advanced"
+                tags = ["uart", "dma"]
+                requires = { min_flash = 512 }
+                [demo.'hello']
+                [demo.'usb']
+                requires = { peripherals = ["usb"] }
+            "#},
+        )
+        .init_git()
+        .build();
+
+    binary()
+        .arg("demos")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains(indoc! {"
+                blink     beginner      Blink the LED of the board
+                          tags: gpio
+                          hardware: LED on PA5
+                hello
+                uart_dma  advanced      Echo the characters of the UART with DMA
+                          tags: uart, dma
+            "})
+            .from_utf8(),
+        );
+
+    binary()
+        .arg("demos")
+        .arg("--tag")
+        .arg("DMA")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("uart_dma")
+                .and(predicates::str::contains("blink").not())
+                .from_utf8(),
+        );
+
+    binary()
+        .arg("demos")
+        .arg("led")
+        .arg("--difficulty")
+        .arg("beginner")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("blink")
+                .and(predicates::str::contains("uart_dma").not())
+                .from_utf8(),
+        );
+
+    // STM32G071CBT6 has 128 KB of flash
+    binary()
+        .arg("demos")
+        .arg("--chip")
+        .arg("STM32G071CBT6")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("hello")
+                .and(predicates::str::contains("uart_dma").not())
+                .and(predicates::str::contains(
+                    "Demo `usb` needs USB, the database has no peripherals of STM32G071CBT6 \
+                    to check them",
+                ))
+                .from_utf8(),
+        );
+
+    binary()
+        .arg("demos")
+        .arg("--difficulty")
+        .arg("intermediate")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("No demo matches the filters").from_utf8());
+}
+
+#[test]
+fn it_merges_a_demo_directory_into_the_project() {
+    let template = tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                [demo.'display']
+                include = ["assets/logo.bmp"]
+                dependencies = { ssd1306 = "0.9", heapless = { version = "0.8", default-features = false } }
+            "#},
+        )
+        .file("demo/display/src/main.rs", "mod screen;\n// {{project-name}}")
+        .file("demo/display/src/screen.rs", "// screen of {{pn}}")
+        .file("demo/display/build.rs", "// build script of the demo")
+        .file("assets/logo.bmp", "BM")
+        .init_git()
+        .build();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_demo("display")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    assert_eq!(
+        dir.read("foobar-project/src/main.rs"),
+        "mod screen;\n// foobar-project"
+    );
+    assert_eq!(
+        dir.read("foobar-project/src/screen.rs"),
+        "// screen of STM32G071CBT6TR"
+    );
+    assert_eq!(
+        dir.read("foobar-project/build.rs"),
+        "// build script of the demo"
+    );
+    assert!(dir.exists("foobar-project/assets/logo.bmp"));
+    let cargo_toml = dir.read("foobar-project/Cargo.toml");
+    assert!(
+        cargo_toml.contains("[dependencies]\nheapless = { version = \"0.8\", default-features = false }\nssd1306 = \"0.9\"\n"),
+        "{cargo_toml}"
+    );
+}
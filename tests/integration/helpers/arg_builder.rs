@@ -42,6 +42,11 @@ impl CargoGenerateArgBuilder {
         self.arg("-d").arg(value)
     }
 
+    /// wrapper for `--values-file <file>` cli argument
+    pub fn arg_values_file(&mut self, file: impl AsRef<OsStr>) -> &mut Self {
+        self.arg("--values-file").arg(file)
+    }
+
     /// wrapper for `--branch <name>` cli argument
     pub fn arg_branch(&mut self, name: impl AsRef<OsStr>) -> &mut Self {
         self.arg("--branch").arg(name)
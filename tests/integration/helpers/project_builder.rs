@@ -8,7 +8,8 @@ use std::str;
 use crate::helpers::project::Project;
 use tempfile::TempDir;
 
-const TEMPLATE_PATH : &str = "../template";
+/// Tables and rows of the `stm32bs.db` of the test templates
+const TEMPLATE_DB: &str = include_str!("stm32bs.sql");
 
 pub struct ProjectBuilder {
     files: Vec<(String, String)>,
@@ -50,7 +51,8 @@ impl ProjectBuilder {
                 description = "A wonderful project"
                 version = ">=0.0.3"
             "#},
-        ).file(
+        )
+        .file(
             "Cargo.toml",
             indoc! {r#"
                 [package]
@@ -58,7 +60,8 @@ impl ProjectBuilder {
                 description = "A wonderful project"
                 version = "0.1.0"
             "#},
-        ).file(
+        )
+        .file(
             "build.rs",
             indoc! {r#"
                 [package]
@@ -66,7 +69,8 @@ impl ProjectBuilder {
                 description = "A wonderful project"
                 version = "0.1.0"
             "#},
-        ).file(
+        )
+        .file(
             "memory.x",
             indoc! {r#"
                 [package]
@@ -74,7 +78,8 @@ impl ProjectBuilder {
                 description = "A wonderful project"
                 version = "0.1.0"
             "#},
-        ).file(
+        )
+        .file(
             ".cargo/config.toml",
             indoc! {r#"
                 [package]
@@ -82,7 +87,8 @@ impl ProjectBuilder {
                 description = "A wonderful project"
                 version = "0.1.0"
             "#},
-        ).file(
+        )
+        .file(
             "src/main.rs",
             indoc! {r#"
                 [package]
@@ -161,10 +167,11 @@ impl ProjectBuilder {
     }
 
     pub fn build(self) -> Project {
-        use std::path::Path;
         let path = self.root.path();
 
-        fs::copy(Path::new(TEMPLATE_PATH).join("stm32bs.db"), path.join("stm32bs.db")).unwrap();
+        sqlite::open(path.join("stm32bs.db"))
+            .and_then(|db| db.execute(TEMPLATE_DB))
+            .expect("couldn't create the database of the template");
 
         for (file, contents) in self.files.iter() {
             let path = path.join(file);
@@ -0,0 +1,44 @@
+-- Database of the test templates, built by `ProjectBuilder::build`.
+-- Schema version 2: the tables of the first version with `meta` and `memory`.
+
+CREATE TABLE cpn (cpn TEXT, refname TEXT);
+INSERT INTO cpn VALUES('STM32G071CBT6TR','STM32G071CBTx');
+INSERT INTO cpn VALUES('STM32G071CBT6','STM32G071CBTx');
+INSERT INTO cpn VALUES('STM32G071RBT6','STM32G071RBTx');
+INSERT INTO cpn VALUES('STM32F407VGT6','STM32F407VGTx');
+INSERT INTO cpn VALUES('STM32U575ZIT6Q','STM32U575ZITxQ');
+INSERT INTO cpn VALUES('STM32H745ZIT6','STM32H745ZITx');
+
+CREATE TABLE pac_content (id INTEGER, pac_name TEXT, pac_ver TEXT, pac_feature TEXT);
+INSERT INTO pac_content VALUES(1,'stm32g0','0.15.1','stm32g071');
+INSERT INTO pac_content VALUES(2,'stm32f4','0.15.1','stm32f407');
+INSERT INTO pac_content VALUES(3,'stm32u5','0.15.1','stm32u575');
+INSERT INTO pac_content VALUES(4,'stm32h7','0.15.1','stm32h745cm7');
+
+CREATE TABLE resource (refname TEXT, family TEXT, core TEXT, core_second TEXT, frequency INTEGER, flash INTEGER, ram INTEGER, ccmram INTEGER, pac INTEGER);
+INSERT INTO resource VALUES('STM32G071CBTx','STM32G0','0+','',64,128,36,0,1);
+INSERT INTO resource VALUES('STM32G071RBTx','STM32G0','0+','',64,128,36,0,1);
+INSERT INTO resource VALUES('STM32F407VGTx','STM32F4','4','',168,1024,128,64,2);
+INSERT INTO resource VALUES('STM32U575ZITxQ','STM32U5','33','',160,2048,786,0,3);
+INSERT INTO resource VALUES('STM32H745ZITx','STM32H7','7','4',480,2048,1024,0,4);
+
+CREATE TABLE memory (refname TEXT, name TEXT, kind TEXT, origin INTEGER, size INTEGER, core TEXT);
+INSERT INTO memory VALUES('STM32G071CBTx','FLASH','flash',134217728,128,'');
+INSERT INTO memory VALUES('STM32G071CBTx','RAM','ram',536870912,36,'');
+INSERT INTO memory VALUES('STM32G071RBTx','FLASH','flash',134217728,128,'');
+INSERT INTO memory VALUES('STM32G071RBTx','RAM','ram',536870912,36,'');
+INSERT INTO memory VALUES('STM32F407VGTx','FLASH','flash',134217728,1024,'');
+INSERT INTO memory VALUES('STM32F407VGTx','RAM','ram',536870912,128,'');
+INSERT INTO memory VALUES('STM32F407VGTx','CCMRAM','ram',268435456,64,'');
+INSERT INTO memory VALUES('STM32U575ZITxQ','FLASH','flash',134217728,2048,'');
+INSERT INTO memory VALUES('STM32U575ZITxQ','RAM','ram',536870912,768,'');
+INSERT INTO memory VALUES('STM32U575ZITxQ','SRAM4','ram',671088640,16,'');
+INSERT INTO memory VALUES('STM32H745ZITx','FLASH','flash',134217728,1024,'CM7');
+INSERT INTO memory VALUES('STM32H745ZITx','FLASH_CM4','flash',135266304,1024,'CM4');
+INSERT INTO memory VALUES('STM32H745ZITx','AXISRAM','ram',603979776,512,'');
+INSERT INTO memory VALUES('STM32H745ZITx','DTCM','ram',536870912,128,'CM7');
+INSERT INTO memory VALUES('STM32H745ZITx','SRAM1','ram',805306368,128,'CM4');
+INSERT INTO memory VALUES('STM32H745ZITx','SRAM4','ram',939524096,64,'');
+
+CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT);
+INSERT INTO meta VALUES('schema_version','2');
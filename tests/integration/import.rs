@@ -0,0 +1,90 @@
+use crate::helpers::prelude::*;
+use std::fs;
+
+const FAMILIES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Families>
+  <Family Name="STM32G0">
+    <SubFamily Name="STM32G0x1">
+      <Mcu Name="STM32G031K(4-6-8)Tx" PackageName="LQFP32" RefName="STM32G031K8Tx" RPN="STM32G031K8">
+        <Core>Arm Cortex-M0+</Core>
+        <Frequency>64</Frequency>
+        <Ram>8</Ram>
+        <Flash>64</Flash>
+        <Temperature Max="85" Min="-40"/>
+      </Mcu>
+    </SubFamily>
+  </Family>
+</Families>
+"#;
+
+const MCU_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Mcu Family="STM32G0" RefName="STM32G031K(4-6-8)Tx" xmlns="http://mcd.rou.st.com/modules.php?name=mcu">
+  <Core>Arm Cortex-M0+</Core>
+  <IP InstanceName="USART2" Name="USART" Version="sci3_v1_1_Cube"/>
+  <Pin Name="PA2" Position="8" Type="I/O">
+    <Signal Name="USART2_TX"/>
+  </Pin>
+</Mcu>
+"#;
+
+#[test]
+fn it_imports_parts_from_the_open_pin_data() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let open_pin_data = tempdir().build();
+    let mcu_dir = open_pin_data.path().join("mcu");
+    fs::create_dir_all(&mcu_dir).unwrap();
+    fs::write(mcu_dir.join("families.xml"), FAMILIES_XML).unwrap();
+    fs::write(mcu_dir.join("STM32G031K(4-6-8)Tx.xml"), MCU_XML).unwrap();
+
+    binary()
+        .arg("import")
+        .arg(open_pin_data.path())
+        .arg("--database")
+        .arg(template.path().join("stm32bs.db"))
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("1 parts imported").from_utf8());
+
+    // the open pin data has no order codes, the part goes by its reference name
+    binary()
+        .arg("chips")
+        .arg("g031")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("STM32G031K8Tx")
+                .and(predicates::str::contains("STM32G031K8T6").not())
+                .from_utf8(),
+        );
+
+    binary()
+        .arg("info")
+        .arg("STM32G031K8Tx")
+        .arg("--format")
+        .arg("json")
+        .arg_path(template.path())
+        .current_dir(template.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("STM32G031K8Tx")
+                .and(predicates::str::contains("stm32g031"))
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_fails_without_the_open_pin_data() {
+    let dir = tempdir().build();
+
+    binary()
+        .arg("import")
+        .arg(dir.path())
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("`families.xml` is not found").from_utf8());
+}
@@ -2,11 +2,17 @@ mod helpers;
 
 // test modules go here
 mod basics;
+mod bsp;
+mod chips;
 mod demo;
 mod git;
 mod git_instead_of;
 #[cfg(e2e_tests_with_ssh_key)]
 mod git_over_ssh;
+mod import;
 mod project_name;
+mod silent;
+mod template_cache;
 mod template_config_file;
 mod template_filters;
+mod values_file;
@@ -0,0 +1,163 @@
+use crate::helpers::prelude::*;
+
+fn template_with_placeholders() -> crate::helpers::project::Project {
+    tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                [placeholders]
+                port = { type="string", prompt="Port of GPIO is used to LED, eg. B", regex = "^[a-fA-F]$"}
+                pin = { type="string", prompt="Pin of the LED", default = "5"}
+            "#},
+        )
+        .file(
+            "src/main.rs",
+            indoc! {r#"
+                port={{port}}
+                pin={{pin}}
+            "#},
+        )
+        .init_git()
+        .build()
+}
+
+#[test]
+fn it_uses_provided_values_and_defaults() {
+    let template = template_with_placeholders();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .arg_define("port=B")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    assert_eq!(dir.read("foobar-project/src/main.rs"), "port=B\npin=5\n");
+}
+
+#[test]
+fn it_lists_all_missing_values() {
+    let template = template_with_placeholders();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains("Silent mode, the following values are missing")
+                .and(predicates::str::contains("chip_pn"))
+                .and(predicates::str::contains("project_type"))
+                .and(predicates::str::contains("port"))
+                .and(predicates::str::contains("- pin:").not())
+                .from_utf8(),
+        );
+
+    assert!(!dir.path().join("foobar-project").exists());
+}
+
+#[test]
+fn it_fails_on_an_ambiguous_chip() {
+    let template = template_with_placeholders();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071")
+        .arg_type("empty")
+        .arg_define("port=B")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("chip_pn").from_utf8());
+}
+
+fn template_with_nrnd_part() -> crate::helpers::project::Project {
+    let template = template_with_placeholders();
+    let db = sqlite::open(template.path().join("stm32bs.db")).unwrap();
+    db.execute(
+        "
+        ALTER TABLE resource ADD COLUMN status TEXT;
+        UPDATE resource SET status = 'Active';
+        UPDATE resource SET status = 'NRND' WHERE refname = 'STM32G071CBTx';
+        INSERT INTO resource SELECT 'STM32G081CBTx', family, core, core_second, frequency,
+            flash, ram, ccmram, pac, 'Active' FROM resource WHERE refname = 'STM32G071CBTx';
+        INSERT INTO cpn VALUES ('STM32G081CBT6', 'STM32G081CBTx');
+        ",
+    )
+    .unwrap();
+    template
+}
+
+#[test]
+fn it_refuses_a_nrnd_part_without_allow_nrnd() {
+    let template = template_with_nrnd_part();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_path(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6")
+        .arg_type("empty")
+        .arg_define("port=B")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains("not recommended for new designs")
+                .and(predicates::str::contains("--allow-nrnd"))
+                .and(predicates::str::contains("STM32G081CBT6"))
+                .from_utf8(),
+        );
+
+    binary()
+        .arg_path(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6")
+        .arg_type("empty")
+        .arg_define("port=B")
+        .arg("--silent")
+        .arg("--allow-nrnd")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("Pin-compatible active parts: STM32G081CBT6")
+                .and(predicates::str::contains("Done!"))
+                .from_utf8(),
+        );
+}
+
+#[test]
+fn it_reports_a_wrong_code_of_the_chip() {
+    let template = template_with_placeholders();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBW6")
+        .arg_type("empty")
+        .arg_define("port=B")
+        .arg("--silent")
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("package code 'W' does not exist").from_utf8());
+}
@@ -0,0 +1,120 @@
+use crate::helpers::prelude::*;
+
+#[test]
+fn it_uses_the_cached_template_offline() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let template_url = format!("file://{}", template.path().display());
+    let home = tempdir().build();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(&template_url)
+        .arg_name("first-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .current_dir(dir.path())
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+    assert!(
+        home.path()
+            .join(".stm32bs/templates")
+            .read_dir()
+            .unwrap()
+            .count()
+            == 1
+    );
+
+    // the origin is gone, the cached template is still there
+    std::fs::remove_dir_all(template.path()).unwrap();
+    binary()
+        .arg_git(&template_url)
+        .arg_name("fallback-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .current_dir(dir.path())
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("Cannot fetch the template, the cached one is used")
+                .from_utf8(),
+        );
+    assert!(dir.exists("fallback-project/Cargo.toml"));
+
+    binary()
+        .arg_git(&template_url)
+        .arg_name("second-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .arg("--offline")
+        .current_dir(dir.path())
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Using the cached template").from_utf8());
+    assert!(dir.exists("second-project/Cargo.toml"));
+
+    binary()
+        .arg_git(&template_url)
+        .arg_name("third-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .arg("--refresh")
+        .current_dir(dir.path())
+        .env("HOME", home.path())
+        .assert()
+        .failure();
+}
+
+#[test]
+fn it_updates_the_cached_template() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let template_url = format!("file://{}", template.path().display());
+    let home = tempdir().build();
+    let dir = tempdir().build();
+    let generate = |name: &str| {
+        binary()
+            .arg_git(&template_url)
+            .arg_name(name)
+            .arg_chip("STM32G071CBT6TR")
+            .arg_type("empty")
+            .current_dir(dir.path())
+            .env("HOME", home.path())
+            .assert()
+            .success();
+    };
+
+    generate("first-project");
+    fs::write(template.path().join("build.rs"), "// updated\n").unwrap();
+    std::process::Command::new("git")
+        .args(["-c", "user.name=Foo Bar", "-c", "user.email=foo@bar.com"])
+        .args(["commit", "--all", "--no-gpg-sign", "--message", "update"])
+        .current_dir(template.path())
+        .assert()
+        .success();
+
+    // without a flag the template is fetched again, not taken from the cache
+    generate("second-project");
+    assert_eq!(dir.read("second-project/build.rs"), "// updated\n");
+}
+
+#[test]
+fn it_fails_offline_without_a_cached_template() {
+    let template = tempdir().with_default_manifest().init_git().build();
+    let home = tempdir().build();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(format!("file://{}", template.path().display()))
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .arg("--offline")
+        .current_dir(dir.path())
+        .env("HOME", home.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("is not cached yet").from_utf8());
+}
@@ -0,0 +1,102 @@
+use crate::helpers::prelude::*;
+
+fn template_with_values() -> crate::helpers::project::Project {
+    tempdir()
+        .with_default_manifest()
+        .file(
+            "stm32bs.toml",
+            indoc! {r#"
+                [template]
+                description = "A wonderful project"
+                version = ">=0.0.3"
+                [placeholders]
+                port = { type="string", prompt="Port of GPIO is used to LED, eg. B", regex = "^[a-fA-F]$"}
+            "#},
+        )
+        .file(
+            "src/main.rs",
+            indoc! {r#"
+                port={{port}}
+                pin={{pin | plus: 1}}
+                led={% if led %}on{% else %}off{% endif %}
+                list={{list | join: "+"}}
+            "#},
+        )
+        .init_git()
+        .build()
+}
+
+#[test]
+fn it_reads_a_values_table() {
+    let template = template_with_values();
+    let dir = tempdir()
+        .file(
+            "values.toml",
+            indoc! {r#"
+                [values]
+                port = "B"
+                pin = 5
+                led = true
+                list = ["a", "b"]
+            "#},
+        )
+        .build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .arg_values_file(dir.path().join("values.toml"))
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    assert_eq!(
+        dir.read("foobar-project/src/main.rs"),
+        "port=B\npin=6\nled=on\nlist=a+b\n"
+    );
+}
+
+#[test]
+fn it_reads_key_value_lines_and_prefers_define() {
+    let template = template_with_values();
+    let dir = tempdir()
+        .file("values.txt", "port=B\npin=5\nled=false\nlist=[\"a\"]\n")
+        .build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .arg_values_file(dir.path().join("values.txt"))
+        .arg_define("port=C")
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Done!").from_utf8());
+
+    assert_eq!(
+        dir.read("foobar-project/src/main.rs"),
+        "port=C\npin=6\nled=off\nlist=a\n"
+    );
+}
+
+#[test]
+fn it_fails_on_a_missing_values_file() {
+    let template = template_with_values();
+    let dir = tempdir().build();
+
+    binary()
+        .arg_git(template.path())
+        .arg_name("foobar-project")
+        .arg_chip("STM32G071CBT6TR")
+        .arg_type("empty")
+        .arg_values_file(dir.path().join("missing.toml"))
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Cannot read the values file").from_utf8());
+}